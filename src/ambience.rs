@@ -0,0 +1,117 @@
+use rodio::Source;
+use serde::Deserialize;
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+// Sonido de ambiente de un cuerpo: "rumble" (retumbo grave, para estrellas y lava),
+// "wind" (viento con ráfagas) o la ruta de un archivo de audio que se repite. Suena
+// a volume mientras la cámara está a menos de range (unidades de la escena) de la
+// superficie y se apaga con el cuadrado de la distancia más allá.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct AmbientConfig {
+    pub sound: String,
+    #[serde(default = "default_volume")]
+    pub volume: f32,
+    #[serde(default = "default_range")]
+    pub range: f32,
+}
+
+fn default_volume() -> f32 {
+    1.0
+}
+
+fn default_range() -> f32 {
+    2.0
+}
+
+// Sonidos que se generan en vez de leerse de un archivo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Synth {
+    Rumble,
+    Wind,
+}
+
+impl Synth {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rumble" => Some(Synth::Rumble),
+            "wind" => Some(Synth::Wind),
+            _ => None,
+        }
+    }
+}
+
+const SAMPLE_RATE: u32 = 44_100;
+
+// Ruido filtrado sin fin, en mono. El retumbo es ruido marrón (ruido blanco
+// integrado) que late despacio; el viento es ruido con un filtro pasabajos cuya
+// frecuencia y volumen suben y bajan con las ráfagas.
+pub struct SynthSource {
+    kind: Synth,
+    seed: u32,
+    filtered: f32,
+    time: f32,
+}
+
+impl SynthSource {
+    pub fn new(kind: Synth, seed: u32) -> Self {
+        SynthSource {
+            kind,
+            // El xorshift no sale nunca de cero
+            seed: seed | 1,
+            filtered: 0.0,
+            time: 0.0,
+        }
+    }
+
+    // Ruido blanco entre -1 y 1
+    fn white(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        self.seed as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
+
+impl Iterator for SynthSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let white = self.white();
+        self.time += 1.0 / SAMPLE_RATE as f32;
+        let t = self.time;
+
+        let sample = match self.kind {
+            Synth::Rumble => {
+                self.filtered = (self.filtered + white * 0.02) * 0.998;
+                let pulse = 0.75 + 0.25 * (TAU * 0.3 * t).sin();
+                self.filtered * 1.5 * pulse
+            }
+            Synth::Wind => {
+                let gust = 0.5 + 0.5 * (TAU * 0.13 * t).sin() * (TAU * 0.07 * t + 1.0).sin();
+                let cutoff = 0.01 + 0.05 * gust;
+                self.filtered += (white - self.filtered) * cutoff;
+                self.filtered * (0.5 + 2.0 * gust)
+            }
+        };
+        Some(sample.clamp(-1.0, 1.0))
+    }
+}
+
+impl Source for SynthSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
@@ -0,0 +1,99 @@
+use std::fmt::Write;
+use std::ops::Range;
+use std::str::FromStr;
+
+// Caracteres de menos a más tinta, para el estilo sin color
+const RAMP: &[u8] = b" .:-=+*#%@";
+// Un carácter de la terminal es más o menos el doble de alto que de ancho
+const CELL_ASPECT: f32 = 2.0;
+
+// Cómo se dibuja la imagen en la terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AsciiStyle {
+    // Un carácter según el brillo, sin color: sirve en cualquier terminal o en un archivo
+    Plain,
+    // Medio bloque con el píxel de arriba como tinta y el de abajo como fondo, en
+    // color de 24 bits: dos píxeles por carácter
+    Ansi,
+}
+
+impl AsciiStyle {
+    // Divisiones de cada carácter en vertical
+    fn pixels_per_row(self) -> usize {
+        match self {
+            AsciiStyle::Plain => 1,
+            AsciiStyle::Ansi => 2,
+        }
+    }
+}
+
+impl FromStr for AsciiStyle {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "plain" => Ok(AsciiStyle::Plain),
+            "ansi" => Ok(AsciiStyle::Ansi),
+            _ => Err(format!("estilo ascii desconocido '{}' (se espera plain o ansi)", name)),
+        }
+    }
+}
+
+// Filas de texto para columns caracteres de ancho con la proporción de width x height
+fn rows(columns: usize, width: usize, height: usize) -> usize {
+    ((columns as f32 * height as f32 / width as f32 / CELL_ASPECT).round() as usize).max(1)
+}
+
+// Convierte la imagen (0RGB, como Renderer::pixels) en texto de columns caracteres
+// de ancho. Cada carácter promedia el bloque de píxeles que cubre, así la imagen
+// puede tener más resolución que la terminal. Las líneas terminan en '\n'.
+pub fn to_ascii(pixels: &[u32], width: usize, height: usize, columns: usize, style: AsciiStyle) -> String {
+    let rows = rows(columns, width, height);
+    let lines = rows * style.pixels_per_row();
+    let mut text = String::with_capacity(rows * (columns + 1));
+
+    for row in 0..rows {
+        for column in 0..columns {
+            let x = span(column, columns, width);
+            match style {
+                AsciiStyle::Plain => {
+                    let [r, g, b] = average(pixels, width, x, span(row, lines, height));
+                    let luminance = (0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32) / 255.0;
+                    let index = (luminance * (RAMP.len() - 1) as f32).round() as usize;
+                    text.push(RAMP[index.min(RAMP.len() - 1)] as char);
+                }
+                AsciiStyle::Ansi => {
+                    let [tr, tg, tb] = average(pixels, width, x.clone(), span(row * 2, lines, height));
+                    let [br, bg, bb] = average(pixels, width, x, span(row * 2 + 1, lines, height));
+                    let _ = write!(text, "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m▀", tr, tg, tb, br, bg, bb);
+                }
+            }
+        }
+        if style == AsciiStyle::Ansi {
+            text.push_str("\x1b[0m");
+        }
+        text.push('\n');
+    }
+    text
+}
+
+// Píxeles que cubre la celda index de count a lo largo de size (al menos uno)
+fn span(index: usize, count: usize, size: usize) -> Range<usize> {
+    let start = (index * size / count).min(size - 1);
+    let end = ((index + 1) * size / count).clamp(start + 1, size);
+    start..end
+}
+
+fn average(pixels: &[u32], width: usize, x: Range<usize>, y: Range<usize>) -> [u8; 3] {
+    let mut sum = [0u32; 3];
+    let mut count = 0;
+    for row in y {
+        for &pixel in &pixels[row * width + x.start..row * width + x.end] {
+            sum[0] += (pixel >> 16) & 0xFF;
+            sum[1] += (pixel >> 8) & 0xFF;
+            sum[2] += pixel & 0xFF;
+            count += 1;
+        }
+    }
+    sum.map(|channel| (channel / count.max(1)) as u8)
+}
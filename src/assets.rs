@@ -0,0 +1,101 @@
+use thiserror::Error;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use crate::gltf_model::{GltfError, GltfModel};
+use crate::mesh::{self, Mesh};
+use crate::obj::{Model, Obj, ObjError};
+
+#[derive(Debug, Error)]
+pub enum ModelError {
+    #[error(transparent)]
+    Obj(#[from] ObjError),
+    #[error(transparent)]
+    Gltf(#[from] GltfError),
+}
+
+// Modelo leído del disco; el formato sale de la extensión (.gltf o .glb son glTF,
+// cualquier otra se lee como OBJ)
+enum ModelFile {
+    Obj(Obj),
+    Gltf(GltfModel),
+}
+
+impl ModelFile {
+    fn load(path: &str) -> Result<Self, ModelError> {
+        let extension = Path::new(path)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("gltf" | "glb") => GltfModel::load(path).map(ModelFile::Gltf).map_err(ModelError::Gltf),
+            _ => Obj::load(path).map(ModelFile::Obj).map_err(ModelError::Obj),
+        }
+    }
+
+    fn get_mesh(&self) -> Mesh {
+        match self {
+            ModelFile::Obj(obj) => obj.get_mesh(),
+            ModelFile::Gltf(gltf) => gltf.get_mesh(),
+        }
+    }
+
+    fn into_model(self) -> Model {
+        match self {
+            ModelFile::Obj(obj) => obj.into_model(),
+            ModelFile::Gltf(gltf) => gltf.into_model(),
+        }
+    }
+}
+
+// Caché de mallas: cada modelo se lee y cada esfera se genera una sola vez, y todos
+// los cuerpos (y las escenas que se cargan después) comparten la misma copia
+#[derive(Default)]
+pub struct AssetManager {
+    meshes: HashMap<String, Arc<Mesh>>,
+    models: HashMap<String, Arc<Model>>,
+}
+
+impl AssetManager {
+    pub fn new() -> Self {
+        AssetManager::default()
+    }
+
+    // Malla guardada con ese nombre; build solo se llama la primera vez
+    pub fn mesh(&mut self, name: &str, build: impl FnOnce() -> Mesh) -> Arc<Mesh> {
+        self.meshes
+            .entry(name.to_string())
+            .or_insert_with(|| Arc::new(build()))
+            .clone()
+    }
+
+    // Modelo OBJ o glTF, identificado por su ruta
+    pub fn model(&mut self, path: &str) -> Result<Arc<Mesh>, ModelError> {
+        if let Some(mesh) = self.meshes.get(path) {
+            return Ok(mesh.clone());
+        }
+
+        let mesh = Arc::new(ModelFile::load(path)?.get_mesh());
+        self.meshes.insert(path.to_string(), mesh.clone());
+        Ok(mesh)
+    }
+
+    // Modelo separado por materiales, con los colores y texturas de su .mtl o de los
+    // materiales del glTF
+    pub fn textured_model(&mut self, path: &str) -> Result<Arc<Model>, ModelError> {
+        if let Some(model) = self.models.get(path) {
+            return Ok(model.clone());
+        }
+
+        let model = Arc::new(ModelFile::load(path)?.into_model());
+        self.models.insert(path.to_string(), model.clone());
+        Ok(model)
+    }
+
+    pub fn icosphere(&mut self, subdivisions: u32) -> Arc<Mesh> {
+        self.mesh(&format!("icosphere_{}", subdivisions), || mesh::generate_icosphere(subdivisions))
+    }
+
+    pub fn uv_sphere(&mut self, stacks: u32, slices: u32) -> Arc<Mesh> {
+        self.mesh(&format!("uv_sphere_{}x{}", stacks, slices), || mesh::generate_uv_sphere(stacks, slices))
+    }
+}
@@ -0,0 +1,337 @@
+use rodio::{Decoder, OutputStream, OutputStreamHandle, PlayError, Sink, SpatialSink, StreamError};
+use rodio::decoder::DecoderError;
+use thiserror::Error;
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+use crate::ambience::{AmbientConfig, Synth, SynthSource};
+use crate::camera::Camera;
+use crate::planet::Planet;
+use crate::sfx::{Sfx, SfxSource, SoundConfig};
+use crate::spectrum::{Analyzer, AudioLevels};
+
+// Formatos que rodio sabe decodificar
+const TRACK_EXTENSIONS: [&str; 4] = ["mp3", "ogg", "wav", "flac"];
+const VOLUME_STEP: f32 = 0.1;
+const MAX_VOLUME: f32 = 1.5;
+// Separación entre los oídos, en unidades de range del sonido de ambiente
+const EAR_SPACING: f32 = 0.2;
+
+#[derive(Debug, Error)]
+pub enum AudioError {
+    #[error("no se pudo abrir el dispositivo de audio: {0}")]
+    Device(#[source] StreamError),
+    #[error("no se pudo empezar a reproducir: {0}")]
+    Sink(#[source] PlayError),
+    #[error("no se pudo leer '{0}': {1}")]
+    Io(String, #[source] io::Error),
+    #[error("no se pudo decodificar la pista '{0}': {1}")]
+    Decode(String, #[source] DecoderError),
+    #[error("no hay pistas de música en '{0}'")]
+    NoTracks(String),
+}
+
+// Sonido de todo el simulador: la música de fondo, una lista de pistas que se
+// reproducen en orden y vuelven a empezar, y el ambiente de los cuerpos, que se oye
+// según dónde está la cámara, y los efectos de la interfaz, cada uno en su propio
+// sink por encima de la música. El volumen, el silencio y la pausa son de la música;
+// el volumen y el silencio valen también para el ambiente y los efectos, y cada tipo
+// de sonido tiene además su propio volumen (SoundConfig). El stream de salida vive
+// mientras viva el administrador; al soltarlo el audio se corta sin hilos que
+// esperar. Solo abrir el dispositivo y cargar la lista pueden fallar: después, una
+// pista que no se puede leer se avisa y se saca de la lista, y sin pistas la música
+// queda en silencio.
+pub struct AudioManager {
+    _stream: OutputStream,
+    handle: OutputStreamHandle,
+    sink: Sink,
+    tracks: Vec<PathBuf>,
+    current: usize,
+    volume: f32,
+    muted: bool,
+    ambients: Vec<Ambient>,
+    effects: Vec<(Sfx, Sink)>,
+    volumes: SoundConfig,
+    analyzer: Analyzer,
+}
+
+// Sonido de ambiente que está sonando, del cuerpo planet de la escena
+struct Ambient {
+    planet: usize,
+    config: AmbientConfig,
+    sink: SpatialSink,
+}
+
+impl AudioManager {
+    // Abre el dispositivo de audio, todavía sin música ni ambiente
+    pub fn new() -> Result<Self, AudioError> {
+        let (stream, handle) = OutputStream::try_default().map_err(AudioError::Device)?;
+        let sink = Sink::try_new(&handle).map_err(AudioError::Sink)?;
+
+        Ok(AudioManager {
+            _stream: stream,
+            handle,
+            sink,
+            tracks: Vec::new(),
+            current: 0,
+            volume: 1.0,
+            muted: false,
+            ambients: Vec::new(),
+            effects: Vec::new(),
+            volumes: SoundConfig::default(),
+            analyzer: Analyzer::new(),
+        })
+    }
+
+    // Empieza la música: path es una carpeta (todas sus pistas, por nombre) o un
+    // archivo (la lista es la de su carpeta y empieza por él)
+    pub fn play_music(&mut self, path: &str) -> Result<(), AudioError> {
+        let (tracks, current) = playlist(Path::new(path))?;
+        self.tracks = tracks;
+        self.play(current);
+        if self.tracks.is_empty() {
+            return Err(AudioError::NoTracks(path.to_string()));
+        }
+        Ok(())
+    }
+
+    // Nombre de la pista que suena, sin carpeta ni extensión
+    pub fn track_name(&self) -> String {
+        match self.tracks.get(self.current) {
+            Some(track) => track.file_stem().unwrap_or(track.as_os_str()).to_string_lossy().into_owned(),
+            None => String::new(),
+        }
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.sink.is_paused()
+    }
+
+    // Niveles de la música que suena, para que la escena la siga; en silencio son cero
+    pub fn levels(&self) -> AudioLevels {
+        if self.muted {
+            AudioLevels::default()
+        } else {
+            self.analyzer.levels()
+        }
+    }
+
+    // Cada pista suena en un sink nuevo: al reemplazar el anterior se corta la que
+    // sonaba. Si la pista no se puede abrir se saca de la lista y se prueba la que
+    // ocupa su lugar.
+    fn play(&mut self, index: usize) {
+        let mut index = index;
+        while !self.tracks.is_empty() {
+            index %= self.tracks.len();
+            match self.start(index) {
+                Ok(()) => return,
+                Err(err) => {
+                    warn!("{}; se saltea la pista", err);
+                    self.tracks.remove(index);
+                }
+            }
+        }
+        warn!("No quedan pistas que se puedan reproducir");
+        self.sink.stop();
+    }
+
+    fn start(&mut self, index: usize) -> Result<(), AudioError> {
+        let path = &self.tracks[index];
+        let name = path.display().to_string();
+        let file = File::open(path).map_err(|err| AudioError::Io(name.clone(), err))?;
+        let source = Decoder::new(BufReader::new(file)).map_err(|err| AudioError::Decode(name, err))?;
+
+        let paused = self.sink.is_paused();
+        self.sink = Sink::try_new(&self.handle).map_err(AudioError::Sink)?;
+        self.sink.append(self.analyzer.tap(source));
+        if paused {
+            self.sink.pause();
+        }
+        self.current = index;
+        self.apply_volume();
+        Ok(())
+    }
+
+    pub fn next_track(&mut self) {
+        if !self.tracks.is_empty() {
+            self.play(self.current + 1);
+        }
+    }
+
+    pub fn previous_track(&mut self) {
+        if !self.tracks.is_empty() {
+            self.play(self.current + self.tracks.len() - 1);
+        }
+    }
+
+    // Se llama una vez por frame: al terminar una pista empieza la siguiente.
+    // Devuelve true si cambió de pista.
+    pub fn update(&mut self) -> bool {
+        self.effects.retain(|(_, sink)| !sink.empty());
+        if self.tracks.is_empty() || !self.sink.empty() || self.sink.is_paused() {
+            return false;
+        }
+        self.next_track();
+        !self.tracks.is_empty()
+    }
+
+    pub fn volume_up(&mut self) {
+        self.volume = (self.volume + VOLUME_STEP).min(MAX_VOLUME);
+        self.muted = false;
+        self.apply_volume();
+    }
+
+    pub fn volume_down(&mut self) {
+        self.volume = (self.volume - VOLUME_STEP).max(0.0);
+        self.apply_volume();
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+        self.apply_volume();
+    }
+
+    pub fn toggle_pause(&mut self) {
+        if self.sink.is_paused() {
+            self.sink.play();
+        } else {
+            self.sink.pause();
+        }
+    }
+
+    pub fn set_volumes(&mut self, volumes: SoundConfig) {
+        self.volumes = volumes;
+        self.apply_volume();
+    }
+
+    // Un efecto no corta a los que están sonando: cada uno va en un sink nuevo que se
+    // suelta en update al terminar
+    pub fn play_effect(&mut self, sfx: Sfx) {
+        match Sink::try_new(&self.handle) {
+            Ok(sink) => {
+                sink.set_volume(self.master_volume() * self.volumes.effect(sfx));
+                sink.append(SfxSource::new(sfx));
+                self.effects.push((sfx, sink));
+            }
+            Err(err) => warn!("{}", AudioError::Sink(err)),
+        }
+    }
+
+    fn apply_volume(&self) {
+        self.sink.set_volume(self.master_volume() * self.volumes.music);
+        for ambient in &self.ambients {
+            ambient.sink.set_volume(self.master_volume() * self.volumes.ambient * ambient.config.volume);
+        }
+        for (sfx, sink) in &self.effects {
+            sink.set_volume(self.master_volume() * self.volumes.effect(*sfx));
+        }
+    }
+
+    fn master_volume(&self) -> f32 {
+        if self.muted { 0.0 } else { self.volume }
+    }
+
+    // Se llama una vez por frame con la cámara y los cuerpos de la escena. Si los
+    // sonidos de la escena cambiaron (otra escena, un sistema generado) se vuelven a
+    // crear; después cada uno se ubica respecto de la cámara. El sonido se pone a la
+    // distancia de la superficie medida en unidades de range, así suena pleno cerca
+    // del cuerpo y rodio lo apaga con el cuadrado de la distancia más lejos.
+    pub fn update_ambients(&mut self, camera: &Camera, planets: &[Planet]) {
+        let wanted: Vec<(usize, &AmbientConfig)> = planets
+            .iter()
+            .enumerate()
+            .filter_map(|(index, planet)| planet.ambient.as_ref().map(|ambient| (index, ambient)))
+            .collect();
+        let changed = wanted.len() != self.ambients.len()
+            || wanted.iter().zip(&self.ambients).any(|((index, config), ambient)| {
+                *index != ambient.planet || **config != ambient.config
+            });
+        if changed {
+            self.ambients = wanted
+                .into_iter()
+                .filter_map(|(index, config)| match self.start_ambient(index, config, &planets[index]) {
+                    Ok(ambient) => Some(ambient),
+                    Err(err) => {
+                        warn!("{}; {} queda sin sonido de ambiente", err, planets[index].name);
+                        None
+                    }
+                })
+                .collect();
+            self.apply_volume();
+        }
+
+        let forward = (camera.center - camera.eye).normalize();
+        let right = forward.cross(&camera.up).normalize();
+        for ambient in &self.ambients {
+            let planet = &planets[ambient.planet];
+            let offset = planet.translation - camera.eye;
+            let radius = planet.scale * planet.mesh.radius();
+            let distance = (offset.magnitude() - radius).max(0.0) / ambient.config.range.max(0.01);
+            let emitter = offset.try_normalize(f32::EPSILON).unwrap_or(forward) * distance;
+
+            let ear = right * (EAR_SPACING / 2.0);
+            ambient.sink.set_emitter_position(emitter.into());
+            ambient.sink.set_left_ear_position((-ear).into());
+            ambient.sink.set_right_ear_position(ear.into());
+        }
+    }
+
+    fn start_ambient(&self, planet: usize, config: &AmbientConfig, body: &Planet) -> Result<Ambient, AudioError> {
+        let origin = [0.0; 3];
+        let sink = SpatialSink::try_new(&self.handle, origin, [-EAR_SPACING / 2.0, 0.0, 0.0], [EAR_SPACING / 2.0, 0.0, 0.0])
+            .map_err(AudioError::Sink)?;
+        match Synth::from_name(&config.sound) {
+            Some(kind) => sink.append(SynthSource::new(kind, body.noise_config.seed as u32 ^ planet as u32)),
+            None => {
+                let file = File::open(&config.sound).map_err(|err| AudioError::Io(config.sound.clone(), err))?;
+                let source = Decoder::new_looped(BufReader::new(file))
+                    .map_err(|err| AudioError::Decode(config.sound.clone(), err))?;
+                sink.append(source);
+            }
+        }
+        Ok(Ambient { planet, config: config.clone(), sink })
+    }
+}
+
+// Pistas de la carpeta (o de la carpeta del archivo) ordenadas por nombre, y cuál va primero
+fn playlist(path: &Path) -> Result<(Vec<PathBuf>, usize), AudioError> {
+    let directory = match path.parent() {
+        _ if path.is_dir() => path,
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let entries = fs::read_dir(directory).map_err(|err| AudioError::Io(directory.display().to_string(), err))?;
+
+    let mut tracks: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|track| {
+            track.extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| TRACK_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+        })
+        .collect();
+    tracks.sort();
+    if tracks.is_empty() {
+        return Err(AudioError::NoTracks(directory.display().to_string()));
+    }
+
+    // Un archivo que no está en la carpeta no impide escuchar las demás pistas
+    let current = match tracks.iter().position(|track| track == path) {
+        Some(current) => current,
+        None if path.is_dir() => 0,
+        None => {
+            warn!("No se encontró '{}'; se empieza por '{}'", path.display(), tracks[0].display());
+            0
+        }
+    };
+    Ok((tracks, current))
+}
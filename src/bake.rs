@@ -0,0 +1,177 @@
+use image::{ImageBuffer, Luma};
+use nalgebra_glm::{Mat4, Vec2, Vec3};
+use rayon::prelude::*;
+use std::f32::consts::PI;
+use std::path::Path;
+use crate::color::Color;
+use crate::fragment::Fragment;
+use crate::lighting::{Light, MAX_LIGHTS};
+use crate::planet::Planet;
+use crate::recorder;
+use crate::renderer::Uniforms;
+use crate::shader_params::ShaderParams;
+use crate::shaders::{fragment_shader, surface_height, ShaderKind};
+use crate::spectrum::AudioLevels;
+
+// Superficie de un planeta evaluada sobre una grilla de latitud y longitud
+pub struct BakedSurface {
+    pub width: usize,
+    pub height: usize,
+    pub albedo: Vec<u32>,
+    // Relieve del shader, si lo tiene, en las unidades del ruido
+    pub heights: Option<Vec<f32>>,
+}
+
+// Evalúa el shader del planeta en cada píxel de una proyección equirrectangular de
+// width x width / 2, con la misma orientación que las coordenadas de textura de la
+// esfera UV: u recorre la longitud desde +x y v va del polo norte al sur.
+// Se toma el color sin iluminar (una luz solo ambiental de intensidad 1), sin luces
+// de ciudades ni oscurecimiento del borde, para usarlo como textura en otro motor.
+// Con with_heights también se evalúa el relieve, si el shader tiene uno.
+pub fn bake_surface(planet: &Planet, params: &ShaderParams, width: usize, sim_time: f32, with_heights: bool) -> BakedSurface {
+    let width = width.max(2);
+    let height = (width / 2).max(1);
+
+    let flat_light = flat_light();
+    let uniforms = flat_uniforms(planet, params, &flat_light, sim_time);
+
+    let point_at = |x: usize, y: usize| {
+        let tex_coords = texel_center(x, y, width, height);
+        (sphere_point(tex_coords), tex_coords)
+    };
+
+    let mut albedo = vec![0; width * height];
+    albedo.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let (point, tex_coords) = point_at(x, y);
+            let fragment = Fragment {
+                position: Vec2::new(x as f32, y as f32),
+                color: Color::black(),
+                depth: 0.0,
+                normal: point,
+                intensity: 1.0,
+                light_visibility: [1.0; MAX_LIGHTS],
+                model_position: point,
+                world_position: point,
+                // Vista y luz de frente: sin borde oscurecido y todo del lado diurno
+                view_dir: point,
+                light_dir: point,
+                tex_coords,
+            };
+            *pixel = fragment_shader(&fragment, &uniforms, planet.shader).to_hex();
+        }
+    });
+
+    // Los shaders sin relieve dan None en todos los puntos
+    let heights = with_heights.then(|| {
+        (0..width * height)
+            .into_par_iter()
+            .map(|index| surface_height(point_at(index % width, index / width).0, &uniforms, planet.shader))
+            .collect::<Option<Vec<f32>>>()
+    }).flatten();
+
+    BakedSurface { width, height, albedo, heights }
+}
+
+// Coordenadas de textura del centro del píxel (x, y) de una imagen de width x height
+pub(crate) fn texel_center(x: usize, y: usize, width: usize, height: usize) -> Vec2 {
+    Vec2::new((x as f32 + 0.5) / width as f32, (y as f32 + 0.5) / height as f32)
+}
+
+// Punto de la esfera unitaria que se evalúa en unas coordenadas de textura
+pub(crate) fn sphere_point(tex_coords: Vec2) -> Vec3 {
+    let (theta, phi) = (tex_coords.y * PI, tex_coords.x * 2.0 * PI);
+    Vec3::new(theta.sin() * phi.cos(), theta.cos(), -theta.sin() * phi.sin())
+}
+
+// Colores del shader de los anillos a lo largo del radio, sin iluminar y con su
+// transparencia: la muestra x cae en u = (x + 0.5) / width de la corona, que va del
+// borde interior al exterior. None si el planeta no tiene anillos.
+pub fn bake_rings(planet: &Planet, params: &ShaderParams, width: usize, sim_time: f32) -> Option<Vec<Color>> {
+    let rings = planet.rings.as_ref()?;
+    let width = width.max(1);
+    let flat_light = flat_light();
+    let uniforms = flat_uniforms(planet, params, &flat_light, sim_time);
+
+    let colors = (0..width)
+        .map(|x| {
+            let u = (x as f32 + 0.5) / width as f32;
+            let point = Vec3::new(rings.inner_radius + (rings.outer_radius - rings.inner_radius) * u, 0.0, 0.0);
+            let fragment = Fragment {
+                position: Vec2::new(x as f32, 0.0),
+                color: Color::black(),
+                depth: 0.0,
+                normal: Vec3::y(),
+                intensity: 1.0,
+                light_visibility: [1.0; MAX_LIGHTS],
+                model_position: point,
+                world_position: point,
+                view_dir: Vec3::y(),
+                light_dir: Vec3::y(),
+                tex_coords: Vec2::new(u, 0.0),
+            };
+            fragment_shader(&fragment, &uniforms, ShaderKind::Ring)
+        })
+        .collect();
+    Some(colors)
+}
+
+// Una luz solo ambiental de intensidad 1: el color del shader sin sombrear
+fn flat_light() -> [Light; 1] {
+    [Light {
+        intensity: 0.0,
+        ambient: 1.0,
+        attenuation: 0.0,
+        ..Light::new(Vec3::zeros())
+    }]
+}
+
+fn flat_uniforms<'a>(planet: &'a Planet, params: &'a ShaderParams, lights: &'a [Light], sim_time: f32) -> Uniforms<'a> {
+    Uniforms {
+        model_matrix: Mat4::identity(),
+        view_matrix: Mat4::identity(),
+        projection_matrix: Mat4::identity(),
+        viewport_matrix: Mat4::identity(),
+        camera_position: Vec3::zeros(),
+        lights,
+        occluders: &[],
+        sim_time,
+        noise: &planet.noise,
+        craters: Some(&planet.craters),
+        texture: planet.texture.as_ref(),
+        atmosphere: None,
+        city_lights: false,
+        twilight: None,
+        tint: planet.tint,
+        palette: planet.palette.as_ref(),
+        fog: None,
+        script: planet.script.as_ref(),
+        params,
+        material: None,
+        pbr: None,
+        music: AudioLevels::default(),
+    }
+}
+
+impl BakedSurface {
+    pub fn save_albedo(&self, path: &Path) -> image::ImageResult<()> {
+        recorder::save_png(path, &self.albedo, self.width, self.height)
+    }
+
+    // Mapa de alturas en grises de 16 bits, estirado entre la altura mínima (negro)
+    // y la máxima (blanco) del planeta. No hace nada si el shader no tiene relieve.
+    pub fn save_heights(&self, path: &Path) -> image::ImageResult<()> {
+        let Some(heights) = &self.heights else {
+            return Ok(());
+        };
+
+        let min = heights.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = heights.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let range = (max - min).max(f32::EPSILON);
+        let image = ImageBuffer::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let value = (heights[y as usize * self.width + x as usize] - min) / range;
+            Luma([(value * u16::MAX as f32).round() as u16])
+        });
+        image.save(path)
+    }
+}
@@ -0,0 +1,127 @@
+use fastnoise_lite::FastNoiseLite;
+use nalgebra_glm::Vec3;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+use std::f32::consts::TAU;
+use crate::mesh::{self, Mesh};
+use crate::noise::{NoiseConfig, NoiseKind};
+
+// Cinturón de asteroides: radios en unidades de la escena, tamaños como escala
+// de cada roca y periodo de la órbita en el borde interior
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BeltConfig {
+    pub seed: u64,
+    pub count: usize,
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+    pub thickness: f32,
+    pub min_size: f32,
+    pub max_size: f32,
+    pub period: f32,
+    pub shapes: usize,
+    pub subdivisions: u32,
+    pub roughness: f32,
+}
+
+impl Default for BeltConfig {
+    fn default() -> Self {
+        BeltConfig {
+            seed: 1,
+            count: 300,
+            inner_radius: 8.0,
+            outer_radius: 9.5,
+            thickness: 0.3,
+            min_size: 0.03,
+            max_size: 0.12,
+            period: 20.0,
+            shapes: 6,
+            subdivisions: 2,
+            roughness: 0.35,
+        }
+    }
+}
+
+// Una roca del cinturón; la malla es una de las formas compartidas
+pub struct Asteroid {
+    pub shape: usize,
+    pub translation: Vec3,
+    pub rotation: Vec3,
+    pub scale: f32,
+    orbit_radius: f32,
+    phase: f32,
+    height: f32,
+    period: f32,
+    spin: Vec3,
+}
+
+// Una de las formas de roca, con el radio de su malla para descartarla sin recorrerla
+pub struct AsteroidShape {
+    pub mesh: Mesh,
+    pub radius: f32,
+}
+
+pub struct AsteroidBelt {
+    pub shapes: Vec<AsteroidShape>,
+    pub asteroids: Vec<Asteroid>,
+    // Ruido celular para los cráteres del shader de roca
+    pub noise: FastNoiseLite,
+}
+
+impl AsteroidBelt {
+    pub fn generate(config: &BeltConfig) -> Self {
+        let mut rng = StdRng::seed_from_u64(config.seed);
+
+        // Pocas formas distintas, compartidas por todas las rocas: cada una es una
+        // roca generada con otra semilla
+        let shapes = (0..config.shapes.max(1))
+            .map(|_| {
+                let mesh = mesh::generate_asteroid(rng.gen(), config.subdivisions, config.roughness);
+                AsteroidShape { radius: mesh.radius(), mesh }
+            })
+            .collect();
+
+        let asteroids = (0..config.count)
+            .map(|_| {
+                // Raíz cuadrada: densidad uniforme por área dentro de la corona
+                let t: f32 = rng.gen_range(0.0f32..1.0).sqrt();
+                let orbit_radius = config.inner_radius + (config.outer_radius - config.inner_radius) * t;
+                let period = config.period * (orbit_radius / config.inner_radius).powf(1.5);
+
+                let mut asteroid = Asteroid {
+                    shape: rng.gen_range(0..config.shapes.max(1)),
+                    translation: Vec3::zeros(),
+                    rotation: Vec3::new(rng.gen_range(0.0..TAU), rng.gen_range(0.0..TAU), rng.gen_range(0.0..TAU)),
+                    scale: rng.gen_range(config.min_size..config.max_size.max(config.min_size + f32::EPSILON)),
+                    orbit_radius,
+                    phase: rng.gen_range(0.0..TAU),
+                    height: rng.gen_range(-0.5f32..0.5) * config.thickness,
+                    period,
+                    spin: Vec3::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)),
+                };
+                asteroid.translation = asteroid.position(0.0);
+                asteroid
+            })
+            .collect();
+
+        let noise = NoiseConfig { seed: config.seed as i32, noise_type: NoiseKind::Cellular, ..NoiseConfig::default() }.build();
+
+        AsteroidBelt { shapes, asteroids, noise }
+    }
+
+    pub fn update(&mut self, sim_time: f32, dt: f32) {
+        for asteroid in self.asteroids.iter_mut() {
+            asteroid.translation = asteroid.position(sim_time);
+            asteroid.rotation += asteroid.spin * dt;
+        }
+    }
+}
+
+impl Asteroid {
+    // Órbita circular en el plano XZ, con el mismo sentido que los planetas
+    fn position(&self, time: f32) -> Vec3 {
+        let angle = TAU * time / self.period + self.phase;
+        Vec3::new(self.orbit_radius * angle.cos(), self.height, self.orbit_radius * angle.sin())
+    }
+}
@@ -0,0 +1,255 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use serde::Deserialize;
+use crate::color::Color;
+use crate::framebuffer::HdrFramebuffer;
+use crate::texture::Texture;
+
+// Cómo se combina el sprite con lo que ya hay en el framebuffer: Alpha lo mezcla
+// según su opacidad (partículas, carteles) y Additive suma luz (brillos, destellos)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlendMode {
+    #[default]
+    Alpha,
+    Additive,
+}
+
+// Aspecto de un billboard. Sin textura es un disco de borde suave; con textura se
+// usa su transparencia. color tiñe la imagen y su alfa es la opacidad; intensity
+// multiplica el color en el espacio lineal, así un brillo puede pasar de 1 y
+// alimentar el bloom.
+#[derive(Clone, Copy)]
+pub struct Sprite<'a> {
+    pub texture: Option<&'a Texture>,
+    pub color: Color,
+    pub intensity: f32,
+    // Giro en el plano de la pantalla, en radianes
+    pub rotation: f32,
+    pub blend: BlendMode,
+    // Los elementos de pantalla (destellos de lente) se dibujan encima de todo
+    pub depth_test: bool,
+}
+
+impl<'a> Sprite<'a> {
+    pub const fn disc(color: Color) -> Self {
+        Sprite {
+            texture: None,
+            color,
+            intensity: 1.0,
+            rotation: 0.0,
+            blend: BlendMode::Alpha,
+            depth_test: true,
+        }
+    }
+
+    pub const fn textured(texture: &'a Texture, color: Color) -> Self {
+        Sprite { texture: Some(texture), ..Sprite::disc(color) }
+    }
+
+    pub const fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub const fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub const fn with_blend(mut self, blend: BlendMode) -> Self {
+        self.blend = blend;
+        self
+    }
+
+    pub const fn without_depth_test(mut self) -> Self {
+        self.depth_test = false;
+        self
+    }
+}
+
+// Dibuja un cuadrado orientado a la cámara de medio lado size (en unidades de la
+// escena) centrado en position. La profundidad se prueba pero no se escribe si el
+// framebuffer tiene la escritura apagada, como en el paso de transparentes.
+pub fn draw_billboard(
+    framebuffer: &mut HdrFramebuffer,
+    position: Vec3,
+    size: f32,
+    sprite: &Sprite,
+    view_matrix: &Mat4,
+    projection_matrix: &Mat4,
+    viewport_matrix: &Mat4,
+) {
+    let Some((center, w)) = project(position, view_matrix, projection_matrix, viewport_matrix) else {
+        return;
+    };
+
+    // Radio en píxeles: el tamaño proyectado en y, a la escala del viewport
+    let radius = (size * projection_matrix[(1, 1)] / w * viewport_matrix[(1, 1)].abs()).max(0.5);
+    if radius > framebuffer.height as f32 {
+        return;
+    }
+
+    draw_sprite(framebuffer, center, radius, sprite);
+}
+
+// Un solo píxel con el color y la mezcla del sprite (la textura y el giro no cuentan):
+// lo más barato para miles de partículas lejanas
+pub fn draw_point(
+    framebuffer: &mut HdrFramebuffer,
+    position: Vec3,
+    sprite: &Sprite,
+    view_matrix: &Mat4,
+    projection_matrix: &Mat4,
+    viewport_matrix: &Mat4,
+) {
+    let Some((center, _)) = project(position, view_matrix, projection_matrix, viewport_matrix) else {
+        return;
+    };
+    if center.x < 0.0 || center.y < 0.0 {
+        return;
+    }
+    let (x, y) = (center.x as usize, center.y as usize);
+    if x >= framebuffer.width || y >= framebuffer.height {
+        return;
+    }
+    if sprite.depth_test && !framebuffer.depth.test_and_write(x, y, center.z) {
+        return;
+    }
+
+    let color = sprite.color.to_linear() * sprite.intensity;
+    let alpha = sprite.color.alpha() as f32 / 255.0;
+    blend_pixel(&mut framebuffer.buffer[y * framebuffer.width + x], color, alpha, sprite.blend);
+}
+
+// Posición en pantalla (en píxeles, z es la profundidad) y w del espacio de recorte,
+// o None si el punto queda detrás de la cámara o del plano cercano
+fn project(position: Vec3, view_matrix: &Mat4, projection_matrix: &Mat4, viewport_matrix: &Mat4) -> Option<(Vec3, f32)> {
+    let clip = projection_matrix * view_matrix * Vec4::new(position.x, position.y, position.z, 1.0);
+    if clip.w <= 0.0 || clip.z < -clip.w {
+        return None;
+    }
+
+    let ndc = Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+    Some(((viewport_matrix * ndc).xyz(), clip.w))
+}
+
+fn blend_pixel(pixel: &mut Vec3, color: Vec3, alpha: f32, blend: BlendMode) {
+    *pixel = match blend {
+        BlendMode::Alpha => pixel.lerp(&color, alpha),
+        BlendMode::Additive => *pixel + color * alpha,
+    };
+}
+
+// Dibuja el sprite directamente en pantalla: center en píxeles (z es la profundidad)
+// y radius es el medio lado del cuadrado en píxeles
+pub fn draw_sprite(framebuffer: &mut HdrFramebuffer, center: Vec3, radius: f32, sprite: &Sprite) {
+    let (sin, cos) = sprite.rotation.sin_cos();
+    // Un cuadrado girado ocupa hasta su diagonal
+    let reach = if sprite.texture.is_some() { radius * (sin.abs() + cos.abs()) } else { radius };
+
+    let min_x = (center.x - reach).floor().max(0.0) as usize;
+    let min_y = (center.y - reach).floor().max(0.0) as usize;
+    let max_x = ((center.x + reach).ceil().max(0.0) as usize).min(framebuffer.width);
+    let max_y = ((center.y + reach).ceil().max(0.0) as usize).min(framebuffer.height);
+
+    let tint = sprite.color.to_linear() * sprite.intensity;
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let dx = x as f32 + 0.5 - center.x;
+            let dy = y as f32 + 0.5 - center.y;
+
+            let (color, coverage) = match sprite.texture {
+                Some(texture) => {
+                    // Coordenadas del píxel en el cuadrado sin girar, de 0 a 1
+                    let u = (dx * cos + dy * sin) / (2.0 * radius) + 0.5;
+                    let v = (dy * cos - dx * sin) / (2.0 * radius) + 0.5;
+                    if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+                        continue;
+                    }
+                    let texel = texture.sample_bilinear(u, v);
+                    (tint.component_mul(&texel.to_linear()), texel.alpha() as f32 / 255.0)
+                }
+                None => (tint, 1.0 - (dx * dx + dy * dy).sqrt() / radius),
+            };
+            if coverage <= 0.0 {
+                continue;
+            }
+
+            let alpha = (sprite.color.alpha() as f32 * coverage).clamp(0.0, 255.0) as u8;
+            if sprite.depth_test && !framebuffer.depth.test_and_write(x, y, center.z) {
+                continue;
+            }
+
+            blend_pixel(&mut framebuffer.buffer[y * framebuffer.width + x], color, alpha as f32 / 255.0, sprite.blend);
+        }
+    }
+}
+
+// Billboard fijo de la escena: posición y medio lado en unidades de la escena, una
+// imagen PNG (con su transparencia) o un disco suave, el color que la tiñe, la
+// opacidad, la intensidad, el giro en grados y la mezcla ("alpha" o "additive")
+#[derive(Debug, Clone, Deserialize)]
+pub struct BillboardConfig {
+    pub position: [f32; 3],
+    pub size: f32,
+    pub texture: Option<String>,
+    #[serde(default = "default_color")]
+    pub color: Color,
+    #[serde(default = "default_one")]
+    pub opacity: f32,
+    #[serde(default = "default_one")]
+    pub intensity: f32,
+    #[serde(default)]
+    pub rotation: f32,
+    #[serde(default)]
+    pub blend: BlendMode,
+}
+
+fn default_color() -> Color {
+    Color::new(255, 255, 255)
+}
+
+fn default_one() -> f32 {
+    1.0
+}
+
+pub struct Billboard {
+    pub position: Vec3,
+    pub size: f32,
+    pub texture: Option<Texture>,
+    pub color: Color,
+    pub intensity: f32,
+    pub rotation: f32,
+    pub blend: BlendMode,
+}
+
+impl Billboard {
+    pub fn load(config: &BillboardConfig) -> Result<Self, image::ImageError> {
+        let texture = match &config.texture {
+            Some(path) => Some(Texture::load_rgba(path)?),
+            None => None,
+        };
+
+        let [x, y, z] = config.position;
+        Ok(Billboard {
+            position: Vec3::new(x, y, z),
+            size: config.size,
+            texture,
+            color: config.color.with_alpha((config.opacity.clamp(0.0, 1.0) * 255.0).round() as u8),
+            intensity: config.intensity,
+            rotation: config.rotation.to_radians(),
+            blend: config.blend,
+        })
+    }
+
+    pub fn sprite(&self) -> Sprite<'_> {
+        let sprite = match &self.texture {
+            Some(texture) => Sprite::textured(texture, self.color),
+            None => Sprite::disc(self.color),
+        };
+        sprite
+            .with_intensity(self.intensity)
+            .with_rotation(self.rotation)
+            .with_blend(self.blend)
+    }
+}
@@ -76,6 +76,17 @@ impl Camera {
     self.has_changed = true;
   }
 
+  pub fn pan(&mut self, delta_right: f32, delta_up: f32) {
+    let forward = (self.center - self.eye).normalize();
+    let right = forward.cross(&self.up).normalize();
+    let up = right.cross(&forward).normalize();
+
+    let offset = right * delta_right + up * delta_up;
+    self.eye += offset;
+    self.center += offset;
+    self.has_changed = true;
+  }
+
   pub fn check_if_changed(&mut self) -> bool {
     if self.has_changed {
       self.has_changed = false;
@@ -85,3 +96,49 @@ impl Camera {
     }
   }
 }
+
+// Tiempo (en segundos) que tarda la cámara en pasar de un objetivo a otro
+const FOLLOW_TRANSITION_TIME: f32 = 1.0;
+
+pub struct CameraFollow {
+  pub target: Option<usize>,
+  from: Vec3,
+  progress: f32,
+}
+
+impl CameraFollow {
+  pub fn new() -> Self {
+    CameraFollow {
+      target: None,
+      from: Vec3::new(0.0, 0.0, 0.0),
+      progress: 1.0,
+    }
+  }
+
+  pub fn set_target(&mut self, target: Option<usize>, camera: &Camera) {
+    if target != self.target {
+      self.target = target;
+      self.from = camera.center;
+      self.progress = 0.0;
+    }
+  }
+
+  // Mueve el centro hacia el objetivo y arrastra el ojo con él para conservar la distancia
+  pub fn update(&mut self, camera: &mut Camera, target_position: Vec3, delta_time: f32) {
+    self.progress = (self.progress + delta_time / FOLLOW_TRANSITION_TIME).min(1.0);
+    let t = self.progress * self.progress * (3.0 - 2.0 * self.progress);
+
+    let new_center = self.from + (target_position - self.from) * t;
+    let offset = new_center - camera.center;
+
+    camera.center = new_center;
+    camera.eye += offset;
+    camera.has_changed = true;
+  }
+}
+
+impl Default for CameraFollow {
+  fn default() -> Self {
+    Self::new()
+  }
+}
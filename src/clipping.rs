@@ -0,0 +1,149 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::vertex::Vertex;
+
+// Planos del frustum en clip space: un vértice está dentro si w + k * componente >= 0
+#[derive(Clone, Copy)]
+enum Plane {
+  Near,
+  Far,
+  Left,
+  Right,
+  Bottom,
+  Top,
+}
+
+const PLANES: [Plane; 6] = [
+  Plane::Near,
+  Plane::Far,
+  Plane::Left,
+  Plane::Right,
+  Plane::Bottom,
+  Plane::Top,
+];
+
+impl Plane {
+  fn distance(&self, p: &Vec4) -> f32 {
+    match self {
+      Plane::Near => p.w + p.z,
+      Plane::Far => p.w - p.z,
+      Plane::Left => p.w + p.x,
+      Plane::Right => p.w - p.x,
+      Plane::Bottom => p.w + p.y,
+      Plane::Top => p.w - p.y,
+    }
+  }
+
+  // El mismo plano antes de la matriz: distance(m * p) = coeficientes · p
+  fn coefficients(&self, m: &Mat4) -> Vec4 {
+    let row = |i: usize| m.row(i).transpose();
+    match self {
+      Plane::Near => row(3) + row(2),
+      Plane::Far => row(3) - row(2),
+      Plane::Left => row(3) + row(0),
+      Plane::Right => row(3) - row(0),
+      Plane::Bottom => row(3) + row(1),
+      Plane::Top => row(3) - row(1),
+    }
+  }
+}
+
+// Los seis planos del frustum en espacio de mundo, para descartar objetos enteros
+// antes de transformar sus vértices
+pub struct Frustum {
+  planes: [Vec4; 6],
+}
+
+impl Frustum {
+  pub fn new(view_projection: &Mat4) -> Self {
+    Frustum {
+      planes: PLANES.map(|plane| {
+        let coefficients = plane.coefficients(view_projection);
+        // Normalizado para que el producto punto sea la distancia al plano
+        coefficients / coefficients.xyz().magnitude()
+      }),
+    }
+  }
+
+  // Falso solo si la esfera queda entera del lado de afuera de algún plano
+  pub fn intersects_sphere(&self, center: &Vec3, radius: f32) -> bool {
+    let point = Vec4::new(center.x, center.y, center.z, 1.0);
+    self.planes.iter().all(|plane| plane.dot(&point) >= -radius)
+  }
+}
+
+// Recorta un triángulo contra los seis planos del frustum (Sutherland-Hodgman)
+// y agrega a output los triángulos resultantes con su posición en pantalla calculada.
+pub fn clip_triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, viewport_matrix: &Mat4, output: &mut Vec<[Vertex; 3]>) {
+  let inside_all = [v1, v2, v3].iter().all(|v| {
+    PLANES.iter().all(|plane| plane.distance(&v.clip_position) >= 0.0)
+  });
+
+  // Caso común: el triángulo está completamente dentro
+  if inside_all {
+    output.push([v1.clone(), v2.clone(), v3.clone()]);
+    return;
+  }
+
+  let mut polygon = vec![v1.clone(), v2.clone(), v3.clone()];
+
+  for plane in PLANES.iter() {
+    polygon = clip_polygon(&polygon, plane);
+    if polygon.len() < 3 {
+      return;
+    }
+  }
+
+  for vertex in polygon.iter_mut() {
+    project(vertex, viewport_matrix);
+  }
+
+  // Triangulación en abanico, conserva el orden de los vértices
+  output.extend(
+    (1..polygon.len() - 1).map(|i| [polygon[0].clone(), polygon[i].clone(), polygon[i + 1].clone()])
+  );
+}
+
+fn clip_polygon(polygon: &[Vertex], plane: &Plane) -> Vec<Vertex> {
+  let mut output = Vec::with_capacity(polygon.len() + 2);
+
+  for i in 0..polygon.len() {
+    let current = &polygon[i];
+    let next = &polygon[(i + 1) % polygon.len()];
+
+    let d_current = plane.distance(&current.clip_position);
+    let d_next = plane.distance(&next.clip_position);
+
+    if d_current >= 0.0 {
+      output.push(current.clone());
+    }
+
+    if (d_current >= 0.0) != (d_next >= 0.0) {
+      let t = d_current / (d_current - d_next);
+      output.push(lerp_vertex(current, next, t));
+    }
+  }
+
+  output
+}
+
+fn lerp_vertex(a: &Vertex, b: &Vertex, t: f32) -> Vertex {
+  Vertex {
+    position: a.position + (b.position - a.position) * t,
+    normal: a.normal + (b.normal - a.normal) * t,
+    tex_coords: a.tex_coords + (b.tex_coords - a.tex_coords) * t,
+    color: a.color.lerp(&b.color, t),
+    clip_position: a.clip_position + (b.clip_position - a.clip_position) * t,
+    world_position: a.world_position + (b.world_position - a.world_position) * t,
+    transformed_position: a.transformed_position,
+    transformed_normal: a.transformed_normal + (b.transformed_normal - a.transformed_normal) * t,
+  }
+}
+
+// División de perspectiva y viewport para los vértices que salen del recorte
+fn project(vertex: &mut Vertex, viewport_matrix: &Mat4) {
+  let clip = vertex.clip_position;
+  let ndc = Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+  let screen = viewport_matrix * ndc;
+
+  vertex.transformed_position = Vec3::new(screen.x, screen.y, screen.z);
+}
@@ -0,0 +1,80 @@
+use std::time::Instant;
+
+// Un frame muy lento (p. ej. al mover la ventana) no debe disparar cientos de pasos
+const MAX_FRAME_DELTA: f32 = 0.25;
+// Límites del multiplicador de velocidad (en valor absoluto)
+const MIN_TIME_SCALE: f32 = 0.125;
+const MAX_TIME_SCALE: f32 = 64.0;
+
+pub struct Clock {
+    last_instant: Instant,
+    accumulator: f32,
+    pub fixed_step: f32,
+    pub frame_delta: f32,
+    pub sim_time: f32,
+    pub time_scale: f32,
+    pub paused: bool,
+}
+
+impl Clock {
+    pub fn new(fixed_step: f32) -> Self {
+        Clock {
+            last_instant: Instant::now(),
+            accumulator: 0.0,
+            fixed_step,
+            frame_delta: 0.0,
+            sim_time: 0.0,
+            time_scale: 1.0,
+            paused: false,
+        }
+    }
+
+    // Mide el tiempo real transcurrido desde el último frame
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        self.frame_delta = (now - self.last_instant).as_secs_f32().min(MAX_FRAME_DELTA);
+        self.last_instant = now;
+        self.accumulator += self.frame_delta;
+    }
+
+    // Consume un paso fijo de simulación si hay tiempo acumulado suficiente
+    pub fn step(&mut self) -> bool {
+        if self.accumulator >= self.fixed_step {
+            self.accumulator -= self.fixed_step;
+            self.sim_time += self.step_delta();
+            true
+        } else {
+            false
+        }
+    }
+
+    // Tiempo simulado que avanza en cada paso fijo: negativo en reversa, cero en pausa
+    pub fn step_delta(&self) -> f32 {
+        if self.paused {
+            0.0
+        } else {
+            self.fixed_step * self.time_scale
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    // Duplica o divide la velocidad conservando el sentido
+    pub fn speed_up(&mut self) {
+        self.set_speed(self.time_scale.abs() * 2.0);
+    }
+
+    pub fn slow_down(&mut self) {
+        self.set_speed(self.time_scale.abs() / 2.0);
+    }
+
+    pub fn reverse(&mut self) {
+        self.time_scale = -self.time_scale;
+    }
+
+    fn set_speed(&mut self, speed: f32) {
+        self.time_scale = speed.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE).copysign(self.time_scale);
+    }
+}
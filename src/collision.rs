@@ -0,0 +1,24 @@
+use nalgebra_glm::Vec3;
+use crate::planet::Planet;
+
+// Saca una esfera (position, radius) de cualquier planeta con el que se superponga,
+// empujándola en la dirección de la normal de contacto. Devuelve la normal del
+// último contacto para que quien se mueve pueda deslizarse sobre la superficie.
+pub fn push_out_of_planets(position: &mut Vec3, radius: f32, planets: &[Planet]) -> Option<Vec3> {
+    let mut contact = None;
+
+    for planet in planets {
+        let offset = *position - planet.translation;
+        let distance = offset.magnitude();
+        let min_distance = planet.collision_radius() + radius;
+
+        if distance < min_distance {
+            // Justo en el centro no hay dirección definida: se sale hacia arriba
+            let normal = if distance > f32::EPSILON { offset / distance } else { Vec3::new(0.0, 1.0, 0.0) };
+            *position = planet.translation + normal * min_distance;
+            contact = Some(normal);
+        }
+    }
+
+    contact
+}
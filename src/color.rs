@@ -1,26 +1,163 @@
+use nalgebra_glm::Vec3;
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
 use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+// Resolución de la tabla de lineal a sRGB; con menos entradas se notan escalones en
+// los tonos oscuros
+const ENCODE_TABLE_SIZE: usize = 16384;
+
+// Si las operaciones entre colores (mezcla, intensidad, suma) se hacen sobre la luz
+// en espacio lineal; si no, se hacen directo sobre los valores sRGB, que oscurece
+// los degradados. Se puede apagar para comparar.
+static LINEAR_MATH: AtomicBool = AtomicBool::new(true);
+
+pub fn set_linear_math(enabled: bool) {
+    LINEAR_MATH.store(enabled, Ordering::Relaxed);
+}
+
+pub fn linear_math() -> bool {
+    LINEAR_MATH.load(Ordering::Relaxed)
+}
 
 #[derive(Debug, Clone, Copy)]
 pub struct Color {
     r: u8,
     g: u8,
     b: u8,
+    a: u8,
 }
 
 impl Color {
     pub const fn new(r: u8, g: u8, b: u8) -> Self {
-        Color { r, g, b }
+        Color { r, g, b, a: 255 }
+    }
+
+    pub const fn new_rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Color { r, g, b, a }
     }
 
     pub const fn from_hex(hex: u32) -> Self {
         let r = ((hex >> 16) & 0xFF) as u8;
         let g = ((hex >> 8) & 0xFF) as u8;
         let b = (hex & 0xFF) as u8;
-        Color { r, g, b }
+        Color { r, g, b, a: 255 }
     }
 
     pub const fn black() -> Self {
-        Color { r: 0, g: 0, b: 0 }
+        Color { r: 0, g: 0, b: 0, a: 255 }
+    }
+
+    // Tono en grados; saturación y valor entre 0 y 1
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let chroma = value * saturation;
+        Color::from_chroma(hue, chroma, value - chroma)
+    }
+
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (hue, max, min) = self.hue_max_min();
+        let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
+        (hue, saturation, max)
+    }
+
+    // Tono en grados; saturación y luminosidad entre 0 y 1
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Color::from_chroma(hue, chroma, lightness - chroma / 2.0)
+    }
+
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (hue, max, min) = self.hue_max_min();
+        let lightness = (max + min) / 2.0;
+        let saturation = if max > min { (max - min) / (1.0 - (2.0 * lightness - 1.0).abs()) } else { 0.0 };
+        (hue, saturation, lightness)
+    }
+
+    // Parte común de HSV y HSL: el tono elige la mezcla de canales con el croma dado
+    // y m sube los tres por igual
+    fn from_chroma(hue: f32, chroma: f32, m: f32) -> Self {
+        let sector = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+        let (r, g, b) = match sector as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let channel = |value: f32| ((value + m).clamp(0.0, 1.0) * 255.0).round() as u8;
+        Color::new(channel(r), channel(g), channel(b))
+    }
+
+    // Tono en grados y los canales mayor y menor, entre 0 y 1
+    fn hue_max_min(&self) -> (f32, f32, f32) {
+        let (r, g, b) = (self.r as f32 / 255.0, self.g as f32 / 255.0, self.b as f32 / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        (hue, max, min)
+    }
+
+    pub fn alpha(&self) -> u8 {
+        self.a
+    }
+
+    pub fn with_alpha(&self, a: u8) -> Self {
+        Color { a, ..*self }
+    }
+
+    // Composición "source over": self encima de dst, según el alfa de self
+    pub fn over(&self, dst: &Color) -> Color {
+        dst.lerp(&self.with_alpha(dst.a), self.a as f32 / 255.0)
+    }
+
+    // Color en intensidad lineal (sin la curva sRGB), para sumar y mezclar luz
+    pub fn to_linear(&self) -> Vec3 {
+        let table = decode_table();
+        Vec3::new(table[self.r as usize], table[self.g as usize], table[self.b as usize])
+    }
+
+    // De intensidad lineal a sRGB; lo que pasa de 1 se recorta
+    pub fn from_linear(color: &Vec3) -> Color {
+        Color::from_linear_dithered(color, 0.5)
+    }
+
+    // Como from_linear, pero cada canal sube al siguiente valor de 8 bits cuando su
+    // parte fraccionaria supera 1 - threshold (0.5 redondea; un umbral que cambia
+    // por píxel reparte el error y disimula los escalones de los degradados)
+    pub fn from_linear_dithered(color: &Vec3, threshold: f32) -> Color {
+        let table = encode_table();
+        let channel = |value: f32| {
+            let encoded = table[(value.clamp(0.0, 1.0) * (ENCODE_TABLE_SIZE - 1) as f32 + 0.5) as usize];
+            (encoded + threshold).min(255.0) as u8
+        };
+        Color::new(channel(color.x), channel(color.y), channel(color.z))
+    }
+
+    // Intensidad lineal codificada con la curva sRGB, de 0 a 1 y todavía sin pasar a 8 bits
+    pub fn encode_srgb(color: &Vec3) -> Vec3 {
+        let table = encode_table();
+        color.map(|value| table[(value.clamp(0.0, 1.0) * (ENCODE_TABLE_SIZE - 1) as f32 + 0.5) as usize] / 255.0)
+    }
+
+    // Como from_linear_dithered, para valores ya codificados en sRGB (de 0 a 1)
+    pub fn from_srgb_dithered(color: &Vec3, threshold: f32) -> Color {
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0 + threshold).min(255.0) as u8;
+        Color::new(channel(color.x), channel(color.y), channel(color.z))
     }
 
     pub fn to_hex(&self) -> u32 {
@@ -30,10 +167,30 @@ impl Color {
     // Linear interpolation between two colors
     pub fn lerp(&self, other: &Color, t: f32) -> Self {
         let t = t.clamp(0.0, 1.0);
+        if linear_math() {
+            let alpha = (self.a as f32 + (other.a as f32 - self.a as f32) * t).round() as u8;
+            return Color::from_linear(&self.to_linear().lerp(&other.to_linear(), t)).with_alpha(alpha);
+        }
         Color {
             r: (self.r as f32 + (other.r as f32 - self.r as f32) * t).round() as u8,
             g: (self.g as f32 + (other.g as f32 - self.g as f32) * t).round() as u8,
             b: (self.b as f32 + (other.b as f32 - self.b as f32) * t).round() as u8,
+            a: (self.a as f32 + (other.a as f32 - self.a as f32) * t).round() as u8,
+        }
+    }
+
+    // Promedio de tres colores con pesos que suman 1, canal por canal y sin pasar a
+    // lineal: es lo que se usa por fragmento para los colores de vértice
+    pub fn weighted(colors: [&Color; 3], weights: [f32; 3]) -> Self {
+        let channel = |value: fn(&Color) -> u8| {
+            let sum: f32 = colors.iter().zip(weights).map(|(color, weight)| value(color) as f32 * weight).sum();
+            sum.round().clamp(0.0, 255.0) as u8
+        };
+        Color {
+            r: channel(|color| color.r),
+            g: channel(|color| color.g),
+            b: channel(|color| color.b),
+            a: channel(|color| color.a),
         }
     }
 
@@ -46,40 +203,163 @@ impl Color {
       }
     
     pub fn blend_multiply(&self, blend: &Color) -> Color {
+        if linear_math() {
+            return Color::from_linear(&self.to_linear().component_mul(&blend.to_linear())).with_alpha(self.a);
+        }
         Color::new(
             ((self.r as f32 * blend.r as f32) / 255.0) as u8,
             ((self.g as f32 * blend.g as f32) / 255.0) as u8,
             ((self.b as f32 * blend.b as f32) / 255.0) as u8
-        )
+        ).with_alpha(self.a)
     }
     
     pub fn blend_add(&self, blend: &Color) -> Color {
+        if linear_math() {
+            return Color::from_linear(&(self.to_linear() + blend.to_linear())).with_alpha(self.a);
+        }
         Color::new(
             (self.r as u16 + blend.r as u16).min(255) as u8,
             (self.g as u16 + blend.g as u16).min(255) as u8,
             (self.b as u16 + blend.b as u16).min(255) as u8
-        )
+        ).with_alpha(self.a)
     }
     
+    // Se queda con el canal más claro de los dos
+    pub fn blend_lighten(&self, blend: &Color) -> Color {
+        Color::new_rgba(self.r.max(blend.r), self.g.max(blend.g), self.b.max(blend.b), self.a)
+    }
+
     pub fn blend_subtract(&self, blend: &Color) -> Color {
-        let r = (self.r as i16 - blend.r as i16).max(0).min(255) as u8;
-        let g = (self.g as i16 - blend.g as i16).max(0).min(255) as u8;
-        let b = (self.b as i16 - blend.b as i16).max(0).min(255) as u8;
+        let r = (self.r as i16 - blend.r as i16).clamp(0, 255) as u8;
+        let g = (self.g as i16 - blend.g as i16).clamp(0, 255) as u8;
+        let b = (self.b as i16 - blend.b as i16).clamp(0, 255) as u8;
 
-        Color::new(r, g, b)
+        Color::new_rgba(r, g, b, self.a)
     }
 }
 
+// "#RGB", "#RRGGBB" o "#RRGGBBAA" (el numeral es opcional); en la forma corta cada
+// dígito se repite, como en CSS: "#f80" es "#ff8800"
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let digits = text.strip_prefix('#').unwrap_or(text);
+        // from_str_radix acepta un signo al principio, que aquí no es un dígito
+        let value = match digits.len() {
+            3 | 6 | 8 if digits.chars().all(|c| c.is_ascii_hexdigit()) => u32::from_str_radix(digits, 16).ok(),
+            _ => None,
+        };
+        match value {
+            Some(value) if digits.len() == 3 => {
+                let expand = |digit: u32| (digit * 0x11) as u8;
+                Ok(Color::new(expand(value >> 8), expand((value >> 4) & 0xF), expand(value & 0xF)))
+            }
+            Some(value) if digits.len() == 6 => Ok(Color::from_hex(value)),
+            Some(value) => Ok(Color::from_hex(value >> 8).with_alpha((value & 0xFF) as u8)),
+            None => Err(format!("color inválido '{}' (se espera #RGB, #RRGGBB o #RRGGBBAA)", text)),
+        }
+    }
+}
+
+// En la escena un color se escribe como "#RRGGBB" (o "#RGB") o como [r, g, b] / [r, g, b, a]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorValue {
+    Hex(String),
+    Rgb([u8; 3]),
+    Rgba([u8; 4]),
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match ColorValue::deserialize(deserializer)? {
+            ColorValue::Hex(text) => text.parse().map_err(de::Error::custom),
+            ColorValue::Rgb([r, g, b]) => Ok(Color::new(r, g, b)),
+            ColorValue::Rgba([r, g, b, a]) => Ok(Color::new_rgba(r, g, b, a)),
+        }
+    }
+}
+
+// Rampa de colores repartidos a la misma distancia entre t = 0 y t = 1. En la
+// escena es una lista de colores, p. ej. palette = ["#8b4513", "#ffa500", "#f5deb3"].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(from = "Vec<Color>")]
+pub struct Gradient {
+    colors: Vec<Color>,
+}
+
+impl From<Vec<Color>> for Gradient {
+    fn from(colors: Vec<Color>) -> Self {
+        Gradient { colors }
+    }
+}
+
+impl Gradient {
+    pub fn new(colors: Vec<Color>) -> Self {
+        Gradient { colors }
+    }
+
+    pub fn colors(&self) -> &[Color] {
+        &self.colors
+    }
+
+    pub fn sample(&self, t: f32) -> Color {
+        sample_ramp(&self.colors, t)
+    }
+}
+
+// Color en t de una rampa de colores equiespaciados (también sirve con arreglos constantes)
+pub fn sample_ramp(colors: &[Color], t: f32) -> Color {
+    match colors.len() {
+        0 => Color::black(),
+        1 => colors[0],
+        n => {
+            let scaled = t.clamp(0.0, 1.0) * (n - 1) as f32;
+            let index = (scaled.floor() as usize).min(n - 2);
+            colors[index].lerp(&colors[index + 1], scaled - index as f32)
+        }
+    }
+}
+
+fn decode_table() -> &'static [f32; 256] {
+    static TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        std::array::from_fn(|i| {
+            let value = i as f32 / 255.0;
+            if value <= 0.04045 { value / 12.92 } else { ((value + 0.055) / 1.055).powf(2.4) }
+        })
+    })
+}
+
+// Valores sRGB en la escala 0-255 sin redondear, para poder aplicar el tramado
+fn encode_table() -> &'static [f32] {
+    static TABLE: OnceLock<Vec<f32>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        (0..ENCODE_TABLE_SIZE)
+            .map(|i| {
+                let value = i as f32 / (ENCODE_TABLE_SIZE - 1) as f32;
+                let encoded = if value <= 0.0031308 { value * 12.92 } else { 1.055 * value.powf(1.0 / 2.4) - 0.055 };
+                encoded * 255.0
+            })
+            .collect()
+    })
+}
+
 use std::ops::Add;
 
 impl Add for Color {
     type Output = Color;
 
     fn add(self, other: Color) -> Color {
+        if linear_math() {
+            return Color::from_linear(&(self.to_linear() + other.to_linear())).with_alpha(self.a);
+        }
         Color {
             r: self.r.saturating_add(other.r),
             g: self.g.saturating_add(other.g),
             b: self.b.saturating_add(other.b),
+            a: self.a,
         }
     }
 }
@@ -90,16 +370,59 @@ impl Mul<f32> for Color {
     type Output = Color;
 
     fn mul(self, scalar: f32) -> Color {
+        if linear_math() {
+            return Color::from_linear(&(self.to_linear() * scalar)).with_alpha(self.a);
+        }
         Color {
             r: (self.r as f32 * scalar).clamp(0.0, 255.0) as u8,
             g: (self.g as f32 * scalar).clamp(0.0, 255.0) as u8,
             b: (self.b as f32 * scalar).clamp(0.0, 255.0) as u8,
+            a: self.a,
         }
     }
 }
 
 impl fmt::Display for Color {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Color(r: {}, g: {}, b: {})", self.r, self.g, self.b)
+        write!(f, "Color(r: {}, g: {}, b: {}, a: {})", self.r, self.g, self.b, self.a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> (u32, u8) {
+        let color: Color = text.parse().unwrap_or_else(|err| panic!("{}", err));
+        (color.to_hex(), color.alpha())
+    }
+
+    #[test]
+    fn parses_hex_forms() {
+        assert_eq!(parse("#f80"), (0xFF8800, 255));
+        assert_eq!(parse("0aF"), (0x00AAFF, 255));
+        assert_eq!(parse("#8b4513"), (0x8B4513, 255));
+        assert_eq!(parse("FFA500"), (0xFFA500, 255));
+        assert_eq!(parse("#10203080"), (0x102030, 0x80));
+        assert_eq!(parse("#ffffff00"), (0xFFFFFF, 0));
+    }
+
+    #[test]
+    fn rejects_invalid_hex() {
+        for text in ["", "#", "#ff", "#ffff", "#fffff", "#1234567", "#123456789", "#gg0000", "#+fff", "# 12345", "#ff00ñ"] {
+            assert!(text.parse::<Color>().is_err(), "'{}' no debería ser un color", text);
+        }
+    }
+
+    #[test]
+    fn deserializes_hex_and_components() {
+        #[derive(Deserialize)]
+        struct Palette {
+            colors: Vec<Color>,
+        }
+        let palette: Palette = toml::from_str(r##"colors = ["#f80", "#10203080", [1, 2, 3], [4, 5, 6, 7]]"##).unwrap();
+        let colors: Vec<(u32, u8)> = palette.colors.iter().map(|color| (color.to_hex(), color.alpha())).collect();
+        assert_eq!(colors, [(0xFF8800, 255), (0x102030, 0x80), (0x010203, 255), (0x040506, 7)]);
+        assert!(toml::from_str::<Palette>(r##"colors = ["#12345"]"##).is_err());
     }
 }
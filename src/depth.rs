@@ -0,0 +1,121 @@
+use rayon::prelude::*;
+
+// Comparación entre la profundidad de un fragmento y la guardada en el buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthTest {
+    Less,
+    LessEqual,
+    Always,
+}
+
+impl DepthTest {
+    fn passes(&self, depth: f32, stored: f32) -> bool {
+        match self {
+            DepthTest::Less => depth < stored,
+            DepthTest::LessEqual => depth <= stored,
+            DepthTest::Always => true,
+        }
+    }
+}
+
+// Z-buffer con prueba configurable. Con `write` desactivado los fragmentos
+// se prueban pero no actualizan la profundidad (útil para transparencias).
+pub struct DepthBuffer {
+    width: usize,
+    height: usize,
+    data: Vec<f32>,
+    test: DepthTest,
+    write: bool,
+}
+
+impl DepthBuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        DepthBuffer {
+            width,
+            height,
+            data: vec![f32::INFINITY; width * height],
+            test: DepthTest::Less,
+            write: true,
+        }
+    }
+
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.data = vec![f32::INFINITY; width * height];
+    }
+
+    pub fn clear(&mut self) {
+        self.data.fill(f32::INFINITY);
+    }
+
+    pub fn set_test(&mut self, test: DepthTest) {
+        self.test = test;
+    }
+
+    pub fn set_write(&mut self, write: bool) {
+        self.write = write;
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> f32 {
+        if x < self.width && y < self.height {
+            self.data[y * self.width + x]
+        } else {
+            f32::INFINITY
+        }
+    }
+
+    // Franjas de rows filas que se pueden usar en paralelo, con la prueba y la
+    // escritura configuradas en el buffer
+    pub fn bands_mut(&mut self, rows: usize) -> impl IndexedParallelIterator<Item = DepthBand<'_>> {
+        let (width, test, write) = (self.width, self.test, self.write);
+        self.data
+            .par_chunks_mut(width * rows)
+            .enumerate()
+            .map(move |(index, data)| DepthBand { y: index * rows, width, data, test, write })
+    }
+
+    // Aplica la prueba de profundidad y, si pasa y la escritura está activa,
+    // guarda la nueva profundidad. Devuelve si el fragmento es visible.
+    pub fn test_and_write(&mut self, x: usize, y: usize, depth: f32) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+
+        let index = y * self.width + x;
+        if !self.test.passes(depth, self.data[index]) {
+            return false;
+        }
+        if self.write {
+            self.data[index] = depth;
+        }
+        true
+    }
+}
+
+// Parte del z-buffer desde la fila y; las coordenadas siguen siendo las de pantalla
+pub struct DepthBand<'a> {
+    pub y: usize,
+    width: usize,
+    data: &'a mut [f32],
+    test: DepthTest,
+    write: bool,
+}
+
+impl DepthBand<'_> {
+    // Solo la prueba, para no sombrear fragmentos que van a quedar tapados
+    pub fn passes(&self, x: usize, y: usize, depth: f32) -> bool {
+        self.test.passes(depth, self.data[(y - self.y) * self.width + x])
+    }
+
+    pub fn test_and_write(&mut self, x: usize, y: usize, depth: f32) -> bool {
+        let index = (y - self.y) * self.width + x;
+        if !self.test.passes(depth, self.data[index]) {
+            return false;
+        }
+        if self.write {
+            self.data[index] = depth;
+        }
+        true
+    }
+}
@@ -0,0 +1,51 @@
+use std::io;
+use thiserror::Error;
+use crate::audio::AudioError;
+use crate::export::ExportError;
+use crate::grading::LutError;
+use crate::save::SaveError;
+use crate::scene::SceneError;
+use crate::shader_params::ShaderParamsError;
+
+// Error de todo el programa: junta los de cada módulo más los del arranque (archivos
+// de salida, la ventana, argumentos), para propagarlos con ? hasta main y mostrar
+// un mensaje legible en lugar de cortar con un panic
+#[derive(Debug, Error)]
+pub enum Error {
+    // Los errores de la escena son grandes (guardan el del modelo, el de TOML...):
+    // en una caja el Result de cada función queda chico
+    #[error(transparent)]
+    Scene(Box<SceneError>),
+    #[error(transparent)]
+    Save(Box<SaveError>),
+    #[error(transparent)]
+    ShaderParams(#[from] ShaderParamsError),
+    #[error(transparent)]
+    Lut(#[from] LutError),
+    #[error(transparent)]
+    Export(#[from] ExportError),
+    #[error(transparent)]
+    Audio(#[from] AudioError),
+    #[error("no se pudo escribir '{0}': {1}")]
+    Io(String, #[source] io::Error),
+    #[error("no se pudo guardar la imagen '{0}': {1}")]
+    Image(String, #[source] image::ImageError),
+    #[error("error de la ventana: {0}")]
+    Window(#[source] minifb::Error),
+    #[error("argumento inválido: {0}")]
+    Argument(String),
+}
+
+impl From<SceneError> for Error {
+    fn from(err: SceneError) -> Self {
+        Error::Scene(Box::new(err))
+    }
+}
+
+impl From<SaveError> for Error {
+    fn from(err: SaveError) -> Self {
+        Error::Save(Box::new(err))
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
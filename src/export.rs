@@ -0,0 +1,543 @@
+use image::{DynamicImage, ImageFormat, RgbImage, RgbaImage};
+use nalgebra_glm::{quat_angle_axis, Quat, Vec3};
+use serde_json::{json, Value};
+use thiserror::Error;
+use std::fs;
+use std::io::{self, Cursor};
+use std::path::Path;
+use crate::bake::{bake_rings, bake_surface};
+use crate::color::Color;
+use crate::lighting::PbrMaterial;
+use crate::mesh::Mesh;
+use crate::obj::ObjMaterial;
+use crate::planet::Planet;
+use crate::renderer::{BELT_BODY, SHIP_BODY};
+use crate::scene::Scene;
+use crate::shader_params::ShaderParams;
+
+// Samplers de las texturas: la superficie de los cuerpos y la tira de los anillos
+const SURFACE_SAMPLER: usize = 0;
+const RING_SAMPLER: usize = 1;
+
+// Colores fijos de lo que no tiene textura horneada (sRGB)
+const ROCK_COLOR: Color = Color::new(110, 104, 98);
+const METAL_COLOR: Color = Color::new(170, 172, 178);
+
+// Tipos de componente y destinos de los buffer views según la especificación
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+// Encabezados del formato binario (.glb)
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_JSON_CHUNK: u32 = 0x4E4F_534A;
+const GLB_BIN_CHUNK: u32 = 0x004E_4942;
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("no se pudo escribir '{0}': {1}")]
+    Io(String, #[source] io::Error),
+    #[error("no se pudo codificar la textura de '{0}': {1}")]
+    Image(String, #[source] image::ImageError),
+    #[error("no se pudo serializar el documento glTF: {0}")]
+    Json(#[source] serde_json::Error),
+}
+
+// Qué y cuánto se exporta
+#[derive(Debug, Clone)]
+pub struct GltfExport {
+    // Ancho de las texturas horneadas de cada cuerpo (el alto es la mitad)
+    pub texture_width: usize,
+    // Segundos de simulación que cubre la animación, desde start_time
+    pub duration: f32,
+    pub start_time: f32,
+    // Claves de animación por segundo de simulación
+    pub frame_rate: f32,
+}
+
+// Buffer binario del archivo y las vistas y accesores que lo describen
+struct Buffers {
+    data: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl Buffers {
+    fn new() -> Self {
+        Buffers {
+            data: Vec::new(),
+            views: Vec::new(),
+            accessors: Vec::new(),
+        }
+    }
+
+    // Agrega los bytes alineados a 4 y devuelve el índice de su buffer view
+    fn push_view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        self.data.resize(self.data.len().next_multiple_of(4), 0);
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": self.data.len(),
+            "byteLength": bytes.len(),
+        });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.data.extend_from_slice(bytes);
+        self.views.push(view);
+        self.views.len() - 1
+    }
+
+    // Accesor de floats de components componentes por elemento ("VEC3", "SCALAR"...)
+    fn push_floats(&mut self, values: &[f32], components: usize, kind: &str, target: Option<u32>, bounds: bool) -> usize {
+        let bytes: Vec<u8> = values.iter().flat_map(|value| value.to_le_bytes()).collect();
+        let view = self.push_view(&bytes, target);
+
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": values.len() / components,
+            "type": kind,
+        });
+        // POSITION y las entradas de las animaciones necesitan mínimo y máximo
+        if bounds {
+            let (min, max): (Vec<f32>, Vec<f32>) = (0..components)
+                .map(|component| {
+                    values.iter().skip(component).step_by(components).fold(
+                        (f32::INFINITY, f32::NEG_INFINITY),
+                        |(min, max), value| (min.min(*value), max.max(*value)),
+                    )
+                })
+                .unzip();
+            accessor["min"] = json!(min);
+            accessor["max"] = json!(max);
+        }
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn push_indices(&mut self, indices: &[u32]) -> usize {
+        let bytes: Vec<u8> = indices.iter().flat_map(|index| index.to_le_bytes()).collect();
+        let view = self.push_view(&bytes, Some(ELEMENT_ARRAY_BUFFER));
+        self.accessors.push(json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_INT,
+            "count": indices.len(),
+            "type": "SCALAR",
+        }));
+        self.accessors.len() - 1
+    }
+}
+
+// Lo que se exporta como un nodo, en el orden de los nodos del archivo
+#[derive(Debug, Clone, Copy)]
+enum Part {
+    Body(usize),
+    Rings(usize),
+    Asteroid(usize),
+    Ship,
+}
+
+struct Node {
+    name: String,
+    part: Part,
+    mesh: usize,
+}
+
+// Las listas del documento que se llenan al recorrer la escena
+struct Document {
+    buffers: Buffers,
+    images: Vec<Value>,
+    textures: Vec<Value>,
+    materials: Vec<Value>,
+    meshes: Vec<Value>,
+}
+
+impl Document {
+    fn new() -> Self {
+        Document {
+            buffers: Buffers::new(),
+            images: Vec::new(),
+            textures: Vec::new(),
+            materials: Vec::new(),
+            meshes: Vec::new(),
+        }
+    }
+
+    // Guarda la imagen como PNG dentro del buffer y devuelve el índice de su textura
+    fn push_texture(&mut self, name: &str, image: DynamicImage, sampler: usize) -> Result<usize, ExportError> {
+        let mut png = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .map_err(|err| ExportError::Image(name.to_string(), err))?;
+
+        let view = self.buffers.push_view(&png, None);
+        self.images.push(json!({ "bufferView": view, "mimeType": "image/png", "name": name }));
+        self.textures.push(json!({ "source": self.images.len() - 1, "sampler": sampler }));
+        Ok(self.textures.len() - 1)
+    }
+
+    fn push_material(&mut self, material: Value) -> usize {
+        self.materials.push(material);
+        self.materials.len() - 1
+    }
+
+    // Una malla con una primitiva por cada par de malla y material
+    fn push_mesh(&mut self, name: &str, primitives: &[(&Mesh, usize)]) -> usize {
+        let primitives: Vec<Value> = primitives
+            .iter()
+            .map(|&(mesh, material)| {
+                let mut primitive = push_mesh(&mut self.buffers, mesh);
+                primitive["material"] = json!(material);
+                primitive
+            })
+            .collect();
+        self.meshes.push(json!({ "name": name, "primitives": primitives }));
+        self.meshes.len() - 1
+    }
+}
+
+// Escribe el sistema en formato glTF 2.0 para abrirlo en Blender o en un motor de
+// juegos: cada cuerpo con su malla (el relieve desplazado o su modelo) y su textura
+// horneada desde el shader, los anillos, las rocas del cinturón y la nave, más una
+// animación con la órbita y el giro de cada uno muestreados. Con extensión .glb todo
+// va en un solo archivo; con .gltf el buffer se guarda al lado, en un .bin con el
+// mismo nombre. La escena se simula durante la exportación, así que queda al final
+// de la animación.
+pub fn export_gltf(scene: &mut Scene, params: &ShaderParams, settings: &GltfExport, path: &str) -> Result<(), ExportError> {
+    let mut document = Document::new();
+    let mut nodes = Vec::new();
+
+    for (index, planet) in scene.planets.iter().enumerate() {
+        let baked = bake_surface(planet, params, settings.texture_width, settings.start_time, false);
+        let image = RgbImage::from_fn(baked.width as u32, baked.height as u32, |x, y| {
+            let pixel = baked.albedo[y as usize * baked.width + x as usize];
+            image::Rgb([(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8])
+        });
+        let texture = document.push_texture(&planet.name, image.into(), SURFACE_SAMPLER)?;
+        let material = document.push_material(material(planet, texture));
+        let mesh = document.push_mesh(&planet.name, &[(planet.mesh.finest(), material)]);
+        nodes.push(Node { name: planet.name.clone(), part: Part::Body(index), mesh });
+
+        // Los anillos, una tira con los colores del shader de la u interior a la exterior
+        if let (Some(rings), Some(colors)) = (&planet.rings, bake_rings(planet, params, settings.texture_width, settings.start_time)) {
+            let name = format!("Anillos de {}", planet.name);
+            let image = RgbaImage::from_fn(colors.len() as u32, 1, |x, _| {
+                let color = colors[x as usize];
+                let pixel = color.to_hex();
+                image::Rgba([(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8, color.alpha()])
+            });
+            let texture = document.push_texture(&name, image.into(), RING_SAMPLER)?;
+            let material = document.push_material(ring_material(&name, texture));
+            let mesh = document.push_mesh(&name, &[(&rings.mesh, material)]);
+            nodes.push(Node { name, part: Part::Rings(index), mesh });
+        }
+    }
+
+    // Las rocas comparten sus pocas formas, como al dibujarlas
+    if let Some(belt) = &scene.belt {
+        let material = document.push_material(rock_material());
+        let shapes: Vec<usize> = belt.shapes
+            .iter()
+            .enumerate()
+            .map(|(index, shape)| document.push_mesh(&format!("{} {}", BELT_BODY, index), &[(&shape.mesh, material)]))
+            .collect();
+        for (index, asteroid) in belt.asteroids.iter().enumerate() {
+            nodes.push(Node { name: format!("{} {}", BELT_BODY, index), part: Part::Asteroid(index), mesh: shapes[asteroid.shape] });
+        }
+    }
+
+    if let Some(ship) = &scene.ship {
+        let materials: Vec<usize> = ship.model.groups
+            .iter()
+            .map(|group| document.push_material(model_material(ship.model.material(group), ship.pbr)))
+            .collect();
+        let primitives: Vec<(&Mesh, usize)> = ship.model.groups
+            .iter()
+            .zip(materials)
+            .map(|(group, material)| (&group.mesh, material))
+            .collect();
+        let mesh = document.push_mesh(SHIP_BODY, &primitives);
+        nodes.push(Node { name: SHIP_BODY.to_string(), part: Part::Ship, mesh });
+    }
+
+    let (nodes, animation) = animate(scene, &mut document.buffers, settings, &nodes);
+    let buffers = document.buffers;
+    let mut json = json!({
+        "asset": { "version": "2.0", "generator": "planet_renderer" },
+        "scene": 0,
+        "scenes": [{ "nodes": (0..nodes.len()).collect::<Vec<_>>() }],
+        "nodes": nodes,
+        "meshes": document.meshes,
+        "materials": document.materials,
+        "textures": document.textures,
+        // La costura de la esfera se repite en u, así que la superficie se envuelve en
+        // horizontal; en la tira de los anillos u es el radio y v da la vuelta
+        "samplers": [
+            { "magFilter": 9729, "minFilter": 9987, "wrapS": 10497, "wrapT": 33071 },
+            { "magFilter": 9729, "minFilter": 9729, "wrapS": 33071, "wrapT": 10497 },
+        ],
+        "images": document.images,
+        "animations": [animation],
+        "accessors": buffers.accessors,
+        "bufferViews": buffers.views,
+    });
+
+    let write = |path: &str, bytes: &[u8]| fs::write(path, bytes).map_err(|err| ExportError::Io(path.to_string(), err));
+    if Path::new(path).extension().is_some_and(|extension| extension.eq_ignore_ascii_case("glb")) {
+        json["buffers"] = json!([{ "byteLength": buffers.data.len() }]);
+        write(path, &glb(&json, buffers.data)?)
+    } else {
+        let bin_path = Path::new(path).with_extension("bin");
+        let uri = bin_path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        json["buffers"] = json!([{ "byteLength": buffers.data.len(), "uri": uri }]);
+        write(&bin_path.to_string_lossy(), &buffers.data)?;
+        write(path, serde_json::to_string_pretty(&json).map_err(ExportError::Json)?.as_bytes())
+    }
+}
+
+// Atributos e índices de una malla
+fn push_mesh(buffers: &mut Buffers, mesh: &Mesh) -> Value {
+    let positions: Vec<f32> = mesh.vertices.iter().flat_map(|vertex| [vertex.position.x, vertex.position.y, vertex.position.z]).collect();
+    let normals: Vec<f32> = mesh.vertices.iter().flat_map(|vertex| [vertex.normal.x, vertex.normal.y, vertex.normal.z]).collect();
+    let tex_coords: Vec<f32> = mesh.vertices.iter().flat_map(|vertex| [vertex.tex_coords.x, vertex.tex_coords.y]).collect();
+
+    let position = buffers.push_floats(&positions, 3, "VEC3", Some(ARRAY_BUFFER), true);
+    let normal = buffers.push_floats(&normals, 3, "VEC3", Some(ARRAY_BUFFER), false);
+    let tex_coord = buffers.push_floats(&tex_coords, 2, "VEC2", Some(ARRAY_BUFFER), false);
+    let indices = buffers.push_indices(&mesh.indices);
+
+    json!({
+        "attributes": { "POSITION": position, "NORMAL": normal, "TEXCOORD_0": tex_coord },
+        "indices": indices,
+    })
+}
+
+// Material PBR con la textura horneada; las estrellas además la emiten
+fn material(planet: &Planet, texture: usize) -> Value {
+    let mut material = json!({
+        "name": planet.name,
+        "pbrMetallicRoughness": {
+            "baseColorTexture": { "index": texture },
+            "baseColorFactor": [1.0, 1.0, 1.0, planet.opacity.clamp(0.0, 1.0)],
+            "metallicFactor": 0.0,
+            "roughnessFactor": 0.9,
+        },
+    });
+    if planet.emissive {
+        material["emissiveTexture"] = json!({ "index": texture });
+        material["emissiveFactor"] = json!([1.0, 1.0, 1.0]);
+    }
+    if planet.transparent && planet.opacity < 1.0 {
+        material["alphaMode"] = json!("BLEND");
+    }
+    if planet.double_sided {
+        material["doubleSided"] = json!(true);
+    }
+    material
+}
+
+// Los anillos se ven de los dos lados y sus huecos dejan pasar la luz
+fn ring_material(name: &str, texture: usize) -> Value {
+    json!({
+        "name": name,
+        "pbrMetallicRoughness": {
+            "baseColorTexture": { "index": texture },
+            "metallicFactor": 0.0,
+            "roughnessFactor": 0.9,
+        },
+        "alphaMode": "BLEND",
+        "doubleSided": true,
+    })
+}
+
+// Las rocas del cinturón, de un solo color (el shader de cráteres no se hornea)
+fn rock_material() -> Value {
+    let color = ROCK_COLOR.to_linear();
+    json!({
+        "name": BELT_BODY,
+        "pbrMetallicRoughness": {
+            "baseColorFactor": [color.x, color.y, color.z, 1.0],
+            "metallicFactor": 0.0,
+            "roughnessFactor": 1.0,
+        },
+    })
+}
+
+// Un material del modelo de la nave, con su color difuso en espacio lineal como pide
+// glTF; las partes sin material son del metal con que las dibuja el renderizador
+fn model_material(material: Option<&ObjMaterial>, pbr: Option<PbrMaterial>) -> Value {
+    let (name, color, pbr) = match material {
+        Some(material) => (
+            material.name.as_str(),
+            material.diffuse,
+            material.pbr.or(pbr).unwrap_or(PbrMaterial { metallic: 0.0, roughness: 0.5 }),
+        ),
+        None => (SHIP_BODY, METAL_COLOR, pbr.unwrap_or(PbrMaterial { metallic: 1.0, roughness: 0.35 })),
+    };
+    let color = color.to_linear();
+    json!({
+        "name": name,
+        "pbrMetallicRoughness": {
+            "baseColorFactor": [color.x, color.y, color.z, 1.0],
+            "metallicFactor": pbr.metallic,
+            "roughnessFactor": pbr.roughness,
+        },
+    })
+}
+
+// Posición, giro (en los ángulos de create_model_matrix) y escala de una parte
+fn pose(scene: &Scene, part: Part) -> (Vec3, Vec3, f32) {
+    match part {
+        Part::Body(index) => {
+            let planet = &scene.planets[index];
+            (planet.translation, planet.rotation, planet.scale)
+        }
+        // Como al dibujarlos: siguen la inclinación del eje del planeta pero no su giro
+        Part::Rings(index) => {
+            let planet = &scene.planets[index];
+            let tilt = planet.rings.as_ref().map_or(0.0, |rings| rings.tilt.to_radians());
+            (planet.translation, Vec3::new(tilt, 0.0, planet.rotation.z), planet.scale)
+        }
+        Part::Asteroid(index) => {
+            let asteroid = &scene.belt.as_ref().expect("el cinturón exportado sigue en la escena").asteroids[index];
+            (asteroid.translation, asteroid.rotation, asteroid.scale)
+        }
+        Part::Ship => {
+            let ship = scene.ship.as_ref().expect("la nave exportada sigue en la escena");
+            (ship.position, ship.rotation(), ship.scale)
+        }
+    }
+}
+
+// Simula la escena durante la animación y devuelve los nodos (en su posición inicial)
+// y la animación con la traslación y la rotación de cada uno en cada clave. Las lunas
+// se exportan con su posición en el mundo, sin jerarquía.
+fn animate(scene: &mut Scene, buffers: &mut Buffers, settings: &GltfExport, nodes: &[Node]) -> (Vec<Value>, Value) {
+    let frames = ((settings.duration * settings.frame_rate).ceil() as usize).max(1);
+    let step = settings.duration / frames as f32;
+
+    let mut times = Vec::with_capacity(frames + 1);
+    let mut translations = vec![Vec::with_capacity((frames + 1) * 3); nodes.len()];
+    let mut rotations: Vec<Vec<Quat>> = vec![Vec::with_capacity(frames + 1); nodes.len()];
+    for frame in 0..=frames {
+        let time = frame as f32 * step;
+        scene.update(settings.start_time + time, if frame == 0 { 0.0 } else { step });
+        times.push(time);
+
+        for (index, node) in nodes.iter().enumerate() {
+            let (translation, rotation, _) = pose(scene, node.part);
+            translations[index].extend([translation.x, translation.y, translation.z]);
+
+            // Del lado del cuaternión más cercano al anterior, para que la interpolación no dé la vuelta larga
+            let rotation = euler_to_quat(&rotation);
+            let rotation = match rotations[index].last() {
+                Some(previous) if previous.coords.dot(&rotation.coords) < 0.0 => -rotation,
+                _ => rotation,
+            };
+            rotations[index].push(rotation);
+        }
+    }
+
+    let input = buffers.push_floats(&times, 1, "SCALAR", None, true);
+    let mut samplers = Vec::new();
+    let mut channels = Vec::new();
+    let mut values = Vec::new();
+    for (index, node) in nodes.iter().enumerate() {
+        let (_, _, scale) = pose(scene, node.part);
+        let rotation: Vec<f32> = rotations[index].iter().flat_map(|rotation| [rotation.i, rotation.j, rotation.k, rotation.w]).collect();
+        values.push(json!({
+            "name": node.name,
+            "mesh": node.mesh,
+            "translation": &translations[index][0..3],
+            "rotation": &rotation[0..4],
+            "scale": [scale, scale, scale],
+        }));
+
+        let translation = buffers.push_floats(&translations[index], 3, "VEC3", None, false);
+        let rotation = buffers.push_floats(&rotation, 4, "VEC4", None, false);
+        for (output, property) in [(translation, "translation"), (rotation, "rotation")] {
+            channels.push(json!({ "sampler": samplers.len(), "target": { "node": index, "path": property } }));
+            samplers.push(json!({ "input": input, "output": output, "interpolation": "LINEAR" }));
+        }
+    }
+
+    (values, json!({ "name": "orbits", "samplers": samplers, "channels": channels }))
+}
+
+// La rotación de create_model_matrix (Rz * Ry * Rx) como cuaternión
+fn euler_to_quat(rotation: &Vec3) -> Quat {
+    quat_angle_axis(rotation.z, &Vec3::z())
+        * quat_angle_axis(rotation.y, &Vec3::y())
+        * quat_angle_axis(rotation.x, &Vec3::x())
+}
+
+// Archivo .glb: encabezado, el JSON y el buffer, cada parte rellenada a 4 bytes
+fn glb(document: &Value, mut data: Vec<u8>) -> Result<Vec<u8>, ExportError> {
+    let mut json = serde_json::to_vec(document).map_err(ExportError::Json)?;
+    json.resize(json.len().next_multiple_of(4), b' ');
+    data.resize(data.len().next_multiple_of(4), 0);
+
+    let length = 12 + 8 + json.len() + 8 + data.len();
+    let mut bytes = Vec::with_capacity(length);
+    for value in [GLB_MAGIC, 2, length as u32, json.len() as u32, GLB_JSON_CHUNK] {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes.extend_from_slice(&json);
+    for value in [data.len() as u32, GLB_BIN_CHUNK] {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes.extend_from_slice(&data);
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::Vec2;
+    use crate::assets::AssetManager;
+    use crate::bake::{sphere_point, texel_center};
+    use crate::save::SceneSource;
+
+    const TEXTURE_WIDTH: usize = 64;
+
+    // Cada vértice de un cuerpo generado cae en el píxel de la textura horneada que
+    // se evaluó en su misma dirección, y ningún triángulo cruza la costura
+    #[test]
+    fn exported_uvs_match_the_baked_texture() {
+        let mut scene = SceneSource::Generated { seed: 7 }.build(&mut AssetManager::new()).unwrap();
+        let names: Vec<String> = scene.planets.iter().map(|planet| planet.name.clone()).collect();
+        let settings = GltfExport { texture_width: TEXTURE_WIDTH, duration: 0.5, start_time: 0.0, frame_rate: 4.0 };
+        let path = std::env::temp_dir().join(format!("planet_renderer_export_{}.glb", std::process::id()));
+        export_gltf(&mut scene, &ShaderParams::default(), &settings, &path.to_string_lossy()).unwrap();
+        let (document, buffers, _) = gltf::import(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let (width, height) = (TEXTURE_WIDTH, TEXTURE_WIDTH / 2);
+        // Lo más que se aleja un punto del píxel de su centro, en radianes
+        let tolerance = 2.0 * std::f32::consts::PI / width as f32;
+        let mut bodies = 0;
+        for node in document.nodes().filter(|node| node.name().is_some_and(|name| names.iter().any(|body| body == name))) {
+            let primitive = node.mesh().unwrap().primitives().next().unwrap();
+            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+            let positions: Vec<Vec3> = reader.read_positions().unwrap().map(Vec3::from).collect();
+            let tex_coords: Vec<Vec2> = reader.read_tex_coords(0).unwrap().into_f32().map(Vec2::from).collect();
+
+            for (position, uv) in positions.iter().zip(&tex_coords) {
+                // La textura se repite en u, así que la copia de la costura (u + 1) lee la primera columna
+                let x = ((uv.x * width as f32).floor() as usize) % width;
+                let y = ((uv.y * height as f32).floor() as usize).min(height - 1);
+                let sampled = sphere_point(texel_center(x, y, width, height));
+                let angle = sampled.angle(&position.normalize());
+                assert!(angle < tolerance, "{:?}: el vértice {:?} con uv {:?} cae a {} rad de su píxel", node.name(), position, uv, angle);
+            }
+            for triangle in reader.read_indices().unwrap().into_u32().collect::<Vec<_>>().chunks(3) {
+                let us = [0, 1, 2].map(|corner| tex_coords[triangle[corner] as usize].x);
+                let span = us.iter().copied().fold(f32::NEG_INFINITY, f32::max) - us.iter().copied().fold(f32::INFINITY, f32::min);
+                assert!(span <= 0.5, "{:?}: el triángulo {:?} cruza la costura con u = {:?}", node.name(), triangle, us);
+            }
+            bodies += 1;
+        }
+        assert_eq!(bodies, names.len());
+    }
+}
@@ -0,0 +1,42 @@
+use nalgebra_glm::Vec3;
+use serde::Deserialize;
+use crate::color::Color;
+
+// Niebla de profundidad: a partir de start (distancia a la cámara) los cuerpos se
+// funden de a poco con el color del espacio, hasta max_amount en end
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct Fog {
+    pub color: Color,
+    pub start: f32,
+    pub end: f32,
+    pub max_amount: f32,
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Fog {
+            color: Color::new(6, 8, 20),
+            start: 30.0,
+            end: 120.0,
+            max_amount: 0.7,
+        }
+    }
+}
+
+impl Fog {
+    // Fracción de niebla a la distancia dada, con una transición suave entre start y end
+    pub fn amount(&self, distance: f32) -> f32 {
+        let t = ((distance - self.start) / (self.end - self.start).max(f32::EPSILON)).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t) * self.max_amount
+    }
+
+    // Mezcla un color lineal (HDR) con el de la niebla
+    pub fn apply(&self, color: Vec3, distance: f32) -> Vec3 {
+        let amount = self.amount(distance);
+        if amount <= 0.0 {
+            return color;
+        }
+        color.lerp(&self.color.to_linear(), amount)
+    }
+}
@@ -1,6 +1,7 @@
 
 use nalgebra_glm::{Vec2, Vec3};
 use crate::color::Color;
+use crate::lighting::MAX_LIGHTS;
 
 pub struct Fragment {
     pub position: Vec2,
@@ -8,18 +9,11 @@ pub struct Fragment {
     pub depth: f32,
     pub normal: Vec3,
     pub intensity: f32,
-    pub vertex_position: Vec3,
+    // Fracción de cada luz que llega al fragmento (1 = iluminado, 0 = en sombra)
+    pub light_visibility: [f32; MAX_LIGHTS],
+    pub model_position: Vec3,
+    pub world_position: Vec3,
+    pub view_dir: Vec3,
+    pub light_dir: Vec3,
+    pub tex_coords: Vec2,
 }
-
-impl Fragment {
-    pub fn new(x: f32, y: f32, color: Color, depth: f32, normal: Vec3, intensity: f32, vertex_position: Vec3,) -> Self {
-        Fragment {
-            position: Vec2::new(x, y),
-            color,
-            depth,
-            normal,
-            intensity,
-            vertex_position
-        }
-    }
-}
\ No newline at end of file
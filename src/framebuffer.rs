@@ -1,10 +1,14 @@
+use nalgebra_glm::Vec3;
+use rayon::prelude::*;
+use crate::color::Color;
+use crate::depth::{DepthBand, DepthBuffer};
+
+// Imagen final en el formato de la ventana (0xRRGGBB), donde también se escribe el HUD
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
     pub buffer: Vec<u32>,
-    pub zbuffer: Vec<f32>,
     background_color: u32,
-    current_color: u32,
 }
 
 // Implementación del método set_pixel
@@ -24,37 +28,107 @@ impl Framebuffer {
             width,
             height,
             buffer: vec![0; width * height],
-            zbuffer: vec![f32::INFINITY; width * height],
             background_color: 0x000000,
-            current_color: 0xFFFFFF
         }
     }
 
+    // Reasigna el buffer para un nuevo tamaño
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.buffer = vec![self.background_color; width * height];
+    }
+
     pub fn clear(&mut self) {
         for pixel in self.buffer.iter_mut() {
             *pixel = self.background_color;
         }
-        for depth in self.zbuffer.iter_mut() {
-            *depth = f32::INFINITY;
+    }
+
+    pub fn set_background_color(&mut self, color: u32) {
+        self.background_color = color;
+    }
+}
+
+// Destino interno del render: color lineal en punto flotante, sin tope en 1 (HDR),
+// y su profundidad. Se lleva al rango de la pantalla al resolverlo a un Framebuffer.
+pub struct HdrFramebuffer {
+    pub width: usize,
+    pub height: usize,
+    pub buffer: Vec<Vec3>,
+    pub depth: DepthBuffer,
+}
+
+impl HdrFramebuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        HdrFramebuffer {
+            width,
+            height,
+            buffer: vec![Vec3::zeros(); width * height],
+            depth: DepthBuffer::new(width, height),
         }
     }
 
-    pub fn point(&mut self, x: usize, y: usize, depth: f32) {
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.buffer = vec![Vec3::zeros(); width * height];
+        self.depth.resize(width, height);
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.par_iter_mut().for_each(|pixel| *pixel = Vec3::zeros());
+        self.depth.clear();
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: Vec3) {
         if x < self.width && y < self.height {
+            self.buffer[y * self.width + x] = color;
+        }
+    }
+
+    // Mezcla el color (sRGB) con el píxel existente según su alfa
+    pub fn blend_point(&mut self, x: usize, y: usize, depth: f32, color: Color) {
+        if self.depth.test_and_write(x, y, depth) {
             let index = y * self.width + x;
+            let alpha = color.alpha() as f32 / 255.0;
+            self.buffer[index] = self.buffer[index].lerp(&color.to_linear(), alpha);
+        }
+    }
 
-            if self.zbuffer[index] > depth {
-                self.buffer[index] = self.current_color;
-                self.zbuffer[index] = depth;
-            }
+    // Franjas horizontales de rows filas (color y profundidad) para dibujar en paralelo
+    pub fn bands_mut(&mut self, rows: usize) -> impl IndexedParallelIterator<Item = FramebufferBand<'_>> {
+        let width = self.width;
+        self.buffer
+            .par_chunks_mut(width * rows)
+            .zip(self.depth.bands_mut(rows))
+            .map(move |(buffer, depth)| FramebufferBand { width, buffer, depth })
+    }
+}
+
+// Filas consecutivas del framebuffer, desde depth.y; se escriben con coordenadas de pantalla
+pub struct FramebufferBand<'a> {
+    width: usize,
+    buffer: &'a mut [Vec3],
+    pub depth: DepthBand<'a>,
+}
+
+impl FramebufferBand<'_> {
+    pub fn point(&mut self, x: usize, y: usize, depth: f32, color: Vec3) {
+        if self.depth.test_and_write(x, y, depth) {
+            self.buffer[(y - self.depth.y) * self.width + x] = color;
         }
     }
 
-    pub fn set_background_color(&mut self, color: u32) {
-        self.background_color = color;
+    pub fn blend_point(&mut self, x: usize, y: usize, depth: f32, color: Vec3, alpha: f32) {
+        if self.depth.test_and_write(x, y, depth) {
+            let index = (y - self.depth.y) * self.width + x;
+            self.buffer[index] = self.buffer[index].lerp(&color, alpha);
+        }
     }
 
-    pub fn set_current_color(&mut self, color: u32) {
-        self.current_color = color;
+    // Para la vista de sobredibujo: el píxel guarda en x cuántos fragmentos cayeron en él
+    pub fn count_fragment(&mut self, x: usize, y: usize) {
+        self.buffer[(y - self.depth.y) * self.width + x].x += 1.0;
     }
-}
\ No newline at end of file
+}
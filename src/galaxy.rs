@@ -0,0 +1,177 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f32::consts::TAU;
+use crate::color::Color;
+use crate::font::{draw_text, GLYPH_HEIGHT};
+use crate::framebuffer::{Framebuffer, HdrFramebuffer};
+use crate::generator::generate_system;
+use crate::billboard::{draw_billboard, Sprite};
+use crate::save::SceneSource;
+use crate::scene::Scene;
+
+// Forma de la galaxia del mapa, en unidades del mapa
+const GALAXY_RADIUS: f32 = 30.0;
+const GALAXY_THICKNESS: f32 = 1.5;
+const ARMS: usize = 2;
+// Vueltas que da cada brazo desde el centro hasta el borde
+const ARM_TWIST: f32 = 0.8;
+const DUST_COUNT: usize = 2500;
+
+const SYSTEM_SIZE: f32 = 0.5;
+// Color en el mapa de una estrella sin tinte propio
+const DEFAULT_STAR_COLOR: Color = Color::new(255, 200, 120);
+const LABEL_COLOR: u32 = 0xDDE6FF;
+const CURRENT_COLOR: u32 = 0x7FFFA0;
+const LABEL_SCALE: usize = 1;
+// Distancia máxima (en píxeles) de un clic a un sistema para seleccionarlo
+const PICK_RADIUS: f32 = 20.0;
+
+// Un sistema del mapa: su escena y dónde está dentro de la galaxia
+pub struct StarSystem {
+    pub name: String,
+    pub source: SceneSource,
+    pub position: Vec3,
+    pub color: Color,
+    pub binary: bool,
+}
+
+impl StarSystem {
+    // El sistema de una escena ya cargada, con el nombre y color de su primera estrella
+    pub fn from_scene(scene: &Scene, source: SceneSource) -> Self {
+        let stars: Vec<_> = scene.planets.iter().filter(|planet| planet.emissive).collect();
+        StarSystem {
+            name: stars.first().map(|star| star.name.clone()).unwrap_or_else(|| "INICIO".to_string()),
+            source,
+            position: Vec3::zeros(),
+            color: stars.first().and_then(|star| star.tint).unwrap_or(DEFAULT_STAR_COLOR),
+            binary: stars.len() > 1,
+        }
+    }
+}
+
+struct Dust {
+    position: Vec3,
+    color: Color,
+}
+
+// Mapa de varios sistemas sobre una galaxia espiral; el primero es el sistema de partida
+pub struct Galaxy {
+    pub systems: Vec<StarSystem>,
+    dust: Vec<Dust>,
+}
+
+impl Galaxy {
+    pub fn generate(seed: u64, count: usize, home: StarSystem) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let mut systems = vec![StarSystem {
+            name: home.name.to_uppercase(),
+            position: spiral_point(&mut rng, 0.35),
+            ..home
+        }];
+        while systems.len() < count.max(1) {
+            let system_seed: u64 = rng.gen();
+            // Solo se genera la descripción, sin mallas: basta para el nombre y el color
+            let config = generate_system(system_seed);
+            let stars: Vec<_> = config.planets.iter().filter(|planet| planet.emissive).collect();
+            let color = stars[0].tint.map(|[r, g, b]| Color::new(r, g, b)).unwrap_or(DEFAULT_STAR_COLOR);
+            let t = rng.gen_range(0.2..1.0);
+
+            systems.push(StarSystem {
+                name: stars[0].name.to_uppercase(),
+                source: SceneSource::Generated { seed: system_seed },
+                position: spiral_point(&mut rng, t),
+                color,
+                binary: stars.len() > 1,
+            });
+        }
+
+        // Polvo de estrellas que dibuja los brazos; más denso y cálido hacia el centro
+        let dust = (0..DUST_COUNT)
+            .map(|_| {
+                let t = rng.gen_range(0.0f32..1.0).powf(0.7);
+                Dust {
+                    position: spiral_point(&mut rng, t),
+                    color: Color::new(255, 210, 160).lerp(&Color::new(150, 170, 255), t),
+                }
+            })
+            .collect();
+
+        Galaxy { systems, dust }
+    }
+
+    // Índice del sistema en el que está la escena actual, si figura en el mapa
+    pub fn find(&self, source: &SceneSource) -> Option<usize> {
+        self.systems.iter().position(|system| &system.source == source)
+    }
+
+    // Sistema más cercano en pantalla a un clic
+    pub fn pick(&self, x: f32, y: f32, view_projection: &Mat4, viewport_matrix: &Mat4) -> Option<usize> {
+        self.systems
+            .iter()
+            .enumerate()
+            .filter_map(|(index, system)| {
+                let screen = project(system.position, view_projection, viewport_matrix)?;
+                let distance = ((screen.x - x).powi(2) + (screen.y - y).powi(2)).sqrt();
+                (distance < PICK_RADIUS).then_some((index, distance))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index)
+    }
+
+    // Dibuja el polvo y los sistemas; el seleccionado se ve más grande
+    pub fn render(
+        &self,
+        framebuffer: &mut HdrFramebuffer,
+        view_matrix: &Mat4,
+        projection_matrix: &Mat4,
+        viewport_matrix: &Mat4,
+        selected: usize,
+    ) {
+        for dust in self.dust.iter() {
+            draw_billboard(framebuffer, dust.position, 0.12, &Sprite::disc(dust.color.with_alpha(90)), view_matrix, projection_matrix, viewport_matrix);
+        }
+
+        for (index, system) in self.systems.iter().enumerate() {
+            let size = if index == selected { SYSTEM_SIZE * 2.0 } else { SYSTEM_SIZE };
+            draw_billboard(framebuffer, system.position, size, &Sprite::disc(system.color.with_alpha(255)), view_matrix, projection_matrix, viewport_matrix);
+        }
+    }
+
+    // Nombres de los sistemas, escritos sobre la imagen final para que no dependan de
+    // la escala del render; el sistema actual se marca con otro color
+    pub fn draw_labels(&self, framebuffer: &mut Framebuffer, view_projection: &Mat4, viewport_matrix: &Mat4, current: Option<usize>) {
+        for (index, system) in self.systems.iter().enumerate() {
+            let screen = match project(system.position, view_projection, viewport_matrix) {
+                Some(screen) if screen.x >= 0.0 && screen.y >= GLYPH_HEIGHT as f32 => screen,
+                _ => continue,
+            };
+
+            let mut label = system.name.clone();
+            if system.binary {
+                label.push_str(" (2)");
+            }
+            let color = if current == Some(index) { CURRENT_COLOR } else { LABEL_COLOR };
+            draw_text(framebuffer, screen.x as usize + 8, screen.y as usize - GLYPH_HEIGHT, &label, color, LABEL_SCALE);
+        }
+    }
+}
+
+// Punto sobre uno de los brazos a una fracción t del radio, con algo de dispersión
+fn spiral_point(rng: &mut StdRng, t: f32) -> Vec3 {
+    let arm = rng.gen_range(0..ARMS) as f32;
+    let angle = TAU * (arm / ARMS as f32 + t * ARM_TWIST) + rng.gen_range(-0.3..0.3);
+    let radius = GALAXY_RADIUS * t + rng.gen_range(-1.5..1.5);
+    let height = rng.gen_range(-0.5f32..0.5) * GALAXY_THICKNESS * (1.0 - t);
+    Vec3::new(radius * angle.cos(), height, radius * angle.sin())
+}
+
+fn project(point: Vec3, view_projection: &Mat4, viewport_matrix: &Mat4) -> Option<Vec3> {
+    let clip = view_projection * Vec4::new(point.x, point.y, point.z, 1.0);
+    if clip.w <= 0.0 {
+        return None;
+    }
+    let screen = viewport_matrix * Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+    Some(Vec3::new(screen.x, screen.y, screen.z))
+}
@@ -0,0 +1,104 @@
+use nalgebra_glm::{Vec2, Vec3};
+use rayon::prelude::*;
+use crate::color::Color;
+use crate::fragment::Fragment;
+use crate::lighting::MAX_LIGHTS;
+
+// Lo que el paso de geometría deja en un píxel: la superficie visible y todo lo que
+// su shader necesita para evaluarse después, sin volver a rasterizar
+#[derive(Debug, Clone, Copy)]
+pub struct Surface {
+    // Índice de la llamada de dibujo (objeto, shader y uniforms) a la que pertenece
+    pub draw: u32,
+    pub depth: f32,
+    pub normal: Vec3,
+    pub world_position: Vec3,
+    pub model_position: Vec3,
+    pub tex_coords: Vec2,
+    // Color de vértice interpolado
+    pub color: Color,
+}
+
+impl Surface {
+    // Reconstruye el fragmento que la rasterización generó en (x, y), sin iluminar
+    pub fn fragment(&self, x: usize, y: usize) -> Fragment {
+        Fragment {
+            position: Vec2::new(x as f32, y as f32),
+            color: self.color,
+            depth: self.depth,
+            normal: self.normal,
+            intensity: 1.0,
+            light_visibility: [1.0; MAX_LIGHTS],
+            model_position: self.model_position,
+            world_position: self.world_position,
+            view_dir: Vec3::new(0.0, 0.0, 1.0),
+            light_dir: Vec3::zeros(),
+            tex_coords: self.tex_coords,
+        }
+    }
+}
+
+// Resultado del paso de iluminación para un píxel: la suma de todas las luces
+#[derive(Debug, Clone, Copy)]
+pub struct LightSample {
+    pub intensity: f32,
+    // Fracción de cada luz que llega (sombras), en el orden de las luces de la escena
+    pub visibility: [f32; MAX_LIGHTS],
+    // Dirección hacia la luz que más ilumina el punto (casi siempre la estrella más cercana)
+    pub light_dir: Vec3,
+    // Color lineal de la luz que llega, que tiñe el resultado del shader
+    pub color: Vec3,
+}
+
+impl Default for LightSample {
+    fn default() -> Self {
+        LightSample {
+            intensity: 1.0,
+            visibility: [1.0; MAX_LIGHTS],
+            light_dir: Vec3::zeros(),
+            color: Vec3::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+// Buffers de geometría del render diferido, del tamaño del framebuffer interno.
+// Los objetos opacos escriben aquí su superficie; la iluminación recorre después
+// cada píxel una vez por luz y el sombreado evalúa un solo shader por píxel.
+pub struct GBuffer {
+    pub width: usize,
+    pub height: usize,
+    pub surfaces: Vec<Option<Surface>>,
+    pub lighting: Vec<LightSample>,
+}
+
+impl GBuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        GBuffer {
+            width,
+            height,
+            surfaces: vec![None; width * height],
+            lighting: vec![LightSample::default(); width * height],
+        }
+    }
+
+    pub fn resize(&mut self, width: usize, height: usize) {
+        *self = GBuffer::new(width, height);
+    }
+
+    pub fn clear(&mut self) {
+        self.surfaces.par_iter_mut().for_each(|surface| *surface = None);
+    }
+
+    pub fn surface(&self, x: usize, y: usize) -> Option<&Surface> {
+        if x < self.width && y < self.height {
+            self.surfaces[y * self.width + x].as_ref()
+        } else {
+            None
+        }
+    }
+
+    // Franjas de rows filas de superficies, alineadas con las del framebuffer
+    pub fn bands_mut(&mut self, rows: usize) -> impl IndexedParallelIterator<Item = &mut [Option<Surface>]> {
+        self.surfaces.par_chunks_mut(self.width * rows)
+    }
+}
@@ -0,0 +1,324 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::ambience::AmbientConfig;
+use crate::belt::BeltConfig;
+use crate::color::{Color, Gradient};
+use crate::names::unique_name;
+use crate::nebula::NebulaConfig;
+use crate::noise::{NoiseConfig, NoiseKind};
+use crate::orbit::Orbit;
+use crate::particles::TailConfig;
+use crate::prominence::ProminenceConfig;
+use crate::scene::{AtmosphereConfig, CloudsConfig, PlanetConfig, RingsConfig, SceneConfig};
+use crate::sfx::SoundConfig;
+use crate::shaders::ShaderKind;
+use crate::ship::ShipConfig;
+use crate::starfield::StarfieldConfig;
+
+// Límites del sistema generado, en unidades de la escena
+const MIN_PLANETS: usize = 3;
+const MAX_PLANETS: usize = 7;
+const FIRST_ORBIT: (f32, f32) = (2.5, 3.5);
+// Cada órbita queda entre 1.25 y 1.4 veces más lejos que la anterior (tipo Titius-Bode)
+const ORBIT_SPACING: (f32, f32) = (1.25, 1.4);
+// Más allá de esta distancia el agua se congela y se forman gigantes gaseosos
+const FROST_LINE: f32 = 7.0;
+// Periodo de una órbita de radio 1; el resto sigue la tercera ley de Kepler
+const PERIOD_SCALE: f32 = 0.6;
+// Probabilidad de un sistema binario; los planetas quedan fuera de la órbita de las
+// dos estrellas, a varias veces su separación, para que sus órbitas sean estables
+const BINARY_CHANCE: f64 = 0.3;
+const CIRCUMBINARY_MARGIN: f32 = 2.2;
+
+// Colores de la estrella compañera: enanas rojas, naranjas y blanco azuladas
+const COMPANION_TINTS: [[u8; 3]; 3] = [[255, 90, 40], [255, 140, 60], [150, 190, 255]];
+
+// Paletas de nebulosa, de lo más tenue a lo más brillante
+const NEBULA_PALETTES: [[Color; 4]; 3] = [
+    [Color::new(10, 0, 30), Color::new(90, 20, 120), Color::new(200, 60, 120), Color::new(255, 170, 110)],
+    [Color::new(0, 10, 30), Color::new(20, 70, 120), Color::new(60, 160, 170), Color::new(200, 240, 220)],
+    [Color::new(20, 5, 5), Color::new(110, 30, 20), Color::new(210, 110, 40), Color::new(255, 220, 150)],
+];
+
+// Crea un sistema completo a partir de una semilla: una o dos estrellas, planetas
+// según la distancia a ellas, lunas, anillos, fondo e inclinaciones
+pub fn generate_system(seed: u64) -> SceneConfig {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut planets = vec![star(&mut rng)];
+
+    // Radio dentro del cual no puede haber planetas: la órbita de las estrellas
+    let mut inner_edge = 0.0;
+    if rng.gen_bool(BINARY_CHANCE) {
+        let separation = make_binary(&mut rng, &mut planets);
+        inner_edge = separation * CIRCUMBINARY_MARGIN;
+    }
+
+    let planet_count = rng.gen_range(MIN_PLANETS..=MAX_PLANETS);
+    let mut semi_major_axis = rng.gen_range(FIRST_ORBIT.0..FIRST_ORBIT.1).max(inner_edge);
+    let mut belt = None;
+    for _ in 0..planet_count {
+        // A veces el primer hueco pasada la línea de hielo queda como cinturón de asteroides
+        if belt.is_none() && semi_major_axis > FROST_LINE && rng.gen_bool(0.6) {
+            belt = Some(BeltConfig {
+                seed: rng.gen(),
+                inner_radius: semi_major_axis * 0.92,
+                outer_radius: semi_major_axis * 1.08,
+                period: PERIOD_SCALE * (semi_major_axis * 0.92).powf(1.5),
+                ..BeltConfig::default()
+            });
+            semi_major_axis *= rng.gen_range(ORBIT_SPACING.0..ORBIT_SPACING.1);
+        }
+
+        let name = unique_name(&mut rng, &names(&planets));
+        let planet = planet(&mut rng, name, semi_major_axis);
+        let moons = moons(&mut rng, &planet, &names(&planets));
+
+        planets.push(planet);
+        planets.extend(moons);
+        semi_major_axis *= rng.gen_range(ORBIT_SPACING.0..ORBIT_SPACING.1);
+    }
+
+    if rng.gen_bool(0.7) {
+        planets.push(comet(&mut rng, inner_edge, semi_major_axis, &names(&planets)));
+    }
+
+    let palette = NEBULA_PALETTES[rng.gen_range(0..NEBULA_PALETTES.len())];
+    SceneConfig {
+        starfield: StarfieldConfig { seed, ..StarfieldConfig::default() },
+        nebula: Some(NebulaConfig {
+            seed: rng.gen(),
+            palette: Gradient::new(palette.to_vec()),
+            intensity: rng.gen_range(0.4..0.8),
+            warp: rng.gen_range(1.0..2.0),
+        }),
+        belt,
+        ship: Some(ShipConfig::default()),
+        billboards: Vec::new(),
+        lights: Vec::new(),
+        fog: None,
+        sound: SoundConfig::default(),
+        planets,
+    }
+}
+
+fn star(rng: &mut StdRng) -> PlanetConfig {
+    let scale = rng.gen_range(1.2..2.0);
+    let name = unique_name(rng, &[]);
+    let mut star = body(rng, name, ShaderKind::Sun, scale);
+    star.emissive = true;
+    star.rotation_period = Some(rng.gen_range(4.0..12.0));
+    star.subdivisions = Some(4);
+    star.noise.octaves = 2;
+    star.prominences = Some(ProminenceConfig::default());
+    star.ambient = ambient("rumble", 3.0);
+    star
+}
+
+// Agrega una compañera a la estrella y pone a las dos en órbitas circulares
+// opuestas alrededor del baricentro (el origen); devuelve la separación entre ellas
+fn make_binary(rng: &mut StdRng, planets: &mut Vec<PlanetConfig>) -> f32 {
+    let scale = rng.gen_range(0.5..0.9);
+    let name = unique_name(rng, &names(planets));
+    let mut companion = body(rng, name, ShaderKind::Sun, scale);
+    companion.emissive = true;
+    companion.tint = Some(COMPANION_TINTS[rng.gen_range(0..COMPANION_TINTS.len())]);
+    companion.rotation_period = Some(rng.gen_range(3.0..8.0));
+    companion.subdivisions = Some(3);
+    companion.noise.octaves = 2;
+    companion.prominences = Some(ProminenceConfig::default());
+    companion.ambient = ambient("rumble", 2.0);
+
+    let primary = &mut planets[0];
+    let separation = (primary.scale + companion.scale) * rng.gen_range(1.3..1.7);
+
+    // Con la misma densidad las masas van como el volumen; cada estrella queda a una
+    // distancia del baricentro proporcional a la masa de la otra
+    let primary_mass = primary.scale.powi(3);
+    let companion_mass = companion.scale.powi(3);
+    let total_mass = primary_mass + companion_mass;
+    let period = PERIOD_SCALE * separation.powf(1.5);
+    let orbit = |semi_major_axis: f32, phase: f32| Orbit {
+        semi_major_axis,
+        eccentricity: 0.0,
+        inclination: 0.0,
+        period,
+        phase,
+    };
+
+    primary.orbit = Some(orbit(separation * companion_mass / total_mass, 0.0));
+    companion.orbit = Some(orbit(separation * primary_mass / total_mass, 180.0));
+    planets.push(companion);
+    separation
+}
+
+// El tipo de planeta depende de la distancia: rocosos y calientes cerca de la
+// estrella, templados en la zona habitable y gigantes más allá de la línea de hielo
+fn planet(rng: &mut StdRng, name: String, semi_major_axis: f32) -> PlanetConfig {
+    let (shader, scale) = if semi_major_axis < 4.0 {
+        (pick(rng, &[ShaderKind::Lava, ShaderKind::Moon, ShaderKind::Metal]), rng.gen_range(0.25..0.5))
+    } else if semi_major_axis < FROST_LINE {
+        (pick(rng, &[ShaderKind::Earth, ShaderKind::Earth, ShaderKind::Moon, ShaderKind::Metal]), rng.gen_range(0.35..0.65))
+    } else {
+        let shader = pick(rng, &[ShaderKind::Jupiter, ShaderKind::Jupiter, ShaderKind::Ice, ShaderKind::Cloud]);
+        let scale = if shader == ShaderKind::Jupiter { rng.gen_range(0.6..1.0) } else { rng.gen_range(0.4..0.7) };
+        (shader, scale)
+    };
+
+    let mut planet = body(rng, name, shader, scale);
+    planet.orbit = Some(Orbit {
+        semi_major_axis,
+        eccentricity: rng.gen_range(0.0..0.12),
+        inclination: rng.gen_range(0.0..6.0),
+        period: PERIOD_SCALE * semi_major_axis.powf(1.5),
+        phase: rng.gen_range(0.0..360.0),
+    });
+
+    // La mayoría tiene poca inclinación; de vez en cuando uno queda casi acostado
+    planet.axial_tilt = if rng.gen_bool(0.1) { rng.gen_range(60.0..98.0) } else { rng.gen_range(0.0..30.0) };
+
+    match shader {
+        ShaderKind::Earth => {
+            planet.subdivisions = Some(5);
+            planet.noise.octaves = 3;
+            planet.displacement = 0.08;
+            planet.clouds = Some(CloudsConfig { height: 1.03, rotation_speed: rng.gen_range(1.0..2.0) });
+            planet.atmosphere = Some(AtmosphereConfig {
+                color: [rng.gen_range(90..140), rng.gen_range(150..200), 255],
+                thickness: rng.gen_range(0.06..0.1),
+            });
+            planet.city_lights = rng.gen_bool(0.5);
+            planet.ambient = ambient("wind", 1.0);
+        }
+        ShaderKind::Lava => {
+            planet.ambient = ambient("rumble", 1.0);
+        }
+        ShaderKind::Moon | ShaderKind::Metal => {
+            planet.noise.noise_type = NoiseKind::Cellular;
+        }
+        ShaderKind::Jupiter | ShaderKind::Ice if rng.gen_bool(0.4) => {
+            let inner_radius = rng.gen_range(1.3..1.6);
+            planet.rings = Some(RingsConfig {
+                inner_radius,
+                outer_radius: inner_radius + rng.gen_range(0.5..1.0),
+                segments: 96,
+                tilt: 0.0,
+            });
+        }
+        _ => {}
+    }
+
+    planet
+}
+
+// Sonido generado que se oye a menos de range de la superficie
+fn ambient(sound: &str, range: f32) -> Option<AmbientConfig> {
+    Some(AmbientConfig { sound: sound.to_string(), volume: 1.0, range })
+}
+
+// Núcleo pequeño en una órbita muy excéntrica e inclinada que pasa cerca de la
+// estrella (sin entrar en la órbita de un par binario) y se aleja hasta las afueras
+fn comet(rng: &mut StdRng, inner_radius: f32, outer_radius: f32, taken: &[String]) -> PlanetConfig {
+    let name = unique_name(rng, taken);
+    let scale = rng.gen_range(0.08..0.15);
+    let mut comet = body(rng, name, ShaderKind::Moon, scale);
+
+    let perihelion = rng.gen_range(2.5f32..4.0).max(inner_radius);
+    let aphelion = outer_radius * rng.gen_range(0.8..1.1);
+    let semi_major_axis = (perihelion + aphelion) / 2.0;
+    comet.orbit = Some(Orbit {
+        semi_major_axis,
+        eccentricity: (aphelion - perihelion) / (aphelion + perihelion),
+        inclination: rng.gen_range(10.0..40.0),
+        period: PERIOD_SCALE * semi_major_axis.powf(1.5),
+        phase: rng.gen_range(0.0..360.0),
+    });
+    comet.noise.noise_type = NoiseKind::Cellular;
+    comet.subdivisions = Some(1);
+    comet.tail = Some(TailConfig::default());
+    comet
+}
+
+// Los gigantes tienen más lunas; sus órbitas quedan cerca del planeta para no
+// cruzarse con las de los vecinos
+fn moons(rng: &mut StdRng, planet: &PlanetConfig, taken: &[String]) -> Vec<PlanetConfig> {
+    let count = match planet.shader {
+        ShaderKind::Jupiter => rng.gen_range(0..=3),
+        ShaderKind::Earth | ShaderKind::Ice => rng.gen_range(0..=1),
+        _ => 0,
+    };
+
+    let ring_radius = planet.rings.as_ref().map(|rings| rings.outer_radius).unwrap_or(1.0);
+    let mut distance = planet.scale * (ring_radius + rng.gen_range(0.8..1.2));
+    let mut taken = taken.to_vec();
+    taken.push(planet.name.clone());
+
+    (0..count)
+        .map(|_| {
+            let scale = planet.scale * rng.gen_range(0.15..0.3);
+            distance += scale * 2.0 + rng.gen_range(0.2..0.5);
+
+            let name = unique_name(rng, &taken);
+            taken.push(name.clone());
+            let mut moon = body(rng, name, ShaderKind::Moon, scale);
+            moon.parent = Some(planet.name.clone());
+            moon.noise.noise_type = NoiseKind::Cellular;
+            moon.subdivisions = Some(2);
+            moon.orbit = Some(Orbit {
+                semi_major_axis: distance,
+                eccentricity: rng.gen_range(0.0..0.05),
+                inclination: rng.gen_range(0.0..15.0),
+                period: PERIOD_SCALE * 3.0 * distance.powf(1.5),
+                phase: rng.gen_range(0.0..360.0),
+            });
+            moon
+        })
+        .collect()
+}
+
+// Cuerpo con los valores por defecto de la escena y una semilla de ruido propia
+fn body(rng: &mut StdRng, name: String, shader: ShaderKind, scale: f32) -> PlanetConfig {
+    PlanetConfig {
+        name,
+        parent: None,
+        orbit: None,
+        scale,
+        shader,
+        texture: None,
+        rotation_speed: 0.0,
+        // Algunos cuerpos giran en sentido contrario
+        rotation_period: Some(rng.gen_range(4.0..16.0) * if rng.gen_bool(0.15) { -1.0 } else { 1.0 }),
+        axial_tilt: 0.0,
+        mass: None,
+        model: None,
+        subdivisions: Some(3),
+        stacks: 24,
+        slices: 24,
+        noise: NoiseConfig { seed: rng.gen(), ..NoiseConfig::default() },
+        double_sided: false,
+        transparent: false,
+        opacity: 1.0,
+        emissive: false,
+        tint: None,
+        palette: None,
+        script: None,
+        rings: None,
+        clouds: None,
+        atmosphere: None,
+        city_lights: false,
+        twilight: None,
+        pbr: None,
+        light: None,
+        displacement: 0.0,
+        tail: None,
+        prominences: None,
+        ambient: None,
+    }
+}
+
+fn names(planets: &[PlanetConfig]) -> Vec<String> {
+    planets.iter().map(|planet| planet.name.clone()).collect()
+}
+
+fn pick(rng: &mut StdRng, options: &[ShaderKind]) -> ShaderKind {
+    options[rng.gen_range(0..options.len())]
+}
@@ -0,0 +1,237 @@
+use gltf::image::Format;
+use gltf::mesh::Mode;
+use nalgebra_glm::{Mat4, Vec2, Vec3, Vec4};
+use thiserror::Error;
+use crate::color::Color;
+use crate::lighting::{Material, PbrMaterial};
+use crate::mesh::{self, Mesh, NormalWeighting};
+use crate::obj::{MaterialGroup, Model, ObjMaterial};
+use crate::texture::Texture;
+use crate::vertex::Vertex;
+
+#[derive(Debug, Error)]
+pub enum GltfError {
+    #[error(transparent)]
+    Load(#[from] gltf::Error),
+    #[error("el archivo no tiene mallas de triángulos")]
+    Empty,
+}
+
+// Modelo glTF 2.0 (.gltf con sus .bin o .glb con todo adentro), ya con las
+// transformaciones de los nodos aplicadas: queda en el espacio de la escena
+pub struct GltfModel {
+    primitives: Vec<Primitive>,
+    materials: Vec<ObjMaterial>,
+}
+
+// Una primitiva de triángulos con su material (índice en materials)
+struct Primitive {
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    material: usize,
+}
+
+impl GltfModel {
+    // Se leen las posiciones, normales, coordenadas de textura y colores de vértice
+    // de las primitivas de triángulos de la escena por defecto; los puntos y las
+    // líneas se ignoran. Sin normales se calculan suaves, como en los OBJ.
+    pub fn load(path: &str) -> Result<Self, GltfError> {
+        let (document, buffers, images) = gltf::import(path).map_err(GltfError::Load)?;
+
+        let mut materials: Vec<ObjMaterial> = document
+            .materials()
+            .map(|material| convert_material(&material, &images))
+            .collect();
+        // Material por defecto del formato (blanco y rugoso, aquí sin metal para que no
+        // quede negro) para las primitivas que no tienen
+        let default_material = materials.len();
+        materials.push(ObjMaterial {
+            name: "default".to_string(),
+            diffuse: Color::new(255, 255, 255),
+            texture: None,
+            specular: roughness_specular(1.0),
+            pbr: Some(PbrMaterial { metallic: 0.0, roughness: 1.0 }),
+            vertex_colors: true,
+        });
+
+        let mut primitives = Vec::new();
+        let scene = document.default_scene().or_else(|| document.scenes().next());
+        let mut nodes: Vec<(gltf::Node, Mat4)> = match scene {
+            Some(scene) => scene.nodes().map(|node| (node, Mat4::identity())).collect(),
+            None => document.nodes().map(|node| (node, Mat4::identity())).collect(),
+        };
+
+        // Recorrido de la jerarquía acumulando la transformación de cada padre
+        while let Some((node, parent)) = nodes.pop() {
+            let transform = parent * Mat4::from(node.transform().matrix());
+            nodes.extend(node.children().map(|child| (child, transform)));
+
+            let Some(gltf_mesh) = node.mesh() else {
+                continue;
+            };
+            for primitive in gltf_mesh.primitives().filter(|primitive| primitive.mode() == Mode::Triangles) {
+                let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+                let Some(positions) = reader.read_positions() else {
+                    continue;
+                };
+                let positions: Vec<Vec3> = positions.map(Vec3::from).collect();
+                let indices: Vec<u32> = match reader.read_indices() {
+                    Some(indices) => indices.into_u32().collect(),
+                    None => (0..positions.len() as u32).collect(),
+                };
+                // Sin NORMAL se calculan después, ya con la transformación del nodo
+                let normals: Option<Vec<Vec3>> = reader.read_normals().map(|normals| normals.map(Vec3::from).collect());
+                let tex_coords: Option<Vec<Vec2>> = reader
+                    .read_tex_coords(0)
+                    .map(|coords| coords.into_f32().map(Vec2::from).collect());
+                // Sin COLOR_0 el color de vértice es blanco y no cambia el material
+                let colors: Option<Vec<Color>> = reader
+                    .read_colors(0)
+                    .map(|colors| colors.into_rgb_f32().map(|[r, g, b]| unit_color(r, g, b)).collect());
+
+                // Las normales se transforman con la inversa traspuesta
+                let normal_matrix = transform.try_inverse().unwrap_or(Mat4::identity()).transpose();
+                let mut vertices: Vec<Vertex> = positions
+                    .iter()
+                    .enumerate()
+                    .map(|(index, position)| {
+                        let position = (transform * Vec4::new(position.x, position.y, position.z, 1.0)).xyz();
+                        let normal = normals.as_ref().map_or(Vec3::new(0.0, 1.0, 0.0), |normals| normals[index]);
+                        let normal = (normal_matrix * Vec4::new(normal.x, normal.y, normal.z, 0.0)).xyz();
+                        let tex_coords = tex_coords.as_ref().map_or(Vec2::zeros(), |coords| coords[index]);
+
+                        let mut vertex = Vertex::new(position, normal.try_normalize(f32::EPSILON).unwrap_or(normal), tex_coords);
+                        vertex.color = colors.as_ref().map_or(Color::new(255, 255, 255), |colors| colors[index]);
+                        vertex
+                    })
+                    .collect();
+
+                // Una escala negativa da vuelta el orden de los vértices
+                let mut indices = indices;
+                if transform.fixed_view::<3, 3>(0, 0).determinant() < 0.0 {
+                    for triangle in indices.chunks_exact_mut(3) {
+                        triangle.swap(1, 2);
+                    }
+                }
+                if normals.is_none() {
+                    mesh::smooth_normals(&mut vertices, &mut indices, NormalWeighting::Angle, Some(mesh::MODEL_CREASE_ANGLE));
+                }
+
+                primitives.push(Primitive {
+                    vertices,
+                    indices,
+                    material: primitive.material().index().unwrap_or(default_material),
+                });
+            }
+        }
+
+        if primitives.is_empty() {
+            return Err(GltfError::Empty);
+        }
+        Ok(GltfModel { primitives, materials })
+    }
+
+    // Une todas las primitivas en una sola malla indexada
+    pub fn get_mesh(&self) -> Mesh {
+        merge(self.primitives.iter())
+    }
+
+    // El modelo separado por materiales, en el orden en que aparecen, como Obj::into_model
+    pub fn into_model(self) -> Model {
+        let mut used: Vec<usize> = Vec::new();
+        for primitive in &self.primitives {
+            if !used.contains(&primitive.material) {
+                used.push(primitive.material);
+            }
+        }
+
+        let groups = used
+            .into_iter()
+            .map(|material| MaterialGroup {
+                mesh: merge(self.primitives.iter().filter(|primitive| primitive.material == material)),
+                material: Some(material),
+            })
+            .collect();
+        Model { groups, materials: self.materials }
+    }
+}
+
+fn merge<'a>(primitives: impl Iterator<Item = &'a Primitive>) -> Mesh {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    for primitive in primitives {
+        let offset = vertices.len() as u32;
+        vertices.extend(primitive.vertices.iter().cloned());
+        indices.extend(primitive.indices.iter().map(|&index| index + offset));
+    }
+
+    Mesh::new(vertices, indices)
+}
+
+// glTF guarda el color base y COLOR_0 en intensidad lineal; Color es sRGB de 8 bits
+fn unit_color(r: f32, g: f32, b: f32) -> Color {
+    Color::from_linear(&Vec3::new(r, g, b))
+}
+
+// El modelo metálico-rugoso de glTF llevado al material de los OBJ: el color base
+// es el difuso y los factores metálico y rugoso se sombrean con PBR; specular guarda
+// el brillo equivalente del modelo simple (más liso = reflejo más fuerte y más
+// chico). Los colores de vértice tiñen el color base.
+fn convert_material(material: &gltf::Material, images: &[gltf::image::Data]) -> ObjMaterial {
+    let pbr = material.pbr_metallic_roughness();
+    let [r, g, b, _] = pbr.base_color_factor();
+    let texture = pbr
+        .base_color_texture()
+        .and_then(|info| images.get(info.texture().source().index()))
+        .and_then(convert_image);
+
+    ObjMaterial {
+        name: material.name().unwrap_or("material").to_string(),
+        diffuse: unit_color(r, g, b),
+        texture,
+        specular: roughness_specular(pbr.roughness_factor()),
+        pbr: Some(PbrMaterial {
+            metallic: pbr.metallic_factor(),
+            roughness: pbr.roughness_factor(),
+        }),
+        vertex_colors: true,
+    }
+}
+
+fn roughness_specular(roughness: f32) -> Material {
+    let smoothness = 1.0 - roughness.clamp(0.0, 1.0);
+    Material {
+        shininess: 2.0 + smoothness * smoothness * 254.0,
+        specular: 0.04 + smoothness * 0.6,
+    }
+}
+
+// Imagen ya decodificada por el importador; los canales de más de 8 bits se reducen
+fn convert_image(image: &gltf::image::Data) -> Option<Texture> {
+    let (channels, bytes) = match image.format {
+        Format::R8 => (1, 1),
+        Format::R8G8 => (2, 1),
+        Format::R8G8B8 => (3, 1),
+        Format::R8G8B8A8 => (4, 1),
+        Format::R16 => (1, 2),
+        Format::R16G16 => (2, 2),
+        Format::R16G16B16 => (3, 2),
+        Format::R16G16B16A16 => (4, 2),
+        // Las texturas en punto flotante no se usan como color base
+        Format::R32G32B32FLOAT | Format::R32G32B32A32FLOAT => return None,
+    };
+
+    // En little endian el byte alto de un canal de 16 bits es el segundo
+    let stride = channels * bytes;
+    let channel = |pixel: &[u8], index: usize| pixel[index.min(channels - 1) * bytes + bytes - 1];
+    let data = image
+        .pixels
+        .chunks_exact(stride)
+        .map(|pixel| match channels {
+            1 | 2 => Color::new(channel(pixel, 0), channel(pixel, 0), channel(pixel, 0)),
+            _ => Color::new(channel(pixel, 0), channel(pixel, 1), channel(pixel, 2)),
+        })
+        .collect();
+    Some(Texture::from_colors(image.width as usize, image.height as usize, data))
+}
@@ -0,0 +1,156 @@
+use nalgebra_glm::Vec3;
+use thiserror::Error;
+use std::fs;
+use std::io;
+
+// Cuánto cambia la temperatura 1 (o -1) los canales rojo y azul
+const TEMPERATURE_SHIFT: f32 = 0.1;
+// Pesos Rec.709 para la luminancia, sobre los valores sRGB
+const LUMA: Vec3 = Vec3::new(0.2126, 0.7152, 0.0722);
+
+#[derive(Debug, Error)]
+pub enum LutError {
+    #[error("no se pudo leer la LUT '{0}': {1}")]
+    Io(String, #[source] io::Error),
+    // Archivo, línea y descripción del problema
+    #[error("LUT inválida '{0}' (línea {1}): {2}")]
+    Parse(String, usize, String),
+}
+
+// Tabla 3D de colores en formato .cube (el de Resolve y otros editores): size³
+// entradas RGB sobre la imagen codificada en sRGB, con el rojo variando más rápido
+#[derive(Debug, Clone)]
+pub struct Lut {
+    size: usize,
+    data: Vec<Vec3>,
+    domain_min: Vec3,
+    domain_max: Vec3,
+}
+
+impl Lut {
+    pub fn load(path: &str) -> Result<Self, LutError> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| LutError::Io(path.to_string(), err))?;
+
+        Lut::parse(&contents)
+            .map_err(|(line, message)| LutError::Parse(path.to_string(), line, message))
+    }
+
+    // Errores con el número de línea (desde 1); TITLE y los comentarios se ignoran
+    fn parse(contents: &str) -> Result<Self, (usize, String)> {
+        let mut size = None;
+        let mut data = Vec::new();
+        let mut domain_min = Vec3::zeros();
+        let mut domain_max = Vec3::repeat(1.0);
+
+        for (index, line) in contents.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with("TITLE") {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let keyword = parts.next().unwrap_or_default();
+            match keyword {
+                "LUT_3D_SIZE" => {
+                    let value = parts.next()
+                        .and_then(|value| value.parse::<usize>().ok())
+                        .filter(|value| *value >= 2)
+                        .ok_or((line_number, "LUT_3D_SIZE debe ser un entero de al menos 2".to_string()))?;
+                    size = Some(value);
+                }
+                "LUT_1D_SIZE" => return Err((line_number, "las LUT 1D no están soportadas".to_string())),
+                "DOMAIN_MIN" => domain_min = parse_triplet(line).ok_or((line_number, "se esperaban tres números".to_string()))?,
+                "DOMAIN_MAX" => domain_max = parse_triplet(line).ok_or((line_number, "se esperaban tres números".to_string()))?,
+                _ => match parse_triplet(line) {
+                    Some(color) if line.split_whitespace().count() == 3 => data.push(color),
+                    _ => return Err((line_number, format!("no se esperaba '{}'", line))),
+                },
+            }
+        }
+
+        let size = size.ok_or((1, "falta LUT_3D_SIZE".to_string()))?;
+        if data.len() != size * size * size {
+            return Err((contents.lines().count(), format!("se esperaban {} entradas y hay {}", size * size * size, data.len())));
+        }
+
+        Ok(Lut { size, data, domain_min, domain_max })
+    }
+
+    // Interpolación trilineal entre las ocho entradas que rodean al color
+    pub fn sample(&self, color: Vec3) -> Vec3 {
+        let max = (self.size - 1) as f32;
+        let position = (color - self.domain_min)
+            .component_div(&(self.domain_max - self.domain_min))
+            .map(|value| value.clamp(0.0, 1.0) * max);
+
+        let base = position.map(|value| value.floor().min(max - 1.0));
+        let t = position - base;
+        let (x, y, z) = (base.x as usize, base.y as usize, base.z as usize);
+
+        let at = |dx: usize, dy: usize, dz: usize| {
+            self.data[((z + dz) * self.size + (y + dy)) * self.size + (x + dx)]
+        };
+        let lerp_x = |dy, dz| at(0, dy, dz).lerp(&at(1, dy, dz), t.x);
+        let front = lerp_x(0, 0).lerp(&lerp_x(1, 0), t.y);
+        let back = lerp_x(0, 1).lerp(&lerp_x(1, 1), t.y);
+        front.lerp(&back, t.z)
+    }
+}
+
+// Los tres números de una línea (después de la palabra clave, si hay una)
+fn parse_triplet(line: &str) -> Option<Vec3> {
+    let values: Vec<f32> = line
+        .split_whitespace()
+        .filter_map(|value| value.parse().ok())
+        .collect();
+    (values.len() == 3).then(|| Vec3::new(values[0], values[1], values[2]))
+}
+
+// Corrección de color sobre la imagen final, ya con la curva de tonos y codificada
+// en sRGB pero antes de pasarla a 8 bits (ver postprocess::resolve): temperatura,
+// contraste y saturación, y después la LUT si hay una
+#[derive(Debug, Clone)]
+pub struct ColorGrading {
+    // 1 = sin cambios; mayor separa los tonos alrededor del gris medio
+    pub contrast: f32,
+    // 1 = sin cambios, 0 = blanco y negro
+    pub saturation: f32,
+    // Entre -1 (frío, azulado) y 1 (cálido, anaranjado)
+    pub temperature: f32,
+    pub lut: Option<Lut>,
+}
+
+impl Default for ColorGrading {
+    fn default() -> Self {
+        ColorGrading {
+            contrast: 1.0,
+            saturation: 1.0,
+            temperature: 0.0,
+            lut: None,
+        }
+    }
+}
+
+impl ColorGrading {
+    pub fn is_neutral(&self) -> bool {
+        self.contrast == 1.0 && self.saturation == 1.0 && self.temperature == 0.0 && self.lut.is_none()
+    }
+
+    pub fn grade(&self, color: Vec3) -> Vec3 {
+        let shift = self.temperature * TEMPERATURE_SHIFT;
+        let mut color = color.component_mul(&Vec3::new(1.0 + shift, 1.0, 1.0 - shift));
+
+        color = (color - Vec3::repeat(0.5)) * self.contrast + Vec3::repeat(0.5);
+
+        let luma = color.dot(&LUMA);
+        color = Vec3::repeat(luma) + (color - Vec3::repeat(luma)) * self.saturation;
+
+        let color = color.map(|value| value.clamp(0.0, 1.0));
+        match &self.lut {
+            Some(lut) => lut.sample(color),
+            None => color,
+        }
+    }
+}
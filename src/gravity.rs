@@ -0,0 +1,186 @@
+use nalgebra_glm::Vec3;
+use crate::planet::Planet;
+
+// Constante de gravitación en unidades de la escena: con ella y las masas por
+// defecto, un planeta a 7 unidades de una estrella de escala 1.5 tarda unos 20 s en dar la vuelta
+pub const G: f32 = 1.0;
+// Suaviza la fuerza a distancias muy cortas para que dos cuerpos que se cruzan no salgan disparados
+const SOFTENING: f32 = 0.05;
+
+pub struct Body {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub mass: f32,
+    acceleration: Vec3,
+}
+
+impl Body {
+    pub fn new(position: Vec3, velocity: Vec3, mass: f32) -> Self {
+        Body { position, velocity, mass, acceleration: Vec3::zeros() }
+    }
+}
+
+// Simulación de N cuerpos: cada planeta atrae a todos los demás y las posiciones
+// se integran con Verlet de velocidad (reversible, así el tiempo puede ir hacia atrás)
+pub struct NBody {
+    pub bodies: Vec<Body>,
+}
+
+impl NBody {
+    // Parte de las posiciones actuales; la velocidad de cada cuerpo es la de su órbita
+    // kepleriana (tangente a la elipse, con la magnitud de vis-viva) alrededor del
+    // baricentro de las estrellas o de su planeta, así el sistema arranca casi igual
+    // que en modo paramétrico
+    pub fn from_planets(planets: &[Planet], sim_time: f32) -> Self {
+        let star_mass: f32 = planets.iter().filter(|planet| planet.emissive).map(|planet| planet.mass).sum();
+        let barycenter = if star_mass > 0.0 {
+            planets
+                .iter()
+                .filter(|planet| planet.emissive)
+                .map(|planet| planet.translation * planet.mass)
+                .sum::<Vec3>() / star_mass
+        } else {
+            Vec3::zeros()
+        };
+        let mut bodies: Vec<Body> = Vec::with_capacity(planets.len());
+
+        for planet in planets.iter() {
+            let velocity = match planet.orbit {
+                Some(orbit) => {
+                    let (center, mu) = match planet.parent {
+                        Some(parent) => (planets[parent].translation, G * (planets[parent].mass + planet.mass)),
+                        // Una estrella de un sistema múltiple gira alrededor del baricentro
+                        // atraída por sus compañeras: en el problema de dos cuerpos la
+                        // órbita de cada una equivale a la de una masa m'³/M² en el centro
+                        None if planet.emissive => {
+                            let companions = star_mass - planet.mass;
+                            (barycenter, G * companions.powi(3) / (star_mass * star_mass))
+                        }
+                        None => (barycenter, G * (star_mass + planet.mass)),
+                    };
+
+                    let radius = (planet.translation - center).magnitude().max(SOFTENING);
+                    let speed = (mu * (2.0 / radius - 1.0 / orbit.semi_major_axis)).max(0.0).sqrt();
+
+                    let epsilon = orbit.period.abs() * 1e-4;
+                    let tangent = orbit.position(sim_time + epsilon) - orbit.position(sim_time);
+                    let tangent = if tangent.magnitude() > f32::EPSILON { tangent.normalize() } else { Vec3::zeros() };
+
+                    // Las lunas se llevan además la velocidad de su planeta (que ya se calculó)
+                    let carried = planet.parent.map(|parent| bodies[parent].velocity).unwrap_or(Vec3::zeros());
+                    tangent * speed + carried
+                }
+                None => Vec3::zeros(),
+            };
+
+            bodies.push(Body::new(planet.translation, velocity, planet.mass));
+        }
+
+        // Sin momento total el centro de masa queda quieto y el sistema no se desplaza
+        let total_mass: f32 = bodies.iter().map(|body| body.mass).sum();
+        if total_mass > 0.0 {
+            let momentum: Vec3 = bodies.iter().map(|body| body.velocity * body.mass).sum();
+            let drift = momentum / total_mass;
+            for body in bodies.iter_mut() {
+                body.velocity -= drift;
+            }
+        }
+
+        NBody::new(bodies)
+    }
+
+    // Sistema con posiciones y velocidades dadas (p. ej. de un estado guardado)
+    pub fn new(bodies: Vec<Body>) -> Self {
+        let mut system = NBody { bodies };
+        let accelerations = system.accelerations();
+        for (body, acceleration) in system.bodies.iter_mut().zip(accelerations) {
+            body.acceleration = acceleration;
+        }
+        system
+    }
+
+    pub fn step(&mut self, dt: f32) {
+        for body in self.bodies.iter_mut() {
+            body.position += body.velocity * dt + body.acceleration * (0.5 * dt * dt);
+        }
+
+        let accelerations = self.accelerations();
+        for (body, acceleration) in self.bodies.iter_mut().zip(accelerations) {
+            body.velocity += (body.acceleration + acceleration) * (0.5 * dt);
+            body.acceleration = acceleration;
+        }
+    }
+
+    // Cambia la velocidad de un cuerpo por un factor, para perturbar el sistema
+    pub fn kick(&mut self, index: usize, factor: f32) {
+        if let Some(body) = self.bodies.get_mut(index) {
+            body.velocity *= factor;
+        }
+    }
+
+    fn accelerations(&self) -> Vec<Vec3> {
+        let mut accelerations = vec![Vec3::zeros(); self.bodies.len()];
+
+        for i in 0..self.bodies.len() {
+            for j in (i + 1)..self.bodies.len() {
+                let offset = self.bodies[j].position - self.bodies[i].position;
+                let distance_squared = offset.magnitude_squared() + SOFTENING * SOFTENING;
+                let force = offset * (G / (distance_squared * distance_squared.sqrt()));
+
+                accelerations[i] += force * self.bodies[j].mass;
+                accelerations[j] -= force * self.bodies[i].mass;
+            }
+        }
+
+        accelerations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    // Energía cinética más la potencial, con el mismo suavizado que la fuerza
+    fn energy(system: &NBody) -> f32 {
+        let kinetic: f32 = system.bodies.iter().map(|body| 0.5 * body.mass * body.velocity.magnitude_squared()).sum();
+        let mut potential = 0.0;
+        for i in 0..system.bodies.len() {
+            for j in (i + 1)..system.bodies.len() {
+                let distance_squared = (system.bodies[j].position - system.bodies[i].position).magnitude_squared();
+                potential -= G * system.bodies[i].mass * system.bodies[j].mass / (distance_squared + SOFTENING * SOFTENING).sqrt();
+            }
+        }
+        kinetic + potential
+    }
+
+    #[test]
+    fn two_body_circular_orbit_keeps_radius_and_energy() {
+        let (star_mass, planet_mass, radius) = (100.0, 1.0, 7.0);
+        let total_mass = star_mass + planet_mass;
+        // Velocidad relativa de la órbita circular con la fuerza suavizada; cada cuerpo
+        // gira alrededor del baricentro con la parte que le toca según su masa
+        let distance_squared: f32 = radius * radius + SOFTENING * SOFTENING;
+        let speed = (G * total_mass * radius * radius / (distance_squared * distance_squared.sqrt())).sqrt();
+        let mut system = NBody::new(vec![
+            Body::new(Vec3::new(-radius * planet_mass / total_mass, 0.0, 0.0), Vec3::new(0.0, 0.0, -speed * planet_mass / total_mass), star_mass),
+            Body::new(Vec3::new(radius * star_mass / total_mass, 0.0, 0.0), Vec3::new(0.0, 0.0, speed * star_mass / total_mass), planet_mass),
+        ]);
+        let initial_energy = energy(&system);
+
+        // Tres vueltas con el paso fijo del simulador
+        let dt = 1.0 / 60.0;
+        let steps = (3.0 * 2.0 * PI * radius / speed / dt) as usize;
+        for _ in 0..steps {
+            system.step(dt);
+            let separation = (system.bodies[1].position - system.bodies[0].position).magnitude();
+            assert!((separation - radius).abs() < radius * 1e-3, "la separación se fue a {}", separation);
+        }
+
+        let drift = ((energy(&system) - initial_energy) / initial_energy).abs();
+        assert!(drift < 1e-4, "la energía cambió un {}", drift);
+        // El baricentro no se mueve
+        let barycenter: Vec3 = system.bodies.iter().map(|body| body.position * body.mass).sum::<Vec3>() / total_mass;
+        assert!(barycenter.magnitude() < 1e-3);
+    }
+}
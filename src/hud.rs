@@ -0,0 +1,117 @@
+use crate::font::{draw_text, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::framebuffer::Framebuffer;
+use crate::profiler::{ProfileReport, Stage};
+
+const TEXT_COLOR: u32 = 0xDDE6FF;
+const NOTICE_COLOR: u32 = 0xFF7A66;
+// Segundos que queda en pantalla un aviso
+const NOTICE_SECONDS: f32 = 6.0;
+const SHADOW_COLOR: u32 = 0x000000;
+const MARGIN: usize = 8;
+const SCALE: usize = 2;
+// Cuerpos que lista el perfil, de los más lentos
+const PROFILE_BODIES: usize = 8;
+
+pub struct HudInfo<'a> {
+    pub time_scale: f32,
+    pub paused: bool,
+    pub camera_mode: &'a str,
+    pub selected: Option<&'a str>,
+    // Vista de depuración activa, si hay una
+    pub debug_view: Option<&'a str>,
+    // Tiempos del render (F10), en la esquina derecha
+    pub profile: Option<&'a ProfileReport>,
+}
+
+pub struct Hud {
+    fps: f32,
+    // Último aviso (p. ej. un error al recargar un shader) y cuánto le queda en pantalla
+    notice: Option<(String, f32)>,
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Hud { fps: 0.0, notice: None }
+    }
+
+    // Muestra un mensaje abajo de la ventana durante unos segundos; reemplaza al anterior
+    pub fn notify(&mut self, message: String) {
+        self.notice = Some((message, NOTICE_SECONDS));
+    }
+
+    // Promedio exponencial para que el contador no parpadee
+    pub fn update(&mut self, frame_delta: f32) {
+        if frame_delta > 0.0 {
+            let current = 1.0 / frame_delta;
+            self.fps = if self.fps == 0.0 { current } else { self.fps * 0.9 + current * 0.1 };
+        }
+        if let Some((_, remaining)) = &mut self.notice {
+            *remaining -= frame_delta;
+            if *remaining <= 0.0 {
+                self.notice = None;
+            }
+        }
+    }
+
+    pub fn draw(&self, framebuffer: &mut Framebuffer, info: &HudInfo) {
+        let mut lines = vec![
+            format!("FPS: {:.0}", self.fps),
+            if info.paused {
+                "VELOCIDAD: PAUSA".to_string()
+            } else {
+                format!("VELOCIDAD: {:.2}X", info.time_scale)
+            },
+            format!("CAMARA: {}", info.camera_mode),
+        ];
+        if let Some(name) = info.selected {
+            lines.push(format!("SELECCION: {}", name));
+        }
+        if let Some(view) = info.debug_view {
+            lines.push(format!("VISTA: {}", view));
+        }
+
+        let line_height = (GLYPH_HEIGHT + 3) * SCALE;
+        for (i, line) in lines.iter().enumerate() {
+            let y = MARGIN + i * line_height;
+            // Sombra de un píxel para que se lea sobre fondos claros
+            draw_text(framebuffer, MARGIN + 1, y + 1, line, SHADOW_COLOR, SCALE);
+            draw_text(framebuffer, MARGIN, y, line, TEXT_COLOR, SCALE);
+        }
+
+        // El perfil va arriba a la derecha, alineado a ese borde
+        if let Some(report) = info.profile {
+            let ms = |time: std::time::Duration| time.as_secs_f32() * 1000.0;
+            let mut lines = vec![format!("FRAME: {:.1} MS", ms(report.frame))];
+            lines.extend(Stage::ALL.iter().map(|&stage| format!("{}: {:.1}", stage.label(), ms(report.stage(stage)))));
+            lines.extend(report.bodies.iter().take(PROFILE_BODIES).map(|(body, time)| format!("{}: {:.1}", body, ms(*time))));
+
+            let advance = (GLYPH_WIDTH + 1) * SCALE;
+            for (i, line) in lines.iter().enumerate() {
+                let x = framebuffer.width.saturating_sub(MARGIN + line.chars().count() * advance);
+                let y = MARGIN + i * line_height;
+                draw_text(framebuffer, x + 1, y + 1, line, SHADOW_COLOR, SCALE);
+                draw_text(framebuffer, x, y, line, TEXT_COLOR, SCALE);
+            }
+        }
+
+        // El aviso va abajo, partido en líneas que entran en el ancho de la ventana
+        if let Some((message, _)) = &self.notice {
+            let columns = (framebuffer.width.saturating_sub(2 * MARGIN) / ((GLYPH_WIDTH + 1) * SCALE)).max(1);
+            let chars: Vec<char> = message.chars().collect();
+            let rows: Vec<String> = chars.chunks(columns).map(|row| row.iter().collect()).collect();
+            for (i, row) in rows.iter().enumerate() {
+                let Some(y) = framebuffer.height.checked_sub(MARGIN + (rows.len() - i) * line_height) else {
+                    continue;
+                };
+                draw_text(framebuffer, MARGIN + 1, y + 1, row, SHADOW_COLOR, SCALE);
+                draw_text(framebuffer, MARGIN, y, row, NOTICE_COLOR, SCALE);
+            }
+        }
+    }
+}
+
+impl Default for Hud {
+    fn default() -> Self {
+        Self::new()
+    }
+}
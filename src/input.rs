@@ -0,0 +1,229 @@
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window};
+use crate::audio::AudioManager;
+use crate::camera::Camera;
+use crate::clock::Clock;
+use crate::ship::ShipControls;
+
+const ORBIT_SENSITIVITY: f32 = 0.01;
+const STEER_SENSITIVITY: f32 = 0.005;
+const ZOOM_SENSITIVITY: f32 = 0.5;
+const PAN_SENSITIVITY: f32 = 0.002;
+// Movimiento máximo (en píxeles) entre presionar y soltar para contar como clic
+const CLICK_TOLERANCE: f32 = 3.0;
+
+// Órbita: la cámara gira alrededor de la escena. Piloto: el teclado y el mouse
+// manejan la nave y la cámara la persigue. Mapa: se elige un sistema de la galaxia.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputMode {
+    Orbit,
+    Pilot,
+    Map,
+}
+
+pub enum CameraCommand {
+    Orbit { yaw: f32, pitch: f32 },
+    Zoom(f32),
+    Pan { right: f32, up: f32 },
+}
+
+pub struct MouseInput {
+    last_position: Option<(f32, f32)>,
+    press_position: Option<(f32, f32)>,
+    click: Option<(f32, f32)>,
+}
+
+impl MouseInput {
+    pub fn new() -> Self {
+        MouseInput {
+            last_position: None,
+            press_position: None,
+            click: None,
+        }
+    }
+
+    // Devuelve la posición del último clic izquierdo (sin arrastre), una sola vez
+    pub fn take_click(&mut self) -> Option<(f32, f32)> {
+        self.click.take()
+    }
+
+    // Traduce el estado del mouse en comandos para la cámara
+    pub fn poll(&mut self, window: &Window, camera: &Camera) -> Vec<CameraCommand> {
+        let mut commands = Vec::new();
+        let position = window.get_mouse_pos(MouseMode::Pass);
+
+        if let (Some((x, y)), Some((last_x, last_y))) = (position, self.last_position) {
+            let (dx, dy) = (x - last_x, y - last_y);
+
+            if dx != 0.0 || dy != 0.0 {
+                if window.get_mouse_down(MouseButton::Left) {
+                    commands.push(CameraCommand::Orbit {
+                        yaw: dx * ORBIT_SENSITIVITY,
+                        pitch: -dy * ORBIT_SENSITIVITY,
+                    });
+                }
+
+                if window.get_mouse_down(MouseButton::Middle) {
+                    // El desplazamiento escala con la distancia para que se sienta igual de cerca y de lejos
+                    let distance = (camera.center - camera.eye).magnitude();
+                    commands.push(CameraCommand::Pan {
+                        right: -dx * distance * PAN_SENSITIVITY,
+                        up: dy * distance * PAN_SENSITIVITY,
+                    });
+                }
+            }
+        }
+        self.last_position = position;
+
+        let left_down = window.get_mouse_down(MouseButton::Left);
+        match (left_down, self.press_position, position) {
+            (true, None, Some(pos)) => self.press_position = Some(pos),
+            (false, Some((press_x, press_y)), Some((x, y))) => {
+                if (x - press_x).abs() <= CLICK_TOLERANCE && (y - press_y).abs() <= CLICK_TOLERANCE {
+                    self.click = Some((x, y));
+                }
+                self.press_position = None;
+            }
+            (false, Some(_), None) => self.press_position = None,
+            _ => {}
+        }
+
+        if let Some((_, scroll_y)) = window.get_scroll_wheel() {
+            if scroll_y != 0.0 {
+                commands.push(CameraCommand::Zoom(scroll_y * ZOOM_SENSITIVITY));
+            }
+        }
+
+        commands
+    }
+
+    // En modo piloto, arrastrar con el botón izquierdo gira la nave: devuelve (yaw, pitch)
+    pub fn poll_steering(&mut self, window: &Window) -> (f32, f32) {
+        let position = window.get_mouse_pos(MouseMode::Pass);
+        let mut steering = (0.0, 0.0);
+
+        if let (Some((x, y)), Some((last_x, last_y))) = (position, self.last_position) {
+            if window.get_mouse_down(MouseButton::Left) {
+                steering = (-(x - last_x) * STEER_SENSITIVITY, (y - last_y) * STEER_SENSITIVITY);
+            }
+        }
+        self.last_position = position;
+        self.press_position = None;
+
+        steering
+    }
+}
+
+impl Default for MouseInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// W/S aceleran y frenan, A/D giran
+pub fn poll_ship_controls(window: &Window) -> ShipControls {
+    let axis = |positive: Key, negative: Key| -> f32 {
+        match (window.is_key_down(positive), window.is_key_down(negative)) {
+            (true, false) => 1.0,
+            (false, true) => -1.0,
+            _ => 0.0,
+        }
+    };
+
+    ShipControls {
+        thrust: axis(Key::W, Key::S),
+        turn: axis(Key::D, Key::A),
+    }
+}
+
+pub fn apply_camera_commands(camera: &mut Camera, commands: &[CameraCommand]) {
+    for command in commands {
+        match *command {
+            CameraCommand::Orbit { yaw, pitch } => camera.orbit(yaw, pitch),
+            CameraCommand::Zoom(delta) => camera.zoom(delta),
+            CameraCommand::Pan { right, up } => camera.pan(right, up),
+        }
+    }
+}
+
+// Espacio pausa, +/- cambian la velocidad y Retroceso invierte el sentido del tiempo
+pub fn poll_time_controls(window: &Window, clock: &mut Clock) {
+    if window.is_key_pressed(Key::Space, KeyRepeat::No) {
+        clock.toggle_pause();
+    }
+    if window.is_key_pressed(Key::Equal, KeyRepeat::No) || window.is_key_pressed(Key::NumPadPlus, KeyRepeat::No) {
+        clock.speed_up();
+    }
+    if window.is_key_pressed(Key::Minus, KeyRepeat::No) || window.is_key_pressed(Key::NumPadMinus, KeyRepeat::No) {
+        clock.slow_down();
+    }
+    if window.is_key_pressed(Key::Backspace, KeyRepeat::No) {
+        clock.reverse();
+    }
+}
+
+// N pasa a la pista siguiente (con Mayús, a la anterior), F2 pausa la música, F6 la
+// silencia y F7/F8 bajan y suben el volumen. Devuelve true si cambió de pista.
+pub fn poll_audio_controls(window: &Window, audio: &mut AudioManager) -> bool {
+    if window.is_key_pressed(Key::F2, KeyRepeat::No) {
+        audio.toggle_pause();
+    }
+    if window.is_key_pressed(Key::F6, KeyRepeat::No) {
+        audio.toggle_mute();
+    }
+    if window.is_key_pressed(Key::F7, KeyRepeat::Yes) {
+        audio.volume_down();
+    }
+    if window.is_key_pressed(Key::F8, KeyRepeat::Yes) {
+        audio.volume_up();
+    }
+    if window.is_key_pressed(Key::N, KeyRepeat::No) {
+        if window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift) {
+            audio.previous_track();
+        } else {
+            audio.next_track();
+        }
+        return true;
+    }
+    false
+}
+
+// Teclas que cambian una opción, el modo o el tiempo: suenan con un clic
+const OPTION_KEYS: [Key; 18] = [
+    Key::P, Key::M, Key::G, Key::K, Key::O, Key::B, Key::L, Key::T, Key::C, Key::F3, Key::F4,
+    Key::F5, Key::F9, Key::F10, Key::F11, Key::Space, Key::Equal, Key::Minus,
+];
+
+pub fn option_key_pressed(window: &Window) -> bool {
+    OPTION_KEYS.iter().any(|key| window.is_key_pressed(*key, KeyRepeat::No))
+}
+
+const FOLLOW_KEYS: [Key; 6] = [Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6];
+
+// Teclas 1-6 siguen al planeta correspondiente, 0 vuelve a la cámara libre
+pub fn poll_follow_target(window: &Window, current: Option<usize>, planet_count: usize) -> Option<usize> {
+    if window.is_key_pressed(Key::Key0, KeyRepeat::No) {
+        return None;
+    }
+
+    for (index, key) in FOLLOW_KEYS.iter().enumerate() {
+        if index < planet_count && window.is_key_pressed(*key, KeyRepeat::No) {
+            return Some(index);
+        }
+    }
+
+    current
+}
+
+// Flechas izquierda y derecha recorren los sistemas del mapa
+pub fn poll_map_selection(window: &Window, current: usize, system_count: usize) -> usize {
+    if system_count == 0 {
+        return 0;
+    }
+    if window.is_key_pressed(Key::Right, KeyRepeat::Yes) {
+        return (current + 1) % system_count;
+    }
+    if window.is_key_pressed(Key::Left, KeyRepeat::Yes) {
+        return (current + system_count - 1) % system_count;
+    }
+    current
+}
@@ -0,0 +1,69 @@
+pub mod framebuffer;
+pub mod gbuffer;
+pub mod fog;
+pub mod depth;
+pub mod triangle;
+pub mod tiles;
+pub mod vertex;
+pub mod obj;
+pub mod gltf_model;
+pub mod color;
+pub mod fragment;
+pub mod shaders;
+pub mod script;
+pub mod shader_params;
+pub mod bake;
+pub mod export;
+pub mod camera;
+pub mod error;
+pub mod scene;
+pub mod mesh;
+pub mod assets;
+pub mod lod;
+pub mod noise;
+pub mod clipping;
+pub mod orbit;
+pub mod clock;
+pub mod input;
+pub mod picking;
+pub mod texture;
+pub mod postprocess;
+pub mod ssao;
+pub mod toon;
+pub mod grading;
+pub mod lighting;
+pub mod starfield;
+pub mod nebula;
+pub mod line;
+pub mod font;
+pub mod hud;
+pub mod recorder;
+pub mod audio;
+pub mod ambience;
+pub mod sfx;
+pub mod spectrum;
+pub mod ascii;
+pub mod planet;
+pub mod collision;
+pub mod gravity;
+pub mod belt;
+pub mod particles;
+pub mod billboard;
+pub mod prominence;
+pub mod ship;
+pub mod generator;
+pub mod names;
+pub mod save;
+pub mod galaxy;
+pub mod scene_manager;
+pub mod profiler;
+pub mod renderer;
+
+pub use camera::{Camera, CameraFollow};
+pub use color::Color;
+pub use framebuffer::{Framebuffer, HdrFramebuffer};
+pub use planet::{Atmosphere, CloudLayer, Planet, Rings};
+pub use renderer::{Antialias, DebugView, Renderer, RenderOptions, Uniforms};
+pub use error::Error;
+pub use scene::{Scene, SceneError};
+pub use shaders::ShaderKind;
@@ -0,0 +1,346 @@
+use nalgebra_glm::Vec3;
+use serde::Deserialize;
+use std::f32::consts::PI;
+use crate::color::Color;
+
+// Parámetros de brillo especular (Blinn-Phong) de cada shader
+#[derive(Debug, Clone, Copy)]
+pub struct Material {
+    pub shininess: f32,
+    pub specular: f32,
+}
+
+// Material físico metálico-rugoso: metallic va de 0 (dieléctrico, como el hielo o el
+// agua) a 1 (metal: refleja con su propio color y no tiene difuso) y roughness de 0
+// (espejo, reflejo chico e intenso) a 1 (mate, reflejo ancho y tenue)
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct PbrMaterial {
+    #[serde(default)]
+    pub metallic: f32,
+    #[serde(default = "default_roughness")]
+    pub roughness: f32,
+}
+
+fn default_roughness() -> f32 {
+    0.5
+}
+
+// Reflectancia de frente de los dieléctricos
+const DIELECTRIC_F0: f32 = 0.04;
+// Por debajo de esta rugosidad el reflejo GGX de una luz puntual se vuelve un punto
+const MIN_ROUGHNESS: f32 = 0.05;
+
+impl PbrMaterial {
+    // Color (lineal) que refleja la superficie vista de frente
+    pub fn f0(&self, albedo: &Vec3) -> Vec3 {
+        Vec3::repeat(DIELECTRIC_F0).lerp(albedo, self.metallic.clamp(0.0, 1.0))
+    }
+}
+
+// Esfera que puede tapar la luz (un planeta)
+#[derive(Debug, Clone, Copy)]
+pub struct Occluder {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+// Franja del crepúsculo: su centro queda un poco del lado nocturno (el aire sigue
+// iluminado después de que el Sol se pone) y su medio ancho es en coseno del ángulo
+const TWILIGHT_CENTER: f32 = -0.05;
+const TWILIGHT_WIDTH: f32 = 0.15;
+
+// Cuántas luces iluminan la escena a la vez (estrellas, cuerpos brillantes y
+// luces de relleno); las demás estrellas solo brillan
+pub const MAX_LIGHTS: usize = 8;
+
+// Una luz puntual (una estrella, una luna brillante) se atenúa con la distancia;
+// una direccional llega igual a toda la escena desde una dirección, como una luz de
+// relleno para una captura
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LightKind {
+    #[default]
+    Point,
+    Directional,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Light {
+    pub kind: LightKind,
+    // En las direccionales es la dirección hacia la luz
+    pub position: Vec3,
+    pub color: Color,
+    pub intensity: f32,
+    pub ambient: f32,
+    pub attenuation: f32,
+}
+
+impl Light {
+    pub fn new(position: Vec3) -> Self {
+        Light {
+            kind: LightKind::Point,
+            position,
+            color: Color::new(255, 255, 255),
+            intensity: 1.0,
+            ambient: 0.05,
+            attenuation: 0.01,
+        }
+    }
+
+    pub fn directional(direction: Vec3) -> Self {
+        Light {
+            kind: LightKind::Directional,
+            ambient: 0.0,
+            attenuation: 0.0,
+            ..Light::new(direction)
+        }
+    }
+
+    // Dirección normalizada hacia la luz y distancia hasta ella (infinita en las
+    // direccionales), o None si el punto está sobre la luz
+    fn towards(&self, position: &Vec3) -> Option<(Vec3, f32)> {
+        let to_light = match self.kind {
+            LightKind::Point => self.position - position,
+            LightKind::Directional => self.position,
+        };
+        let distance = to_light.magnitude();
+        if distance <= f32::EPSILON {
+            return None;
+        }
+        match self.kind {
+            LightKind::Point => Some((to_light / distance, distance)),
+            LightKind::Directional => Some((to_light / distance, f32::INFINITY)),
+        }
+    }
+
+    fn falloff(&self, distance: f32) -> f32 {
+        match self.kind {
+            LightKind::Point => 1.0 / (1.0 + self.attenuation * distance * distance),
+            LightKind::Directional => 1.0,
+        }
+    }
+
+    // Dirección normalizada hacia la luz desde un punto en espacio de mundo
+    pub fn direction_from(&self, position: &Vec3) -> Vec3 {
+        self.towards(position).map_or(Vec3::new(0.0, 0.0, 0.0), |(light_dir, _)| light_dir)
+    }
+
+    // Término difuso (Lambert) para un punto y normal en espacio de mundo
+    pub fn diffuse(&self, position: &Vec3, normal: &Vec3) -> f32 {
+        let Some((light_dir, distance)) = self.towards(position) else {
+            return self.intensity;
+        };
+
+        let lambert = normal.dot(&light_dir).max(0.0);
+        lambert * self.falloff(distance) * self.intensity
+    }
+
+    // Brillo especular de Blinn-Phong con el vector medio entre luz y vista
+    pub fn specular(&self, position: &Vec3, normal: &Vec3, view_dir: &Vec3, material: &Material) -> f32 {
+        let Some((light_dir, distance)) = self.towards(position) else {
+            return 0.0;
+        };
+        if normal.dot(&light_dir) <= 0.0 {
+            return 0.0;
+        }
+
+        let half_vector = (light_dir + view_dir).normalize();
+        normal.dot(&half_vector).max(0.0).powf(material.shininess) * material.specular * self.falloff(distance) * self.intensity
+    }
+
+    // Fracción de luz que llega al punto (1 = iluminado, 0 = en sombra).
+    // El borde de la sombra se suaviza un poco para simular la penumbra.
+    pub fn visibility(&self, position: &Vec3, occluders: &[Occluder]) -> f32 {
+        let Some((light_dir, distance)) = self.towards(position) else {
+            return 1.0;
+        };
+
+        occluders.iter().fold(1.0, |visibility: f32, occluder| {
+            let t = (occluder.center - position).dot(&light_dir);
+            if t <= 0.0 || t >= distance {
+                return visibility;
+            }
+            // Un cuerpo que es la luz misma (una luna brillante) no se tapa a sí mismo
+            if self.kind == LightKind::Point && (occluder.center - self.position).magnitude() < occluder.radius {
+                return visibility;
+            }
+
+            let closest = position + light_dir * t;
+            let miss = (occluder.center - closest).magnitude();
+            let penumbra = occluder.radius * 0.1;
+            let lit = ((miss - occluder.radius + penumbra) / (2.0 * penumbra)).clamp(0.0, 1.0);
+
+            visibility.min(lit)
+        })
+    }
+}
+
+// Luz de la escena: con direction es direccional (direction apunta hacia la luz) y si
+// no, puntual en position. El color es RGB; ambient es la luz que llega aunque la
+// superficie no mire hacia ella y attenuation la caída con la distancia (solo puntuales).
+// En un cuerpo (una luna brillante) la posición es la del cuerpo y se ignora la de aquí.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LightConfig {
+    #[serde(default)]
+    pub position: [f32; 3],
+    pub direction: Option<[f32; 3]>,
+    #[serde(default = "default_light_color")]
+    pub color: [u8; 3],
+    #[serde(default = "default_light_intensity")]
+    pub intensity: f32,
+    #[serde(default)]
+    pub ambient: f32,
+    #[serde(default = "default_attenuation")]
+    pub attenuation: f32,
+}
+
+fn default_light_color() -> [u8; 3] {
+    [255, 255, 255]
+}
+
+fn default_light_intensity() -> f32 {
+    1.0
+}
+
+fn default_attenuation() -> f32 {
+    0.01
+}
+
+impl From<&LightConfig> for Light {
+    fn from(config: &LightConfig) -> Self {
+        let base = match config.direction {
+            Some([x, y, z]) => Light::directional(Vec3::new(x, y, z)),
+            None => {
+                let [x, y, z] = config.position;
+                Light { attenuation: config.attenuation, ..Light::new(Vec3::new(x, y, z)) }
+            }
+        };
+        let [r, g, b] = config.color;
+        Light {
+            color: Color::new(r, g, b),
+            intensity: config.intensity,
+            ambient: config.ambient,
+            ..base
+        }
+    }
+}
+
+// Visibilidad de cada luz desde un punto, en el mismo orden que lights
+pub fn visibilities(lights: &[Light], position: &Vec3, occluders: &[Occluder]) -> [f32; MAX_LIGHTS] {
+    let mut visibility = [1.0; MAX_LIGHTS];
+    for (visible, light) in visibility.iter_mut().zip(lights) {
+        *visible = light.visibility(position, occluders);
+    }
+    visibility
+}
+
+// Intensidad final de un punto con varias estrellas: cada una aporta su difuso
+// apagado por su propia sombra, y el ambiente se cuenta una sola vez
+pub fn diffuse_lighting(lights: &[Light], position: &Vec3, normal: &Vec3, visibility: &[f32]) -> f32 {
+    let ambient = lights.iter().map(|light| light.ambient).fold(0.0, f32::max);
+    let direct: f32 = lights
+        .iter()
+        .zip(visibility)
+        .map(|(light, visible)| light.diffuse(position, normal) * visible)
+        .sum();
+    ambient + direct
+}
+
+// Suma de los reflejos de todas las estrellas que ve el punto
+pub fn specular_lighting(
+    lights: &[Light],
+    position: &Vec3,
+    normal: &Vec3,
+    view_dir: &Vec3,
+    material: &Material,
+    visibility: &[f32],
+) -> f32 {
+    lights
+        .iter()
+        .zip(visibility)
+        .map(|(light, visible)| light.specular(position, normal, view_dir, material) * visible)
+        .sum()
+}
+
+// Color de la luz que llega al punto: el de cada luz pesado por su aporte difuso,
+// así un blanco puro da 1 y no cambia nada. Los shaders trabajan con una intensidad
+// sola; este color tiñe después su resultado.
+pub fn light_color(lights: &[Light], position: &Vec3, normal: &Vec3, visibility: &[f32]) -> Vec3 {
+    let white = Vec3::new(1.0, 1.0, 1.0);
+    if lights.iter().all(|light| light.color.to_hex() == 0xFFFFFF) {
+        return white;
+    }
+
+    let (sum, total) = lights
+        .iter()
+        .zip(visibility)
+        .fold((Vec3::zeros(), 0.0), |(sum, total), (light, visible)| {
+            let weight = light.diffuse(position, normal) * visible;
+            (sum + light.color.to_linear() * weight, total + weight)
+        });
+    if total <= f32::EPSILON {
+        return white;
+    }
+    sum / total
+}
+
+// La luz que más ilumina al punto (sin contar la orientación de la superficie), que
+// decide el lado diurno en efectos como el terminador: casi siempre la estrella más cercana
+pub fn dominant_light<'a>(lights: &'a [Light], position: &Vec3) -> Option<&'a Light> {
+    let strength = |light: &Light| {
+        light.towards(position).map_or(f32::INFINITY, |(_, distance)| light.falloff(distance) * light.intensity)
+    };
+    lights.iter().max_by(|a, b| strength(a).total_cmp(&strength(b)))
+}
+
+// Peso de la franja del terminador en un punto: 1 sobre la línea donde se pone el
+// Sol y 0 lejos de ella, con una caída suave hacia el día y hacia la noche
+pub fn twilight_band(normal: &Vec3, light_dir: &Vec3) -> f32 {
+    if light_dir.magnitude_squared() <= f32::EPSILON {
+        return 0.0;
+    }
+    let sun_angle = normal.normalize().dot(light_dir);
+    let t = ((sun_angle - TWILIGHT_CENTER).abs() / TWILIGHT_WIDTH).min(1.0);
+    1.0 - t * t * (3.0 - 2.0 * t)
+}
+
+// Reflejo especular de Cook-Torrance de todas las luces: distribución de normales GGX,
+// Fresnel de Schlick y sombreado geométrico de Smith. f0 es el color reflejado de
+// frente (PbrMaterial::f0). El difuso de las luces no se divide por π, así que el
+// especular se multiplica por π para mantener la proporción entre los dos.
+pub fn pbr_specular(
+    lights: &[Light],
+    position: &Vec3,
+    normal: &Vec3,
+    view_dir: &Vec3,
+    f0: &Vec3,
+    roughness: f32,
+    visibility: &[f32],
+) -> Vec3 {
+    let roughness = roughness.clamp(MIN_ROUGHNESS, 1.0);
+    let alpha2 = roughness.powi(4);
+    let k = (roughness + 1.0).powi(2) / 8.0;
+    let n_dot_v = normal.dot(view_dir).max(1e-4);
+    let view_shadowing = n_dot_v / (n_dot_v * (1.0 - k) + k);
+
+    lights.iter().zip(visibility).fold(Vec3::zeros(), |specular, (light, visible)| {
+        let Some((light_dir, distance)) = light.towards(position) else {
+            return specular;
+        };
+        let n_dot_l = normal.dot(&light_dir);
+        if n_dot_l <= 0.0 {
+            return specular;
+        }
+        let Some(half_vector) = (light_dir + view_dir).try_normalize(f32::EPSILON) else {
+            return specular;
+        };
+
+        let n_dot_h = normal.dot(&half_vector).max(0.0);
+        let v_dot_h = view_dir.dot(&half_vector).max(0.0);
+        let distribution = alpha2 / (PI * (n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0).powi(2));
+        let geometry = view_shadowing * n_dot_l / (n_dot_l * (1.0 - k) + k);
+        let fresnel = f0 + (Vec3::repeat(1.0) - f0) * (1.0 - v_dot_h).powi(5);
+
+        let brdf = distribution * geometry / (4.0 * n_dot_v * n_dot_l);
+        specular + fresnel * (brdf * PI * n_dot_l * light.falloff(distance) * light.intensity * visible)
+    })
+}
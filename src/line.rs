@@ -0,0 +1,60 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::color::Color;
+use crate::framebuffer::HdrFramebuffer;
+
+// Dibuja un segmento 3D usando las mismas transformaciones que los triángulos.
+// Se recorta contra el plano cercano y se mezcla con prueba de profundidad;
+// la escritura en el z-buffer depende del estado del DepthBuffer.
+pub fn draw_line_3d(
+    framebuffer: &mut HdrFramebuffer,
+    start: Vec3,
+    end: Vec3,
+    view_projection: &Mat4,
+    viewport_matrix: &Mat4,
+    color: Color,
+) {
+    let mut a = view_projection * Vec4::new(start.x, start.y, start.z, 1.0);
+    let mut b = view_projection * Vec4::new(end.x, end.y, end.z, 1.0);
+
+    // Recorte contra el plano cercano (z >= -w)
+    let da = a.z + a.w;
+    let db = b.z + b.w;
+    if da < 0.0 && db < 0.0 {
+        return;
+    }
+    if da < 0.0 {
+        a = a + (b - a) * (da / (da - db));
+    } else if db < 0.0 {
+        b = b + (a - b) * (db / (db - da));
+    }
+
+    let to_screen = |clip: Vec4| -> Vec3 {
+        let ndc = Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+        let screen = viewport_matrix * ndc;
+        Vec3::new(screen.x, screen.y, screen.z)
+    };
+    let (a, b) = (to_screen(a), to_screen(b));
+
+    for point in dda(a, b) {
+        if point.x >= 0.0 && point.y >= 0.0 {
+            framebuffer.blend_point(point.x as usize, point.y as usize, point.z, color);
+        }
+    }
+}
+
+// Segmento entre dos puntos ya en pantalla, sin prueba de profundidad
+pub fn draw_line_screen(framebuffer: &mut HdrFramebuffer, a: Vec3, b: Vec3, color: Color) {
+    let color = color.to_linear();
+    for point in dda(a, b) {
+        if point.x >= 0.0 && point.y >= 0.0 {
+            framebuffer.set_pixel(point.x as usize, point.y as usize, color);
+        }
+    }
+}
+
+// DDA: un paso por píxel en el eje más largo
+fn dda(a: Vec3, b: Vec3) -> impl Iterator<Item = Vec3> {
+    let steps = (b.x - a.x).abs().max((b.y - a.y).abs()).ceil().min(4096.0) as usize;
+    let count = if steps == 0 { 0 } else { steps + 1 };
+    (0..count).map(move |i| a + (b - a) * (i as f32 / steps as f32))
+}
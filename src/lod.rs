@@ -0,0 +1,108 @@
+use nalgebra_glm::Mat4;
+use std::f32::consts::PI;
+use std::sync::Arc;
+use crate::assets::AssetManager;
+use crate::mesh::Mesh;
+
+// Largo en píxeles de las aristas a partir del cual se pasa a una malla más fina
+const LOD_EDGE_PIXELS: f32 = 4.0;
+// Radio aparente (en píxeles) por debajo del cual un cuerpo se dibuja como impostor
+pub const IMPOSTOR_PIXELS: f32 = 3.0;
+// Niveles que se generan además del más detallado
+const LOD_COARSER_LEVELS: u32 = 3;
+// Ángulo que abarca una arista del icosaedro sin subdividir; cada subdivisión lo parte a la mitad
+const ICOSPHERE_EDGE_ANGLE: f32 = 1.107;
+
+struct LodLevel {
+    // Radio aparente (en píxeles) hasta el que alcanza este nivel
+    max_radius: f32,
+    mesh: Arc<Mesh>,
+}
+
+// Versiones de la misma malla, de la más detallada a la más simple; en cada frame
+// se usa la más simple cuyas aristas no se ven más largas que LOD_EDGE_PIXELS
+pub struct LodMeshes {
+    levels: Vec<LodLevel>,
+    // Radio de la malla más detallada, para descartar el cuerpo si queda fuera de la vista
+    radius: f32,
+}
+
+impl LodMeshes {
+    // Un único nivel, para los modelos cargados de archivo
+    pub fn single(mesh: Arc<Mesh>) -> Self {
+        LodMeshes::finish(vec![LodLevel { max_radius: f32::INFINITY, mesh }])
+    }
+
+    // Icoesferas de subdivisions hacia abajo
+    pub fn icosphere(assets: &mut AssetManager, subdivisions: u32) -> Self {
+        let levels = (subdivisions.saturating_sub(LOD_COARSER_LEVELS)..=subdivisions)
+            .rev()
+            .map(|level| LodLevel {
+                max_radius: LOD_EDGE_PIXELS / (ICOSPHERE_EDGE_ANGLE / 2f32.powi(level as i32)),
+                mesh: assets.icosphere(level),
+            })
+            .collect();
+        LodMeshes::finish(levels)
+    }
+
+    // Esferas UV con la mitad de divisiones en cada nivel
+    pub fn uv_sphere(assets: &mut AssetManager, stacks: u32, slices: u32) -> Self {
+        let levels = (0..=LOD_COARSER_LEVELS)
+            .map(|level| (stacks >> level, slices >> level))
+            .take_while(|&(stacks, slices)| stacks >= 4 && slices >= 6)
+            .map(|(stacks, slices)| {
+                let edge_angle = (PI / stacks as f32).max(2.0 * PI / slices as f32);
+                LodLevel { max_radius: LOD_EDGE_PIXELS / edge_angle, mesh: assets.uv_sphere(stacks, slices) }
+            })
+            .collect::<Vec<_>>();
+
+        // Con muy pocas divisiones no hay niveles más simples que generar
+        if levels.is_empty() {
+            return LodMeshes::single(assets.uv_sphere(stacks, slices));
+        }
+        LodMeshes::finish(levels)
+    }
+
+    // Los mismos niveles transformados (p. ej. con relieve), cada uno por separado
+    pub fn map(&self, transform: impl Fn(&Mesh) -> Mesh) -> Self {
+        LodMeshes::finish(
+            self.levels
+                .iter()
+                .map(|level| LodLevel { max_radius: level.max_radius, mesh: Arc::new(transform(&level.mesh)) })
+                .collect(),
+        )
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    // El nivel más detallado, el que se usa de cerca y el que se exporta
+    pub fn finest(&self) -> &Mesh {
+        &self.levels[0].mesh
+    }
+
+    // Malla para un cuerpo que se ve con ese radio en pantalla
+    pub fn select(&self, projected_radius: f32) -> &Mesh {
+        self.levels
+            .iter()
+            .rev()
+            .find(|level| projected_radius <= level.max_radius)
+            .unwrap_or(&self.levels[0])
+            .mesh
+            .as_ref()
+    }
+
+    // Con la cámara lo bastante cerca, el nivel más detallado se usa siempre
+    fn finish(mut levels: Vec<LodLevel>) -> Self {
+        levels[0].max_radius = f32::INFINITY;
+        let radius = levels[0].mesh.radius();
+        LodMeshes { levels, radius }
+    }
+}
+
+// Radio aparente en píxeles de una esfera a cierta distancia de la cámara
+pub fn projected_radius(radius: f32, distance: f32, projection_matrix: &Mat4, screen_height: f32) -> f32 {
+    let pixels_per_unit = projection_matrix[(1, 1)] * screen_height / 2.0;
+    radius * pixels_per_unit / distance.max(f32::EPSILON)
+}
@@ -1,353 +1,843 @@
-use nalgebra_glm::{Vec3, Mat4, look_at, perspective};
-use minifb::{Key, Window, WindowOptions};
-use std::time::Duration;
+use nalgebra_glm::Vec3;
+use minifb::{Key, KeyRepeat, Window, WindowOptions};
+use std::time::{Duration, Instant};
 use std::f32::consts::PI;
-
-mod framebuffer;
-mod triangle;
-mod vertex;
-mod obj;
-mod color;
-mod fragment;
-mod shaders;
-mod camera;
-
-use rodio::{Decoder, OutputStream, source::Source};
-use std::fs::File;
-use std::io::BufReader;
+use std::io::{IsTerminal, Write};
 use std::thread;
-use std::sync::Arc;
-use std::sync::Mutex;
-use framebuffer::Framebuffer;
-use vertex::Vertex;
-use obj::Obj;
-use camera::Camera;
-use triangle::triangle;
-use shaders::{vertex_shader, fragment_shader};
-use fastnoise_lite::{FastNoiseLite, NoiseType};
-
-pub struct Uniforms {
-    model_matrix: Mat4,
-    view_matrix: Mat4,
-    projection_matrix: Mat4,
-    viewport_matrix: Mat4,
-    time: u32,
-    noise: FastNoiseLite
+use std::process;
+use clap::Parser;
+use planet_renderer::{Antialias, Camera, CameraFollow, DebugView, Renderer, RenderOptions, Scene, ShaderKind};
+use planet_renderer::clock::Clock;
+use planet_renderer::color;
+use planet_renderer::input::{
+    InputMode, MouseInput, apply_camera_commands, option_key_pressed, poll_audio_controls, poll_follow_target, poll_map_selection,
+    poll_ship_controls, poll_time_controls,
+};
+use planet_renderer::picking::{screen_ray, pick_planet};
+use planet_renderer::renderer::{create_view_matrix, create_perspective_matrix, create_viewport_matrix};
+use planet_renderer::hud::{Hud, HudInfo};
+use planet_renderer::error::{Error, Result};
+use planet_renderer::recorder::{self, Recorder};
+use planet_renderer::ascii::{self, AsciiStyle};
+use planet_renderer::audio::AudioManager;
+use planet_renderer::collision::push_out_of_planets;
+use planet_renderer::save::{SaveState, SceneSource};
+use planet_renderer::galaxy::{Galaxy, StarSystem};
+use planet_renderer::scene_manager::SceneManager;
+use planet_renderer::assets::AssetManager;
+use planet_renderer::postprocess::ToneMap;
+use planet_renderer::grading::{ColorGrading, Lut};
+use planet_renderer::sfx::Sfx;
+use planet_renderer::shader_params::{ShaderParams, ShaderParamsFile};
+use planet_renderer::bake::bake_surface;
+use planet_renderer::export::{export_gltf, GltfExport};
+use planet_renderer::profiler::Profiler;
+use tracing::{error, info, warn, Level};
+use tracing_subscriber::filter::filter_fn;
+use tracing_subscriber::prelude::*;
+
+// Factor por el que K multiplica la velocidad del planeta seleccionado en modo gravedad
+const KICK_FACTOR: f32 = 1.2;
+// Distancia mínima entre el ojo de la cámara y la superficie de un planeta
+const CAMERA_RADIUS: f32 = 0.2;
+// Galaxia del mapa (tecla M): semilla por defecto y cantidad de sistemas
+const GALAXY_SEED: u64 = 42;
+const GALAXY_SYSTEMS: usize = 12;
+
+fn default_camera() -> Camera {
+    Camera::new(
+        Vec3::new(0.0, 0.0, 30.0), // Alejamos la cámara para ver todo el sistema
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+    )
 }
 
-pub struct Planet {
-    translation: Vec3,
-    rotation: Vec3,
-    scale: f32,
-    vertex_array: Vec<Vertex>,
-    shader_selection: u32,
+// Vista de la galaxia desde arriba y un poco de costado
+fn map_camera() -> Camera {
+    Camera::new(
+        Vec3::new(0.0, 45.0, 45.0),
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+    )
 }
 
-fn create_noise() -> FastNoiseLite {
-    create_cloud_noise()
-}
+#[derive(Parser)]
+#[command(about = "Simulador del sistema planetario")]
+struct Args {
+    /// Archivo TOML con la descripción de la escena
+    #[arg(long, default_value = "assets/scenes/solar_system.toml")]
+    scene: String,
 
-fn create_cloud_noise() -> FastNoiseLite {
-    let mut noise = FastNoiseLite::with_seed(1337);
-    noise.set_noise_type(Some(NoiseType::OpenSimplex2));
-    noise
-}
+    /// Ancho de la ventana y del framebuffer
+    #[arg(long, default_value_t = 800)]
+    width: usize,
 
-fn create_model_matrix(translation: Vec3, scale: f32, rotation: Vec3) -> Mat4 {
-    let (sin_x, cos_x) = rotation.x.sin_cos();
-    let (sin_y, cos_y) = rotation.y.sin_cos();
-    let (sin_z, cos_z) = rotation.z.sin_cos();
+    /// Alto de la ventana y del framebuffer
+    #[arg(long, default_value_t = 600)]
+    height: usize,
 
-    let rotation_matrix_x = Mat4::new(
-        1.0,  0.0,    0.0,   0.0,
-        0.0,  cos_x, -sin_x, 0.0,
-        0.0,  sin_x,  cos_x, 0.0,
-        0.0,  0.0,    0.0,   1.0,
-    );
+    /// Escala del framebuffer interno: 2 = supersampling, 0.5 = media resolución
+    #[arg(long, default_value_t = 1.0)]
+    render_scale: f32,
 
-    let rotation_matrix_y = Mat4::new(
-        cos_y,  0.0,  sin_y, 0.0,
-        0.0,    1.0,  0.0,   0.0,
-        -sin_y, 0.0,  cos_y, 0.0,
-        0.0,    0.0,  0.0,   1.0,
-    );
+    /// Suavizado de bordes: none, ssaa (cuatro muestras por píxel) o fxaa (filtro sobre la imagen)
+    #[arg(long, default_value = "none")]
+    antialias: Antialias,
 
-    let rotation_matrix_z = Mat4::new(
-        cos_z, -sin_z, 0.0, 0.0,
-        sin_z,  cos_z, 0.0, 0.0,
-        0.0,    0.0,  1.0, 0.0,
-        0.0,    0.0,  0.0, 1.0,
-    );
+    /// Curva de tonos del HDR a la pantalla: aces, reinhard o none (solo recorta)
+    #[arg(long, default_value = "aces")]
+    tone_map: ToneMap,
 
-    let rotation_matrix = rotation_matrix_z * rotation_matrix_y * rotation_matrix_x;
+    /// Multiplicador de la luz antes de la curva de tonos
+    #[arg(long, default_value_t = 1.0)]
+    exposure: f32,
 
-    let transform_matrix = Mat4::new(
-        scale, 0.0,   0.0,   translation.x,
-        0.0,   scale, 0.0,   translation.y,
-        0.0,   0.0,   scale, translation.z,
-        0.0,   0.0,   0.0,   1.0,
-    );
+    /// Contraste de la imagen final (1 = sin cambios)
+    #[arg(long, default_value_t = 1.0)]
+    contrast: f32,
+
+    /// Saturación de la imagen final (1 = sin cambios, 0 = blanco y negro)
+    #[arg(long, default_value_t = 1.0)]
+    saturation: f32,
+
+    /// Temperatura de color de la imagen final, de -1 (fría) a 1 (cálida)
+    #[arg(long, default_value_t = 0.0, allow_hyphen_values = true)]
+    temperature: f32,
+
+    /// LUT 3D en formato .cube que se aplica al final (un "look" de película)
+    #[arg(long)]
+    lut: Option<String>,
+
+    /// Archivo TOML con los colores y constantes de los shaders integrados; se recarga al guardarlo
+    #[arg(long)]
+    shader_params: Option<String>,
+
+    /// Arranca en el estilo de dibujo animado (luz en escalones y contornos); la tecla C lo alterna
+    #[arg(long)]
+    toon: bool,
+
+    /// Mezcla los colores de los shaders sobre los valores sRGB en lugar de en espacio lineal (para comparar)
+    #[arg(long)]
+    srgb_math: bool,
+
+    /// Genera el sistema de esa semilla en lugar de cargar la escena
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Arranca con la simulación de gravedad de N cuerpos en lugar de las órbitas fijas
+    #[arg(long)]
+    gravity: bool,
+
+    /// Música de fondo: un archivo (la lista sigue con el resto de su carpeta) o una carpeta de pistas
+    #[arg(long, default_value = "assets/music/Good Egg Galaxy  Super Mario Galaxy.mp3")]
+    music: String,
+
+    /// Desactiva la música
+    #[arg(long)]
+    no_music: bool,
+
+    /// Desactiva todo el sonido: la música y el ambiente de los planetas
+    #[arg(long)]
+    no_audio: bool,
+
+    /// Ventana sin bordes en la esquina de la pantalla (usar con la resolución del monitor)
+    #[arg(long)]
+    fullscreen: bool,
+
+    /// Al grabar con F11, guarda un PNG cada N frames
+    #[arg(long, default_value_t = 2)]
+    record_skip: u32,
+
+    /// Renderiza sin ventana ni audio y guarda los frames como PNG
+    #[arg(long)]
+    headless: bool,
+
+    /// Cantidad de frames en modo headless y en --ascii
+    #[arg(long, default_value_t = 600)]
+    frames: u32,
+
+    /// Carpeta de salida en modo headless y de las texturas de --bake
+    #[arg(long, default_value = "frames")]
+    out: String,
+
+    /// Dibuja en la terminal en lugar de abrir una ventana: plain (caracteres según el brillo) o ansi (bloques de color)
+    #[arg(long)]
+    ascii: Option<AsciiStyle>,
+
+    /// Ancho en caracteres del modo --ascii (el alto sale de --width y --height)
+    #[arg(long, default_value_t = 120)]
+    ascii_columns: usize,
+
+    /// Exporta la superficie de cada planeta como textura equirrectangular en --out y termina
+    #[arg(long)]
+    bake: bool,
+
+    /// Ancho de las texturas de --bake (el alto es la mitad)
+    #[arg(long, default_value_t = 2048)]
+    bake_width: usize,
+
+    /// Con --bake, exporta también el mapa de alturas de los planetas con relieve
+    #[arg(long)]
+    bake_heights: bool,
+
+    /// Exporta el sistema como glTF (.gltf o .glb) con texturas y la animación de las órbitas y termina
+    #[arg(long)]
+    export_gltf: Option<String>,
+
+    /// Segundos de simulación de la animación exportada (por defecto, el periodo de la órbita más larga)
+    #[arg(long)]
+    export_duration: Option<f32>,
+
+    /// Claves de animación por segundo de simulación en el glTF
+    #[arg(long, default_value_t = 10.0)]
+    export_frame_rate: f32,
+
+    /// Ancho de las texturas horneadas del glTF (el alto es la mitad)
+    #[arg(long, default_value_t = 1024)]
+    export_texture_width: usize,
+
+    /// Archivo donde F5 guarda el estado de la simulación y del que F9 lo recupera
+    #[arg(long, default_value = "save.toml")]
+    save_file: String,
 
-    transform_matrix * rotation_matrix
+    /// Continúa desde un estado guardado en lugar de cargar la escena
+    #[arg(long)]
+    load: Option<String>,
+
+    /// Escribe en la consola, cada segundo, cuánto tarda cada etapa del render y cada cuerpo
+    #[arg(long)]
+    profile: bool,
 }
 
+fn scene_source(args: &Args) -> SceneSource {
+    match args.seed {
+        Some(seed) => SceneSource::Generated { seed },
+        None => SceneSource::File { path: args.scene.clone() },
+    }
+}
 
-fn create_view_matrix(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
-    look_at(&eye, &center, &up)
+// Opciones de render iniciales según los argumentos
+fn render_options(args: &Args) -> Result<RenderOptions> {
+    let lut = args.lut.as_deref().map(Lut::load).transpose()?;
+
+    Ok(RenderOptions {
+        tone_map: args.tone_map,
+        exposure: args.exposure,
+        toon: args.toon,
+        grading: ColorGrading {
+            contrast: args.contrast,
+            saturation: args.saturation,
+            temperature: args.temperature,
+            lut,
+        },
+        ..RenderOptions::default()
+    })
 }
 
-fn create_perspective_matrix(window_width: f32, window_height: f32) -> Mat4 {
-    let fov = 45.0 * PI / 180.0;
-    let aspect_ratio = window_width / window_height;
-    let near = 0.1;
-    let far = 1000.0;
+// Abre el archivo de parámetros de los shaders, si se indicó uno, y deja sus
+// valores en las opciones de render
+fn open_shader_params(args: &Args, options: &mut RenderOptions) -> Result<Option<ShaderParamsFile>> {
+    let Some(path) = args.shader_params.as_deref() else {
+        return Ok(None);
+    };
+    let (file, params) = ShaderParamsFile::open(path)?;
+    options.shader_params = params;
+    Ok(Some(file))
+}
 
-    perspective(fov, aspect_ratio, near, far)
+// Vuelve a leer los parámetros de los shaders si el archivo cambió; con un error
+// se siguen usando los anteriores
+fn reload_shader_params(file: &mut ShaderParamsFile, options: &mut RenderOptions, hud: &mut Hud) {
+    match file.reload_if_changed() {
+        Ok(Some(params)) => {
+            options.shader_params = params;
+            info!("Parámetros recargados: {}", file.path());
+        }
+        Ok(None) => {}
+        Err(err) => report(hud, &err),
+    }
 }
 
-fn create_viewport_matrix(width: f32, height: f32) -> Mat4 {
-    Mat4::new(
-        width / 2.0, 0.0, 0.0, width / 2.0,
-        0.0, -height / 2.0, 0.0, height / 2.0,
-        0.0, 0.0, 1.0, 0.0,
-        0.0, 0.0, 0.0, 1.0
-    )
+// Recompila los shaders de script que cambiaron e informa sus errores; con un
+// error de compilación el planeta sigue con la versión anterior
+fn reload_scripts(scene: &mut Scene, hud: &mut Hud) {
+    for script in scene.planets.iter_mut().filter_map(|planet| planet.script.as_mut()) {
+        match script.reload_if_changed() {
+            Ok(true) => info!("Shader recargado: {}", script.path()),
+            Ok(false) => {}
+            Err(err) => report(hud, &err),
+        }
+        if let Some(err) = script.take_error() {
+            report(hud, &format!("en el shader {}", err));
+        }
+    }
 }
 
-fn play_music(file_path: &str, stop_signal: Arc<Mutex<bool>>) {
-    // Crea un nuevo stream de salida
-    let (_stream, stream_handle) = OutputStream::try_default().unwrap();
+// Un error durante la simulación no la corta: se informa en la consola y en la ventana
+fn report(hud: &mut Hud, err: &dyn std::fmt::Display) {
+    error!("{}", err);
+    hud.notify(format!("Error: {}", err));
+}
 
-    // Abre el archivo de audio
-    let file = File::open(file_path).unwrap();
-    let source = Decoder::new(BufReader::new(file)).unwrap();
+// Los spans del renderizador siempre llegan al perfilador (F10 muestra sus tiempos).
+// Los mensajes del programa van a la consola (stderr) desde INFO, sin los spans;
+// los informes del perfilador solo se escriben con --profile (ver end_profile_frame)
+fn init_tracing() -> Profiler {
+    let profiler = Profiler::new();
+    let console = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .with_filter(filter_fn(|metadata| metadata.is_event() && *metadata.level() <= Level::INFO));
+    tracing_subscriber::registry().with(profiler.layer()).with(console).init();
+    profiler
+}
 
-    // Reproduce la música
-    stream_handle.play_raw(source.convert_samples()).unwrap();
+// Cierra el frame en el perfilador y, con --profile, escribe el informe cuando hay uno nuevo
+fn end_profile_frame(profiler: &mut Profiler, args: &Args) {
+    if let (Some(report), true) = (profiler.end_frame(), args.profile) {
+        report.log();
+    }
+}
 
-    // Mantén el programa corriendo mientras se reproduce la música
-    while !*stop_signal.lock().unwrap() {
-        thread::sleep(std::time::Duration::from_millis(100));
+// Se informa la semilla de un sistema generado para poder volver a crearlo
+fn build_scene(source: &SceneSource, assets: &mut AssetManager) -> Result<Scene> {
+    if let SceneSource::Generated { seed } = source {
+        info!("Sistema generado con la semilla {}", seed);
     }
+    Ok(source.build(assets)?)
 }
 
-fn render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex], shader_selection: u32) {
-    // Vertex Shader
-    let mut transformed_vertices = Vec::with_capacity(vertex_array.len());
-    for vertex in vertex_array {
-        let transformed = vertex_shader(vertex, uniforms);
-        transformed_vertices.push(transformed);
-    }
-
-    // Primitive Assembly
-    let mut triangles = Vec::new();
-    for i in (0..transformed_vertices.len()).step_by(3) {
-        if i + 2 < transformed_vertices.len() {
-            triangles.push([
-                transformed_vertices[i].clone(),
-                transformed_vertices[i + 1].clone(),
-                transformed_vertices[i + 2].clone(),
-            ]);
-        }
-    }
-
-    // Rasterization
-    let mut fragments = Vec::new();
-    for tri in &triangles {
-        fragments.extend(triangle(&tri[0], &tri[1], &tri[2], shader_selection));
-    }
-
-    // Fragment Processing
-    for fragment in fragments {
-        let x = fragment.position.x as usize;
-        let y = fragment.position.y as usize;
-        let mut shaded_color = fragment_shader(&fragment, &uniforms, "lava");
-
-        if x < framebuffer.width && y < framebuffer.height {
-            if shader_selection == 0 {
-                shaded_color = fragment_shader(&fragment, &uniforms, "lava");
-            } else if shader_selection == 1 {
-                shaded_color = fragment_shader(&fragment, &uniforms, "ice");
-            } else if shader_selection == 2 {
-                shaded_color = fragment_shader(&fragment, &uniforms, "cloud");
-            } else if shader_selection == 3 {
-                shaded_color = fragment_shader(&fragment, &uniforms, "jupiter");
-            } else if shader_selection == 4{
-                shaded_color = fragment_shader(&fragment, &uniforms, "ring");
-            } else if shader_selection == 5{
-                shaded_color = fragment_shader(&fragment, &uniforms, "metal");
-            }
-            let color = shaded_color.to_hex();
-            framebuffer.set_current_color(color);
-            framebuffer.point(x, y, fragment.depth);
+// Evalúa los shaders de cada planeta sobre una grilla de latitud y longitud y guarda
+// las texturas como <planeta>.png (y <planeta>_height.png) en la carpeta de salida
+fn run_bake(scene: &Scene, sim_time: f32, args: &Args) -> Result<()> {
+    std::fs::create_dir_all(&args.out).map_err(|err| Error::Io(args.out.clone(), err))?;
+
+    let params = load_shader_params(args)?;
+    for planet in &scene.planets {
+        let baked = bake_surface(planet, &params, args.bake_width, sim_time, args.bake_heights);
+        let name: String = planet.name
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+
+        let out = std::path::Path::new(&args.out);
+        let albedo_path = out.join(format!("{}.png", name));
+        baked.save_albedo(&albedo_path).map_err(|err| Error::Image(albedo_path.display().to_string(), err))?;
+        info!("Exportado {}", albedo_path.display());
+
+        if baked.heights.is_some() {
+            let height_path = out.join(format!("{}_height.png", name));
+            baked.save_heights(&height_path).map_err(|err| Error::Image(height_path.display().to_string(), err))?;
+            info!("Exportado {}", height_path.display());
         }
     }
+    Ok(())
 }
 
-fn main() {
-    let file_path = "assets/music/Good Egg Galaxy  Super Mario Galaxy.mp3";
-    let stop_signal = Arc::new(Mutex::new(false));
-    let stop_signal_clone = Arc::clone(&stop_signal);
+// Parámetros de los shaders para --bake y --export-gltf, sin recarga
+fn load_shader_params(args: &Args) -> Result<ShaderParams> {
+    Ok(args.shader_params.as_deref().map(ShaderParams::load).transpose()?.unwrap_or_default())
+}
 
-    let music_thread = thread::spawn(move || {
-        play_music(file_path, stop_signal_clone);
+// Guarda el sistema en glTF; la animación dura por defecto una vuelta del cuerpo más lejano
+fn run_export(mut scene: Scene, start_time: f32, path: &str, args: &Args) -> Result<()> {
+    let duration = args.export_duration.unwrap_or_else(|| {
+        scene.planets
+            .iter()
+            .filter_map(|planet| planet.orbit.as_ref())
+            .map(|orbit| orbit.period.abs())
+            .fold(0.0, f32::max)
     });
+    let settings = GltfExport {
+        texture_width: args.export_texture_width,
+        duration: if duration > 0.0 { duration } else { 10.0 },
+        start_time,
+        frame_rate: args.export_frame_rate.max(0.1),
+    };
+
+    let params = load_shader_params(args)?;
+    export_gltf(&mut scene, &params, &settings, path)?;
+    info!("Exportado {} ({} s de animación)", path, settings.duration);
+    Ok(())
+}
+
+// Simula y dibuja sin ventana ni audio, guardando cada frame como PNG
+fn run_headless(mut scene: Scene, camera: Camera, start_time: f32, profiler: &mut Profiler, args: &Args) -> Result<()> {
+    std::fs::create_dir_all(&args.out).map_err(|err| Error::Io(args.out.clone(), err))?;
+
+    let mut renderer = Renderer::with_antialias(args.width, args.height, args.render_scale, args.antialias);
+    let mut render_options = render_options(args)?;
+    open_shader_params(args, &mut render_options)?;
+
+    // Paso fijo: el resultado no depende de la velocidad de la máquina
+    let step = 1.0 / 60.0;
+    for frame in 0..args.frames {
+        let sim_time = start_time + frame as f32 * step;
+        scene.update(sim_time, if frame == 0 { 0.0 } else { step });
+        renderer.render(&scene, &camera, sim_time, &render_options);
+        end_profile_frame(profiler, args);
+
+        let path = std::path::Path::new(&args.out).join(format!("frame_{:05}.png", frame));
+        recorder::save_png(&path, renderer.pixels(), args.width, args.height)
+            .map_err(|err| Error::Image(path.display().to_string(), err))?;
+    }
+
+    info!("{} frames guardados en {}", args.frames, args.out);
+    Ok(())
+}
+
+// Simula y dibuja la escena como texto en la terminal, a la velocidad de la
+// simulación, durante --frames frames. Si la salida no es una terminal (un archivo,
+// una tubería) los frames se escriben uno detrás de otro, sin mover el cursor.
+fn run_ascii(mut scene: Scene, camera: Camera, start_time: f32, style: AsciiStyle, profiler: &mut Profiler, args: &Args) -> Result<()> {
+    // Dos píxeles de ancho por carácter, con la proporción de --width y --height; al
+    // convertir se promedian los que caen en cada uno
+    let columns = args.ascii_columns.max(1);
+    let width = columns * 2;
+    let height = ((width * args.height) as f32 / args.width as f32).round().max(1.0) as usize;
+    let mut renderer = Renderer::with_antialias(width, height, args.render_scale, args.antialias);
+    let mut render_options = render_options(args)?;
+    open_shader_params(args, &mut render_options)?;
+
+    let mut stdout = std::io::stdout().lock();
+    let terminal = stdout.is_terminal();
+    if terminal {
+        // Limpia la pantalla y esconde el cursor
+        let _ = write!(stdout, "\x1b[2J\x1b[?25l");
+    }
+
+    let step = 1.0 / 60.0;
+    for frame in 0..args.frames {
+        let started = Instant::now();
+        let sim_time = start_time + frame as f32 * step;
+        scene.update(sim_time, if frame == 0 { 0.0 } else { step });
+        renderer.render(&scene, &camera, sim_time, &render_options);
+        end_profile_frame(profiler, args);
+
+        let text = ascii::to_ascii(renderer.pixels(), width, height, columns, style);
+        let written = if terminal {
+            write!(stdout, "\x1b[H{}", text)
+        } else {
+            writeln!(stdout, "{}", text)
+        };
+        // La terminal o la tubería se cerró: no tiene sentido seguir dibujando
+        if written.and_then(|_| stdout.flush()).is_err() {
+            break;
+        }
+        if terminal {
+            thread::sleep(Duration::from_secs_f32(step).saturating_sub(started.elapsed()));
+        }
+    }
+
+    if terminal {
+        let _ = write!(stdout, "\x1b[?25h");
+    }
+    Ok(())
+}
+
+// Los errores del arranque (un archivo que falta, un modelo que no se puede leer,
+// la ventana que no se abre) llegan hasta acá y terminan el programa con un mensaje
+fn main() {
+    let args = Args::parse();
+    let mut profiler = init_tracing();
+    if let Err(err) = run(args, &mut profiler) {
+        error!("{}", err);
+        process::exit(1);
+    }
+}
+
+fn run(args: Args, profiler: &mut Profiler) -> Result<()> {
+    // NaN no es menor ni mayor que 0, así que se rechaza aparte junto con el infinito
+    if !args.render_scale.is_finite() || args.render_scale <= 0.0 {
+        return Err(Error::Argument("--render-scale debe ser un número mayor que 0".to_string()));
+    }
+    color::set_linear_math(!args.srgb_math);
+
+    // Configuración de planetas desde el archivo de escena, el generador o un estado guardado
+    let saved = args.load.as_deref().map(SaveState::load).transpose()?;
+    let source = saved.as_ref().map(|saved| saved.source.clone()).unwrap_or_else(|| scene_source(&args));
+    let mut assets = AssetManager::new();
+    let mut scene = build_scene(&source, &mut assets)?;
+
+    let mut camera = default_camera();
+    let mut clock = Clock::new(1.0 / 60.0);
+    if let Some(saved) = &saved {
+        saved.apply(&mut scene, &mut camera, &mut clock)?;
+    }
+
+    if args.gravity && scene.gravity.is_none() {
+        scene.toggle_gravity(clock.sim_time);
+    }
+
+    if args.bake {
+        return run_bake(&scene, clock.sim_time, &args);
+    }
+
+    if let Some(path) = &args.export_gltf {
+        return run_export(scene, clock.sim_time, path, &args);
+    }
+
+    if args.headless {
+        return run_headless(scene, camera, clock.sim_time, profiler, &args);
+    }
+
+    if let Some(style) = args.ascii {
+        return run_ascii(scene, camera, clock.sim_time, style, profiler, &args);
+    }
+
+    // El sonido se corta solo al terminar, cuando se suelta el administrador. Sin
+    // dispositivo de audio o sin pistas el simulador sigue igual, en silencio.
+    let mut audio = if args.no_audio {
+        None
+    } else {
+        match AudioManager::new() {
+            Ok(audio) => Some(audio),
+            Err(err) => {
+                warn!("{}; se sigue sin sonido (--no-audio evita este aviso)", err);
+                None
+            }
+        }
+    };
+    if let Some(audio) = &mut audio {
+        audio.set_volumes(scene.sound);
+    }
+    if let (Some(audio), false) = (&mut audio, args.no_music) {
+        match audio.play_music(&args.music) {
+            Ok(()) => info!("Música: {}", audio.track_name()),
+            Err(err) => warn!("{}; se sigue sin música (--no-music evita este aviso)", err),
+        }
+    }
 
-    let window_width = 800;
-    let window_height = 600;
-    let framebuffer_width = 800;
-    let framebuffer_height = 600;
     let frame_delay = Duration::from_millis(16);
-    let mut shader_selection = 0;
-
-    // Configuración de planetas
-    let mut planets = vec![
-        Planet {
-            translation: Vec3::new(0.0, 0.0, 0.0), // El Sol en el centro
-            rotation: Vec3::new(0.0, 0.0, 0.0),
-            scale: 1.5, // Tamaño mayor para el Sol
-            vertex_array: Obj::load("assets/models/sphere.obj")
-                .expect("Failed to load sphere.obj")
-                .get_vertex_array(),
-            shader_selection: 0, // Shader para el Sol
-        },
-        Planet {
-            translation: Vec3::new(3.0, 0.0, 0.0), // Posición inicial del planeta
-            rotation: Vec3::new(0.0, 0.0, 0.0),
-            scale: 0.5, // Tamaño del planeta
-            vertex_array: Obj::load("assets/models/sphere.obj")
-                .expect("Failed to load planet.obj")
-                .get_vertex_array(),
-            shader_selection: 1, // Shader para el planeta
-        },
-        Planet {
-            translation: Vec3::new(4.0, 0.0, 0.0), // Posición inicial del planeta
-            rotation: Vec3::new(0.0, 0.0, 0.0),
-            scale: 0.5, // Tamaño del planeta
-            vertex_array: Obj::load("assets/models/sphere.obj")
-                .expect("Failed to load planet.obj")
-                .get_vertex_array(),
-            shader_selection: 2, // Shader para el planeta
-        },
-        Planet {
-            translation: Vec3::new(6.0, 0.0, 0.0), // Posición inicial del planeta
-            rotation: Vec3::new(0.0, 0.0, 0.0),
-            scale: 0.5, // Tamaño del planeta
-            vertex_array: Obj::load("assets/models/sphere.obj")
-                .expect("Failed to load planet.obj")
-                .get_vertex_array(),
-            shader_selection: 3, // Shader para el planeta
-        },
-        Planet {
-            translation: Vec3::new(8.0, 0.0, 0.0), // Posición inicial del planeta
-            rotation: Vec3::new(0.0, 0.0, 0.0),
-            scale: 0.5, // Tamaño del planeta
-            vertex_array: Obj::load("assets/models/sphere.obj")
-                .expect("Failed to load planet.obj")
-                .get_vertex_array(),
-            shader_selection: 4, // Shader para el planeta
-        },
-        Planet {
-            translation: Vec3::new(10.0, 0.0, 0.0), // Posición inicial del planeta
-            rotation: Vec3::new(0.0, 0.0, 0.0),
-            scale: 0.5, // Tamaño del planeta
-            vertex_array: Obj::load("assets/models/sphere.obj")
-                .expect("Failed to load planet.obj")
-                .get_vertex_array(),
-            shader_selection: 5, // Shader para el planeta
-        },
-    ];
+    let mut shader_selection = ShaderKind::Lava;
 
-    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+    let mut renderer = Renderer::with_antialias(args.width, args.height, args.render_scale, args.antialias);
     let mut window = Window::new(
         "Simulador del sistema planetario",
-        window_width,
-        window_height,
-        WindowOptions::default(),
+        args.width,
+        args.height,
+        WindowOptions {
+            borderless: args.fullscreen,
+            topmost: args.fullscreen,
+            resize: true,
+            ..WindowOptions::default()
+        },
     )
-    .unwrap();
+    .map_err(Error::Window)?;
 
-    window.set_position(500, 500);
+    if args.fullscreen {
+        window.set_position(0, 0);
+    } else {
+        window.set_position(500, 500);
+    }
     window.update();
 
-    framebuffer.set_background_color(0x000);
-
-    // Parámetros de la cámara
-    let mut camera = Camera::new(
-        Vec3::new(0.0, 0.0, 30.0), // Alejamos la cámara para ver todo el sistema
-        Vec3::new(0.0, 0.0, 0.0),
-        Vec3::new(0.0, 1.0, 0.0),
+    // El sistema de partida es el primero del mapa; el resto sale de la semilla de la
+    // galaxia, que es la del sistema de partida si es generado
+    let galaxy_seed = match &source {
+        SceneSource::Generated { seed } => *seed,
+        SceneSource::File { .. } => GALAXY_SEED,
+    };
+    let galaxy = Galaxy::generate(
+        galaxy_seed,
+        GALAXY_SYSTEMS,
+        StarSystem::from_scene(&scene, source.clone()),
     );
-
-    let mut time = 0;
+    let mut manager = SceneManager::new(scene, source, assets);
+    let mut map_camera = map_camera();
+    let mut map_selection = 0;
+
+    let mut mouse = MouseInput::new();
+    let mut follow = CameraFollow::new();
+    let mut render_options = render_options(&args)?;
+    let mut shader_params = open_shader_params(&args, &mut render_options)?;
+    let mut hud = Hud::new();
+    let mut input_mode = InputMode::Orbit;
+    let mut show_profile = false;
+    // F11 graba un PNG cada --record-skip frames
+    let mut recorder = Recorder::new("recordings", args.record_skip);
 
     while window.is_open() {
         if window.is_key_down(Key::Escape) {
             break;
         }
 
-        time += 1;
+        // Si cambia el tamaño de la ventana se reasigna el framebuffer;
+        // las matrices de proyección y viewport salen de su tamaño en cada frame
+        let (window_width, window_height) = window.get_size();
+        if window_width > 0
+            && window_height > 0
+            && (window_width, window_height) != (renderer.width(), renderer.height())
+        {
+            renderer.resize(window_width, window_height);
+        }
 
-        shader_selection = handle_input(&window, &mut camera, shader_selection);
+        // Los shaders en script se recargan al guardar el archivo
+        reload_scripts(&mut manager.scene, &mut hud);
+        if let Some(file) = &mut shader_params {
+            reload_shader_params(file, &mut render_options, &mut hud);
+        }
 
-        framebuffer.clear();
+        // Efectos de sonido de este frame; suenan todos juntos al final
+        let mut effects = Vec::new();
+        if option_key_pressed(&window) {
+            effects.push(Sfx::Click);
+        }
 
-        // Matrices de vista y proyección
-        let view_matrix = create_view_matrix(camera.eye, camera.center, camera.up);
-        let projection_matrix = create_perspective_matrix(window_width as f32, window_height as f32);
-        let viewport_matrix = create_viewport_matrix(
-            framebuffer_width as f32,
-            framebuffer_height as f32,
-        );
+        poll_time_controls(&window, &mut clock);
+        if let Some(audio) = &mut audio {
+            let pressed = poll_audio_controls(&window, audio);
+            if audio.update() || pressed {
+                info!("Música: {}", audio.track_name());
+            }
+            audio.update_ambients(&camera, &manager.scene.planets);
+        }
+        // Los shaders del Sol y de la lava laten con la música
+        render_options.music = audio.as_ref().map(AudioManager::levels).unwrap_or_default();
+        clock.tick();
+        while clock.step() {
+            manager.scene.update(clock.sim_time, clock.step_delta());
+        }
 
-        for (index, planet) in planets.iter_mut().enumerate() {
-            if index == 0 {
-                // El Sol no se mueve
-                planet.rotation.y += 0.02; // Rotación del Sol
-            } else {
-                // Los planetas orbitan alrededor del Sol
-                let angle = time as f32 * 0.01 * (index as f32); // Ángulo para la órbita
-                let distance = 3.0 + (index as f32) * 2.0; // Distancia desde el Sol
-                planet.translation.x = distance * angle.cos();
-                planet.translation.z = distance * angle.sin();
-                planet.rotation.y += 0.02; // Rotación del planeta
+        // Salto entre sistemas: al cambiar de escena los índices de planetas dejan de valer
+        match manager.update_warp(&mut camera, clock.frame_delta) {
+            Ok(true) => {
+                follow.set_target(None, &camera);
+                render_options.selected_planet = None;
             }
+            Ok(false) => {}
+            Err(err) => report(&mut hud, &err),
+        }
+        render_options.warp = manager.warp_intensity();
 
-            let model_matrix = create_model_matrix(
-                planet.translation,
-                planet.scale,
-                planet.rotation,
-            );
+        // P alterna entre la cámara orbital y el pilotaje de la nave
+        if window.is_key_pressed(Key::P, KeyRepeat::No) && manager.scene.ship.is_some() {
+            input_mode = match input_mode {
+                InputMode::Orbit => InputMode::Pilot,
+                InputMode::Pilot | InputMode::Map => InputMode::Orbit,
+            };
+            follow.set_target(None, &camera);
+            if let Some(ship) = &mut manager.scene.ship {
+                ship.controls = Default::default();
+            }
+        }
 
-            let uniforms = Uniforms {
-                model_matrix,
-                view_matrix,
-                projection_matrix,
-                viewport_matrix,
-                time,
-                noise: create_noise(),
+        // M abre y cierra el mapa de la galaxia, con el sistema actual seleccionado
+        if window.is_key_pressed(Key::M, KeyRepeat::No) && !manager.is_warping() {
+            input_mode = match input_mode {
+                InputMode::Map => InputMode::Orbit,
+                InputMode::Orbit | InputMode::Pilot => {
+                    map_selection = galaxy.find(&manager.source).unwrap_or(0);
+                    InputMode::Map
+                }
             };
+        }
+
+        // G alterna el modo de gravedad; K empuja al planeta seleccionado
+        if window.is_key_pressed(Key::G, KeyRepeat::No) {
+            manager.scene.toggle_gravity(clock.sim_time);
+        }
+        if window.is_key_pressed(Key::K, KeyRepeat::No) {
+            if let (Some(gravity), Some(index)) = (&mut manager.scene.gravity, render_options.selected_planet) {
+                gravity.kick(index, KICK_FACTOR);
+            }
+        }
+
+        if window.is_key_pressed(Key::O, KeyRepeat::No) {
+            render_options.show_orbits = !render_options.show_orbits;
+        }
+        if window.is_key_pressed(Key::B, KeyRepeat::No) {
+            render_options.bloom = !render_options.bloom;
+        }
+        if window.is_key_pressed(Key::L, KeyRepeat::No) {
+            render_options.lens_flare = !render_options.lens_flare;
+        }
+        if window.is_key_pressed(Key::T, KeyRepeat::No) {
+            render_options.dither = !render_options.dither;
+        }
+        if window.is_key_pressed(Key::C, KeyRepeat::No) {
+            render_options.toon = !render_options.toon;
+        }
+        if window.is_key_pressed(Key::F4, KeyRepeat::No) {
+            render_options.ssao = !render_options.ssao;
+        }
+        if window.is_key_pressed(Key::F3, KeyRepeat::No) {
+            render_options.debug_view = render_options.debug_view.next();
+        }
+        if window.is_key_pressed(Key::F10, KeyRepeat::No) {
+            show_profile = !show_profile;
+        }
+        if window.is_key_pressed(Key::F11, KeyRepeat::No) {
+            recorder.toggle();
+        }
+        // R reemplaza la escena por un sistema nuevo; los índices de planetas dejan de valer
+        if window.is_key_pressed(Key::R, KeyRepeat::No) {
+            let next = SceneSource::Generated { seed: rand::random() };
+            match build_scene(&next, &mut manager.assets) {
+                Ok(generated) => {
+                    manager.replace(generated, next);
+                    effects.push(Sfx::Generate);
+                    follow.set_target(None, &camera);
+                    render_options.selected_planet = None;
+                }
+                Err(err) => report(&mut hud, &err),
+            }
+        }
 
-            render(
-                &mut framebuffer,
-                &uniforms,
-                &planet.vertex_array,
-                planet.shader_selection,
+        // F5 guarda el estado de la simulación y F9 vuelve a él
+        if window.is_key_pressed(Key::F5, KeyRepeat::No) {
+            match SaveState::capture(&manager.source, &manager.scene, &camera, &clock).save(&args.save_file) {
+                Ok(()) => info!("Estado guardado en {}", args.save_file),
+                Err(err) => report(&mut hud, &err),
+            }
+        }
+        if window.is_key_pressed(Key::F9, KeyRepeat::No) {
+            let loaded = SaveState::load(&args.save_file).and_then(|saved| {
+                let mut loaded_scene = saved.source.build(&mut manager.assets)?;
+                saved.apply(&mut loaded_scene, &mut camera, &mut clock)?;
+                Ok((saved.source, loaded_scene))
+            });
+            match loaded {
+                Ok((loaded_source, loaded_scene)) => {
+                    manager.replace(loaded_scene, loaded_source);
+                    input_mode = InputMode::Orbit;
+                    follow.set_target(None, &camera);
+                    render_options.selected_planet = None;
+                    info!("Estado cargado de {}", args.save_file);
+                }
+                Err(err) => report(&mut hud, &err),
+            }
+        }
+
+        // Durante el salto la cámara la mueve el SceneManager
+        let warping = manager.is_warping();
+        match (input_mode, &mut manager.scene.ship) {
+            _ if warping => {}
+            // La nave usa el tiempo real, no el de la simulación, para no depender de la velocidad
+            (InputMode::Pilot, Some(ship)) => {
+                let (yaw, pitch) = mouse.poll_steering(&window);
+                ship.steer(yaw, pitch);
+                ship.controls = poll_ship_controls(&window);
+                ship.update(clock.frame_delta);
+                ship.collide(&manager.scene.planets);
+                ship.chase(&mut camera, clock.frame_delta);
+            }
+            // En el mapa el mouse gira la galaxia, el clic o las flechas eligen un sistema
+            // y Enter salta hacia él
+            (InputMode::Map, _) => {
+                let mouse_commands = mouse.poll(&window, &map_camera);
+                apply_camera_commands(&mut map_camera, &mouse_commands);
+                let previous_selection = map_selection;
+                map_selection = poll_map_selection(&window, map_selection, galaxy.systems.len());
+
+                if let Some((mouse_x, mouse_y)) = mouse.take_click() {
+                    let view_projection = create_perspective_matrix(window_width as f32, window_height as f32)
+                        * create_view_matrix(map_camera.eye, map_camera.center, map_camera.up);
+                    let viewport = create_viewport_matrix(window_width as f32, window_height as f32);
+                    if let Some(index) = galaxy.pick(mouse_x, mouse_y, &view_projection, &viewport) {
+                        map_selection = index;
+                    }
+                }
+                if map_selection != previous_selection {
+                    effects.push(Sfx::Select);
+                }
+
+                if window.is_key_pressed(Key::Enter, KeyRepeat::No) {
+                    if galaxy.find(&manager.source) != Some(map_selection) {
+                        info!("Saltando a {}", galaxy.systems[map_selection].name);
+                        manager.warp_to(galaxy.systems[map_selection].source.clone(), &default_camera());
+                        effects.push(Sfx::Warp);
+                    }
+                    input_mode = InputMode::Orbit;
+                    follow.set_target(None, &camera);
+                }
+            }
+            _ => {
+                shader_selection = handle_input(&window, &mut camera, shader_selection);
+                let mouse_commands = mouse.poll(&window, &camera);
+                apply_camera_commands(&mut camera, &mouse_commands);
+
+                // Cámara que sigue a un planeta
+                let follow_target = poll_follow_target(&window, follow.target, manager.scene.planets.len());
+                if follow_target.is_some() && follow_target != follow.target {
+                    effects.push(Sfx::Select);
+                }
+                follow.set_target(follow_target, &camera);
+                if let Some(index) = follow.target {
+                    follow.update(&mut camera, manager.scene.planets[index].translation, clock.frame_delta);
+                }
+            }
+        }
+
+        // La cámara tampoco atraviesa los planetas (salvo en pleno salto)
+        if !warping && push_out_of_planets(&mut camera.eye, CAMERA_RADIUS, &manager.scene.planets).is_some() {
+            camera.has_changed = true;
+        }
+
+        // Selección de planetas con el mouse
+        if let Some((mouse_x, mouse_y)) = mouse.take_click() {
+            let ray = screen_ray(
+                mouse_x,
+                mouse_y,
+                window_width as f32,
+                window_height as f32,
+                &create_view_matrix(camera.eye, camera.center, camera.up),
+                &create_perspective_matrix(window_width as f32, window_height as f32),
             );
+            render_options.selected_planet = pick_planet(&ray, &manager.scene.planets);
+            if render_options.selected_planet.is_some() {
+                effects.push(Sfx::Select);
+            }
         }
 
+        if let Some(audio) = &mut audio {
+            for sfx in effects {
+                audio.play_effect(sfx);
+            }
+        }
+
+        if input_mode == InputMode::Map {
+            renderer.render_map(&galaxy, &map_camera, map_selection, galaxy.find(&manager.source));
+        } else {
+            renderer.render(&manager.scene, &camera, clock.sim_time, &render_options);
+        }
+        end_profile_frame(profiler, &args);
+
+        // HUD
+        hud.update(clock.frame_delta);
+        let camera_mode = match (input_mode, follow.target) {
+            _ if manager.is_warping() => "SALTO".to_string(),
+            (InputMode::Map, _) => "MAPA".to_string(),
+            (InputMode::Pilot, _) => "PILOTO".to_string(),
+            (InputMode::Orbit, Some(index)) => format!("SIGUIENDO {}", manager.scene.planets[index].name),
+            (InputMode::Orbit, None) => "LIBRE".to_string(),
+        };
+        let selected = match input_mode {
+            InputMode::Map => Some(galaxy.systems[map_selection].name.as_str()),
+            _ => render_options.selected_planet.map(|index| manager.scene.planets[index].name.as_str()),
+        };
+        hud.draw(&mut renderer.output, &HudInfo {
+            time_scale: clock.time_scale,
+            paused: clock.paused,
+            camera_mode: &camera_mode,
+            selected,
+            debug_view: (render_options.debug_view != DebugView::Off).then(|| render_options.debug_view.label()),
+            profile: show_profile.then(|| profiler.report()),
+        });
+
+        recorder.capture(renderer.pixels(), renderer.width(), renderer.height());
+
         window
-            .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
-            .unwrap();
+            .update_with_buffer(renderer.pixels(), renderer.width(), renderer.height())
+            .map_err(Error::Window)?;
 
         std::thread::sleep(frame_delay);
     }
-
-    *stop_signal.lock().unwrap() = true;
-    music_thread.join().unwrap();
+    Ok(())
 }
 
-fn handle_input(window: &Window, camera: &mut Camera, mut shader_selection: u32) -> u32 {
+fn handle_input(window: &Window, camera: &mut Camera, mut shader_selection: ShaderKind) -> ShaderKind {
     let movement_speed = 1.0;
     let rotation_speed = PI/50.0;
     let zoom_speed = 0.1;
@@ -394,20 +884,20 @@ fn handle_input(window: &Window, camera: &mut Camera, mut shader_selection: u32)
 
     // Shader selection controls
     if window.is_key_down(Key::NumPad0) {
-        shader_selection = 0;
+        shader_selection = ShaderKind::Lava;
     }
 
     if window.is_key_down(Key::NumPad1) {
-        shader_selection = 1;
+        shader_selection = ShaderKind::Ice;
     }
 
     if window.is_key_down(Key::NumPad2) {
-        shader_selection = 2;
+        shader_selection = ShaderKind::Cloud;
     }
 
     if window.is_key_down(Key::NumPad3) {
-        shader_selection = 3;
+        shader_selection = ShaderKind::Jupiter;
     }
 
-    return shader_selection;
+    shader_selection
 }
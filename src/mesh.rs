@@ -0,0 +1,535 @@
+use nalgebra_glm::{Vec2, Vec3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use crate::noise::{fbm, ridged, Fractal, NoiseConfig};
+use crate::vertex::Vertex;
+
+// Malla indexada: cada vértice se guarda una vez y los triángulos son tríos de índices
+#[derive(Clone)]
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>) -> Self {
+        Mesh { vertices, indices }
+    }
+
+    // Distancia del origen al vértice más lejano
+    pub fn radius(&self) -> f32 {
+        self.vertices
+            .iter()
+            .map(|vertex| vertex.position.magnitude())
+            .fold(0.0, f32::max)
+    }
+}
+
+// Genera una esfera unitaria (radio 1) de stacks x slices divisiones.
+// La costura (u = 0 y u = 1) repite sus vértices para que la textura no se enrolle.
+pub fn generate_uv_sphere(stacks: u32, slices: u32) -> Mesh {
+    let stacks = stacks.max(2);
+    let slices = slices.max(3);
+
+    let mut vertices = Vec::with_capacity(((stacks + 1) * (slices + 1)) as usize);
+    for stack in 0..=stacks {
+        for slice in 0..=slices {
+            let v = stack as f32 / stacks as f32;
+            let u = slice as f32 / slices as f32;
+
+            let theta = v * PI;         // Latitud, 0 en el polo norte
+            let phi = u * 2.0 * PI;     // Longitud
+
+            let position = Vec3::new(
+                theta.sin() * phi.cos(),
+                theta.cos(),
+                -theta.sin() * phi.sin(),
+            );
+
+            vertices.push(Vertex::new(position, position, Vec2::new(u, v)));
+        }
+    }
+
+    let index = |stack: u32, slice: u32| stack * (slices + 1) + slice;
+    let mut indices = Vec::with_capacity((stacks * slices * 6) as usize);
+
+    for stack in 0..stacks {
+        for slice in 0..slices {
+            let top_left = index(stack, slice);
+            let top_right = index(stack, slice + 1);
+            let bottom_left = index(stack + 1, slice);
+            let bottom_right = index(stack + 1, slice + 1);
+
+            // En los polos uno de los dos triángulos es degenerado
+            if stack != 0 {
+                indices.extend_from_slice(&[top_left, bottom_left, top_right]);
+            }
+            if stack != stacks - 1 {
+                indices.extend_from_slice(&[top_right, bottom_left, bottom_right]);
+            }
+        }
+    }
+
+    Mesh::new(vertices, indices)
+}
+
+// Genera una icoesfera unitaria: un icosaedro cuyos triángulos se dividen en
+// cuatro subdivisions veces, proyectando los nuevos vértices sobre la esfera.
+// Los triángulos quedan casi del mismo tamaño, mejor que la esfera UV para desplazar.
+pub fn generate_icosphere(subdivisions: u32) -> Mesh {
+    let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+    let mut positions: Vec<Vec3> = [
+        (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+        (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+        (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+    ]
+    .iter()
+    .map(|&(x, y, z)| Vec3::new(x, y, z).normalize())
+    .collect();
+
+    let mut faces: Vec<[u32; 3]> = vec![
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        // Cada arista se parte una sola vez aunque la compartan dos triángulos
+        let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut midpoint = |a: u32, b: u32, positions: &mut Vec<Vec3>| -> u32 {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                positions.push(((positions[a as usize] + positions[b as usize]) * 0.5).normalize());
+                (positions.len() - 1) as u32
+            })
+        };
+
+        faces = faces
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let ab = midpoint(a, b, &mut positions);
+                let bc = midpoint(b, c, &mut positions);
+                let ca = midpoint(c, a, &mut positions);
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    // Coordenadas de textura equirrectangulares, como en la esfera UV: u recorre la
+    // longitud desde +x y v va del polo norte al sur
+    let mut vertices: Vec<Vertex> = positions
+        .iter()
+        .map(|&position| {
+            let u = ((-position.z).atan2(position.x) / (2.0 * PI)).rem_euclid(1.0);
+            let v = position.y.clamp(-1.0, 1.0).acos() / PI;
+            Vertex::new(position, position, Vec2::new(u, v))
+        })
+        .collect();
+
+    // Un triángulo que cruza la costura tiene esquinas cerca de u = 1 y otras cerca de
+    // u = 0: esas últimas pasan a una copia con u + 1, así no recorre toda la textura
+    let mut seam_copies: HashMap<u32, u32> = HashMap::new();
+    for face in faces.iter_mut() {
+        let us = face.map(|index| vertices[index as usize].tex_coords.x);
+        let max = us.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let min = us.iter().copied().fold(f32::INFINITY, f32::min);
+        if max - min <= 0.5 {
+            continue;
+        }
+        for index in face.iter_mut() {
+            if vertices[*index as usize].tex_coords.x < 0.5 {
+                *index = *seam_copies.entry(*index).or_insert_with(|| {
+                    let mut copy = vertices[*index as usize].clone();
+                    copy.tex_coords.x += 1.0;
+                    vertices.push(copy);
+                    (vertices.len() - 1) as u32
+                });
+            }
+        }
+    }
+
+    Mesh::new(vertices, faces.concat())
+}
+
+// Roca irregular: una icoesfera deformada con ruido de la semilla. Un fBm de baja
+// frecuencia da la forma general (abollada, algo alargada) y encima un ruido de
+// crestas talla filos y surcos. roughness es el desplazamiento máximo relativo al
+// radio; las normales se recalculan después, así la iluminación sigue el relieve.
+pub fn generate_asteroid(seed: u64, subdivisions: u32, roughness: f32) -> Mesh {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let shape_noise = NoiseConfig { seed: rng.gen(), frequency: 1.0, ..NoiseConfig::default() }.build();
+    let ridge_noise = NoiseConfig { seed: rng.gen(), frequency: 1.0, ..NoiseConfig::default() }.build();
+    let ridge_offset = Vec3::new(rng.gen_range(0.0..100.0), rng.gen_range(0.0..100.0), rng.gen_range(0.0..100.0));
+
+    let mut asteroid = generate_icosphere(subdivisions);
+    displace(&mut asteroid.vertices, roughness, |position| {
+        let shape = fbm(&shape_noise, position * 1.2, Fractal::new(2));
+        // ridged va de 0 a 1: se centra para que los surcos también hundan la roca
+        let ridges = ridged(&ridge_noise, position * 2.5 + ridge_offset, Fractal::new(4)) * 2.0 - 1.0;
+        shape * 0.7 + ridges * 0.3
+    });
+    smooth_normals(&mut asteroid.vertices, &mut asteroid.indices, NormalWeighting::Area, None);
+    asteroid
+}
+
+// Genera un anillo plano (corona circular) en el plano XZ con la normal hacia +y.
+// Las coordenadas de textura van en dirección radial: u = 0 en el borde interior
+// y u = 1 en el exterior; v recorre el ángulo.
+pub fn generate_annulus(inner_radius: f32, outer_radius: f32, segments: u32) -> Mesh {
+    let segments = segments.max(3);
+    let normal = Vec3::new(0.0, 1.0, 0.0);
+
+    // Un par de vértices (interior y exterior) por segmento, más el que cierra la vuelta
+    let mut vertices = Vec::with_capacity(((segments + 1) * 2) as usize);
+    for segment in 0..=segments {
+        let v = segment as f32 / segments as f32;
+        let angle = v * 2.0 * PI;
+        let direction = Vec3::new(angle.cos(), 0.0, -angle.sin());
+
+        vertices.push(Vertex::new(direction * inner_radius, normal, Vec2::new(0.0, v)));
+        vertices.push(Vertex::new(direction * outer_radius, normal, Vec2::new(1.0, v)));
+    }
+
+    let mut indices = Vec::with_capacity((segments * 6) as usize);
+    for segment in 0..segments {
+        let inner = segment * 2;
+        let outer = inner + 1;
+        let inner_next = inner + 2;
+        let outer_next = inner + 3;
+
+        indices.extend_from_slice(&[inner, outer, outer_next]);
+        indices.extend_from_slice(&[inner, outer_next, inner_next]);
+    }
+
+    Mesh::new(vertices, indices)
+}
+
+// Genera un disco lleno en el plano XZ con la normal hacia +y, con las mismas
+// coordenadas de textura que la corona: u = 0 en el centro y u = 1 en el borde.
+// El centro se repite en cada segmento para que v siga al ángulo.
+pub fn generate_disk(radius: f32, segments: u32) -> Mesh {
+    let segments = segments.max(3);
+    let normal = Vec3::new(0.0, 1.0, 0.0);
+
+    // Primero el borde (con el vértice que cierra la vuelta) y después los centros
+    let mut vertices = Vec::with_capacity((segments * 2 + 1) as usize);
+    for segment in 0..=segments {
+        let v = segment as f32 / segments as f32;
+        let angle = v * 2.0 * PI;
+        let direction = Vec3::new(angle.cos(), 0.0, -angle.sin());
+        vertices.push(Vertex::new(direction * radius, normal, Vec2::new(1.0, v)));
+    }
+    for segment in 0..segments {
+        let v = (segment as f32 + 0.5) / segments as f32;
+        vertices.push(Vertex::new(Vec3::zeros(), normal, Vec2::new(0.0, v)));
+    }
+
+    let mut indices = Vec::with_capacity((segments * 3) as usize);
+    for segment in 0..segments {
+        let center = segments + 1 + segment;
+        indices.extend_from_slice(&[center, segment, segment + 1]);
+    }
+
+    Mesh::new(vertices, indices)
+}
+
+// Genera un toro en el plano XZ: un tubo de radio minor_radius alrededor de un
+// círculo de radio major_radius. v recorre el círculo, como en la corona, y u da la
+// vuelta al tubo empezando por el borde interior, así que por arriba u crece hacia
+// afuera (0.25 es la cara superior, 0.5 el borde exterior y 0.75 la inferior).
+pub fn generate_torus(major_radius: f32, minor_radius: f32, major_segments: u32, minor_segments: u32) -> Mesh {
+    let major_segments = major_segments.max(3);
+    let minor_segments = minor_segments.max(3);
+
+    let mut vertices = Vec::with_capacity(((major_segments + 1) * (minor_segments + 1)) as usize);
+    for major in 0..=major_segments {
+        let v = major as f32 / major_segments as f32;
+        let angle = v * 2.0 * PI;
+        let direction = Vec3::new(angle.cos(), 0.0, -angle.sin());
+
+        for minor in 0..=minor_segments {
+            let u = minor as f32 / minor_segments as f32;
+            let tube_angle = u * 2.0 * PI;
+            let normal = -direction * tube_angle.cos() + Vec3::new(0.0, tube_angle.sin(), 0.0);
+            let position = direction * major_radius + normal * minor_radius;
+
+            vertices.push(Vertex::new(position, normal, Vec2::new(u, v)));
+        }
+    }
+
+    let index = |major: u32, minor: u32| major * (minor_segments + 1) + minor;
+    let mut indices = Vec::with_capacity((major_segments * minor_segments * 6) as usize);
+    for major in 0..major_segments {
+        for minor in 0..minor_segments {
+            let current = index(major, minor);
+            let outward = index(major, minor + 1);
+            let next = index(major + 1, minor);
+            let next_outward = index(major + 1, minor + 1);
+
+            indices.extend_from_slice(&[current, outward, next_outward]);
+            indices.extend_from_slice(&[current, next_outward, next]);
+        }
+    }
+
+    Mesh::new(vertices, indices)
+}
+
+// Desplaza cada vértice a lo largo de su normal. height recibe la posición sobre
+// la esfera unitaria y devuelve la altura relativa, que se escala por amplitude.
+pub fn displace(vertices: &mut [Vertex], amplitude: f32, height: impl Fn(Vec3) -> f32) {
+    for vertex in vertices.iter_mut() {
+        let offset = height(vertex.position.normalize()) * amplitude;
+        vertex.position += vertex.normal * offset;
+    }
+}
+
+// Posición redondeada para reconocer los vértices repetidos en las costuras
+fn position_key(position: &Vec3) -> [i32; 3] {
+    [
+        (position.x * 10000.0).round() as i32,
+        (position.y * 10000.0).round() as i32,
+        (position.z * 10000.0).round() as i32,
+    ]
+}
+
+// Cómo pesa cada triángulo en la normal suave de los vértices que toca
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalWeighting {
+    // Por su área: sirve para mallas de triángulos parejos, como las esferas
+    Area,
+    // Por el ángulo que forma en el vértice, así el resultado no depende de cómo se
+    // triangularon las caras (un quad partido en dos pesa lo mismo que entero)
+    Angle,
+}
+
+// Ángulo de pliegue para los modelos que no traen normales: las caras que se doblan
+// más que esto (las aristas de una caja, p. ej.) quedan con un borde marcado
+pub const MODEL_CREASE_ANGLE: f32 = PI / 3.0;
+
+// Normales suaves: cada vértice recibe el promedio ponderado de las normales de los
+// triángulos que comparten su posición (también a través de costuras con vértices
+// repetidos). Con crease_angle solo se promedian los triángulos cuya normal se aparta
+// a lo sumo ese ángulo (en radianes) de la del triángulo de cada esquina; un vértice
+// que queda con normales distintas en sus triángulos se duplica y los índices pasan a
+// la copia. Si la suma se anula el vértice conserva la normal que tenía.
+pub fn smooth_normals(vertices: &mut Vec<Vertex>, indices: &mut [u32], weighting: NormalWeighting, crease_angle: Option<f32>) {
+    let min_cos = crease_angle.map(f32::cos);
+
+    // Normal unitaria de cada triángulo y lo que aporta a cada una de sus esquinas
+    let faces: Vec<(Vec3, [Vec3; 3])> = indices
+        .chunks_exact(3)
+        .map(|triangle| {
+            let [a, b, c] = [0, 1, 2].map(|corner| vertices[triangle[corner] as usize].position);
+            let face_normal = (b - a).cross(&(c - a));
+            let unit = face_normal.try_normalize(f32::EPSILON).unwrap_or(Vec3::zeros());
+            let weights = match weighting {
+                NormalWeighting::Area => [face_normal; 3],
+                // Un triángulo degenerado no tiene ángulos ni aporta nada
+                NormalWeighting::Angle if unit == Vec3::zeros() => [Vec3::zeros(); 3],
+                NormalWeighting::Angle => [(a, c, b), (b, a, c), (c, b, a)]
+                    .map(|(corner, previous, next)| unit * (previous - corner).angle(&(next - corner))),
+            };
+            (unit, weights)
+        })
+        .collect();
+
+    // Esquinas (triángulo, vértice del triángulo) que caen en cada posición
+    let mut corners: HashMap<[i32; 3], Vec<(usize, usize)>> = HashMap::new();
+    for (face, triangle) in indices.chunks_exact(3).enumerate() {
+        for (corner, &index) in triangle.iter().enumerate() {
+            corners.entry(position_key(&vertices[index as usize].position)).or_default().push((face, corner));
+        }
+    }
+
+    // La primera normal de cada vértice se queda en él; otra distinta va a una copia
+    let mut assigned: Vec<Option<Vec3>> = vec![None; vertices.len()];
+    let mut copies: HashMap<(u32, [u32; 3]), u32> = HashMap::new();
+    for (face, triangle) in indices.chunks_exact_mut(3).enumerate() {
+        let own = faces[face].0;
+        for index in triangle.iter_mut() {
+            let vertex = *index as usize;
+            let sum: Vec3 = corners[&position_key(&vertices[vertex].position)]
+                .iter()
+                .filter(|&&(other, _)| match min_cos {
+                    // Un triángulo degenerado no tiene dirección y se suaviza con todos
+                    Some(min_cos) if own != Vec3::zeros() => own.dot(&faces[other].0) >= min_cos,
+                    _ => true,
+                })
+                .map(|&(other, corner)| faces[other].1[corner])
+                .sum();
+            let normal = if sum.magnitude() > f32::EPSILON { sum.normalize() } else { vertices[vertex].normal };
+
+            match assigned[vertex] {
+                None => {
+                    assigned[vertex] = Some(normal);
+                    vertices[vertex].normal = normal;
+                }
+                Some(current) if current == normal => {}
+                Some(_) => {
+                    let key = (*index, normal.map(f32::to_bits).into());
+                    *index = *copies.entry(key).or_insert_with(|| {
+                        let mut copy = vertices[vertex].clone();
+                        copy.normal = normal;
+                        vertices.push(copy);
+                        (vertices.len() - 1) as u32
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn assert_unit_normals(mesh: &Mesh) {
+        for vertex in &mesh.vertices {
+            assert!((vertex.normal.magnitude() - 1.0).abs() < 1e-5, "normal {:?} no es unitaria", vertex.normal);
+        }
+    }
+
+    // Los triángulos van en sentido antihorario vistos desde el lado de sus normales
+    fn assert_front_faces(mesh: &Mesh) {
+        for triangle in mesh.indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|corner| &mesh.vertices[triangle[corner] as usize]);
+            let face = (b.position - a.position).cross(&(c.position - a.position));
+            let normal = a.normal + b.normal + c.normal;
+            assert!(face.dot(&normal) > 0.0, "el triángulo {:?} está al revés", triangle);
+        }
+    }
+
+    #[test]
+    fn disk_counts_normals_and_winding() {
+        let segments = 16;
+        let disk = generate_disk(2.0, segments);
+        // El borde con el vértice que cierra la vuelta y un centro por segmento
+        assert_eq!(disk.vertices.len(), (segments * 2 + 1) as usize);
+        assert_eq!(disk.indices.len(), (segments * 3) as usize);
+        assert_unit_normals(&disk);
+        assert_front_faces(&disk);
+        assert!((disk.radius() - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn torus_counts_normals_and_winding() {
+        let (major_segments, minor_segments) = (24, 8);
+        let torus = generate_torus(2.0, 0.5, major_segments, minor_segments);
+        assert_eq!(torus.vertices.len(), ((major_segments + 1) * (minor_segments + 1)) as usize);
+        assert_eq!(torus.indices.len(), (major_segments * minor_segments * 6) as usize);
+        assert_unit_normals(&torus);
+        assert_front_faces(&torus);
+        assert!((torus.radius() - 2.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn icosphere_counts_unit_vertices_and_shared_edges() {
+        for subdivisions in 0..4 {
+            let sphere = generate_icosphere(subdivisions);
+            // Cada subdivisión cuadruplica las caras; por Euler quedan 10·4ⁿ + 2 posiciones
+            let faces = 20 * 4usize.pow(subdivisions);
+            assert_eq!(sphere.indices.len(), faces * 3);
+
+            for vertex in &sphere.vertices {
+                assert!((vertex.position.magnitude() - 1.0).abs() < 1e-5, "el vértice {:?} no está sobre la esfera", vertex.position);
+            }
+            assert_unit_normals(&sphere);
+            assert_front_faces(&sphere);
+
+            // Los puntos medios se comparten: solo se repiten las posiciones de la costura
+            // (con u + 1) y cada arista la usan exactamente dos triángulos
+            let positions: HashSet<[i32; 3]> = sphere.vertices.iter().map(|vertex| position_key(&vertex.position)).collect();
+            assert_eq!(positions.len(), faces / 2 + 2);
+            let seam = sphere.vertices.iter().filter(|vertex| vertex.tex_coords.x >= 1.0).count();
+            assert_eq!(sphere.vertices.len(), positions.len() + seam);
+            let mut edges: HashMap<([i32; 3], [i32; 3]), u32> = HashMap::new();
+            for triangle in sphere.indices.chunks(3) {
+                let key = |corner: usize| position_key(&sphere.vertices[triangle[corner] as usize].position);
+                for (a, b) in [(key(0), key(1)), (key(1), key(2)), (key(2), key(0))] {
+                    *edges.entry((a.min(b), a.max(b))).or_default() += 1;
+                }
+            }
+            assert!(edges.values().all(|&count| count == 2));
+        }
+    }
+
+    #[test]
+    fn icosphere_uvs_match_the_uv_sphere() {
+        let sphere = generate_icosphere(3);
+        // La misma longitud desde +x y latitud desde el polo norte que la esfera UV
+        for vertex in &sphere.vertices {
+            let [u, v] = [vertex.tex_coords.x, vertex.tex_coords.y];
+            let (theta, phi) = (v * PI, u * 2.0 * PI);
+            let expected = Vec3::new(theta.sin() * phi.cos(), theta.cos(), -theta.sin() * phi.sin());
+            assert!((expected - vertex.position).magnitude() < 1e-4, "u = {}, v = {} no es {:?}", u, v, vertex.position);
+        }
+        // Ningún triángulo recorre la textura de punta a punta
+        for triangle in sphere.indices.chunks(3) {
+            let us = [0, 1, 2].map(|corner| sphere.vertices[triangle[corner] as usize].tex_coords.x);
+            let span = us.iter().copied().fold(f32::NEG_INFINITY, f32::max) - us.iter().copied().fold(f32::INFINITY, f32::min);
+            assert!(span <= 0.5, "el triángulo {:?} cruza la costura con u = {:?}", triangle, us);
+        }
+    }
+
+    // Caja de lado 2 con los 8 vértices compartidos y las caras hacia afuera
+    fn shared_cube() -> Mesh {
+        let vertices = (0..8)
+            .map(|corner| {
+                let axis = |bit: u32| if corner & bit != 0 { 1.0 } else { -1.0 };
+                Vertex::new(Vec3::new(axis(1), axis(2), axis(4)), Vec3::zeros(), Vec2::zeros())
+            })
+            .collect();
+        let faces: [[u32; 4]; 6] = [[0, 4, 6, 2], [1, 3, 7, 5], [0, 1, 5, 4], [2, 6, 7, 3], [0, 2, 3, 1], [4, 5, 7, 6]];
+        let indices = faces.iter().flat_map(|&[a, b, c, d]| [a, b, c, a, c, d]).collect();
+        Mesh::new(vertices, indices)
+    }
+
+    #[test]
+    fn smooth_normals_without_crease_share_corners() {
+        for weighting in [NormalWeighting::Area, NormalWeighting::Angle] {
+            let mut cube = shared_cube();
+            smooth_normals(&mut cube.vertices, &mut cube.indices, weighting, None);
+            assert_eq!(cube.vertices.len(), 8);
+            assert_unit_normals(&cube);
+            assert_front_faces(&cube);
+            // Por ángulo cada esquina pesa sus tres caras igual aunque una esté partida
+            // en dos triángulos en ella, así que apunta en diagonal
+            if weighting == NormalWeighting::Angle {
+                for vertex in &cube.vertices {
+                    assert!((vertex.normal - vertex.position.normalize()).magnitude() < 1e-5, "{:?}", vertex.normal);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn smooth_normals_split_creases() {
+        let mut cube = shared_cube();
+        smooth_normals(&mut cube.vertices, &mut cube.indices, NormalWeighting::Angle, Some(MODEL_CREASE_ANGLE));
+        // Cada esquina queda con una copia por cara, con la normal de esa cara
+        assert_eq!(cube.vertices.len(), 24);
+        for triangle in cube.indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|corner| &cube.vertices[triangle[corner] as usize]);
+            let face = (b.position - a.position).cross(&(c.position - a.position)).normalize();
+            for vertex in [a, b, c] {
+                assert!((vertex.normal - face).magnitude() < 1e-5, "{:?} en una cara {:?}", vertex.normal, face);
+            }
+        }
+        assert_unit_normals(&cube);
+    }
+
+    #[test]
+    fn smooth_normals_keep_gentle_curves_smooth() {
+        // En la icoesfera las caras vecinas se doblan mucho menos que el pliegue
+        let mut sphere = generate_icosphere(2);
+        let count = sphere.vertices.len();
+        smooth_normals(&mut sphere.vertices, &mut sphere.indices, NormalWeighting::Angle, Some(MODEL_CREASE_ANGLE));
+        assert_eq!(sphere.vertices.len(), count);
+        for vertex in &sphere.vertices {
+            assert!(vertex.normal.dot(&vertex.position) > 0.99);
+        }
+    }
+}
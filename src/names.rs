@@ -0,0 +1,41 @@
+use rand::Rng;
+
+// Sílabas con las que se arman los nombres: un inicio, cero o más sílabas
+// intermedias y un final. Solo ASCII, porque la fuente del HUD no tiene tildes.
+const STARTS: [&str; 20] = [
+    "ka", "ze", "lo", "mi", "tha", "ve", "or", "an", "dra", "sel",
+    "ny", "qu", "ri", "bel", "ga", "xo", "tur", "el", "pho", "cy",
+];
+const MIDDLES: [&str; 16] = [
+    "ra", "li", "no", "ta", "ri", "me", "lo", "ven", "sa", "ru",
+    "di", "go", "ne", "phi", "ca", "to",
+];
+const ENDS: [&str; 16] = [
+    "on", "ia", "us", "ar", "is", "e", "a", "os", "ea", "ix",
+    "um", "an", "or", "eth", "ys", "ara",
+];
+
+// Nombre pronunciable de dos a cuatro sílabas, con la primera letra en mayúscula
+pub fn generate_name(rng: &mut impl Rng) -> String {
+    let mut name = String::from(STARTS[rng.gen_range(0..STARTS.len())]);
+    for _ in 0..rng.gen_range(0..=2) {
+        name.push_str(MIDDLES[rng.gen_range(0..MIDDLES.len())]);
+    }
+    name.push_str(ENDS[rng.gen_range(0..ENDS.len())]);
+
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => name,
+    }
+}
+
+// Genera un nombre que no esté en la lista, para que cada cuerpo sea distinguible
+pub fn unique_name(rng: &mut impl Rng, taken: &[String]) -> String {
+    loop {
+        let name = generate_name(rng);
+        if !taken.contains(&name) {
+            return name;
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use fastnoise_lite::{FastNoiseLite, FractalType, NoiseType};
+use nalgebra_glm::{Mat4, Vec3};
+use rayon::prelude::*;
+use serde::Deserialize;
+use std::f32::consts::PI;
+use crate::color::{self, Color, Gradient};
+use crate::framebuffer::HdrFramebuffer;
+use crate::texture::Texture;
+
+// Resolución del mapa equirrectangular donde se hornea la nebulosa
+const MAP_WIDTH: usize = 512;
+const MAP_HEIGHT: usize = 256;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct NebulaConfig {
+    pub seed: i32,
+    pub palette: Gradient,
+    pub intensity: f32,
+    pub warp: f32,
+}
+
+impl Default for NebulaConfig {
+    fn default() -> Self {
+        NebulaConfig {
+            seed: 7,
+            palette: Gradient::new(vec![
+                Color::new(10, 0, 30),
+                Color::new(90, 20, 120),
+                Color::new(200, 60, 120),
+                Color::new(255, 170, 110),
+            ]),
+            intensity: 0.6,
+            warp: 1.5,
+        }
+    }
+}
+
+pub struct Nebula {
+    map: Texture,
+}
+
+impl Nebula {
+    // Evalúa el fBm con deformación de dominio una sola vez sobre la esfera celeste
+    pub fn generate(config: &NebulaConfig) -> Self {
+        let mut noise = FastNoiseLite::with_seed(config.seed);
+        noise.set_noise_type(Some(NoiseType::OpenSimplex2));
+        noise.set_fractal_type(Some(FractalType::FBm));
+        noise.set_fractal_octaves(Some(5));
+        noise.set_frequency(Some(1.0));
+
+        let data = (0..MAP_WIDTH * MAP_HEIGHT)
+            .into_par_iter()
+            .map(|index| {
+                let u = (index % MAP_WIDTH) as f32 / MAP_WIDTH as f32;
+                let v = (index / MAP_WIDTH) as f32 / MAP_HEIGHT as f32;
+                let p = equirectangular_direction(u, v) * 1.5;
+
+                // Deformación de dominio: el ruido desplaza las coordenadas del propio ruido
+                let q = Vec3::new(
+                    noise.get_noise_3d(p.x, p.y, p.z),
+                    noise.get_noise_3d(p.x + 5.2, p.y + 1.3, p.z + 2.8),
+                    noise.get_noise_3d(p.x + 1.7, p.y + 9.2, p.z + 4.1),
+                );
+                let w = p + q * config.warp;
+                let value = noise.get_noise_3d(w.x, w.y, w.z) * 0.5 + 0.5;
+
+                // Las zonas bajas quedan como espacio vacío. El brillo se pensó sobre
+                // valores sRGB; con la mezcla en espacio lineal se lleva a su equivalente.
+                let density = ((value - 0.35) / 0.65).clamp(0.0, 1.0);
+                let mut brightness = density * density * config.intensity;
+                if color::linear_math() {
+                    brightness = brightness.powf(2.2);
+                }
+                config.palette.sample(q.magnitude().min(1.0)) * brightness
+            })
+            .collect();
+
+        Nebula {
+            map: Texture::from_colors(MAP_WIDTH, MAP_HEIGHT, data),
+        }
+    }
+
+    // Pasada de fondo: cada píxel toma el color de la nebulosa en su dirección de vista
+    pub fn render(&self, framebuffer: &mut HdrFramebuffer, view_matrix: &Mat4, projection_matrix: &Mat4) {
+        let width = framebuffer.width;
+        let height = framebuffer.height;
+
+        // Inversa de la rotación de la vista y escala de la proyección
+        let rotation = view_matrix.fixed_view::<3, 3>(0, 0).transpose();
+        let (scale_x, scale_y) = (projection_matrix[(0, 0)], projection_matrix[(1, 1)]);
+
+        framebuffer.buffer
+            .par_chunks_mut(width)
+            .enumerate()
+            .for_each(|(y, row)| {
+                let ndc_y = 1.0 - 2.0 * (y as f32 + 0.5) / height as f32;
+
+                for (x, pixel) in row.iter_mut().enumerate() {
+                    let ndc_x = 2.0 * (x as f32 + 0.5) / width as f32 - 1.0;
+                    let view_dir = Vec3::new(ndc_x / scale_x, ndc_y / scale_y, -1.0);
+                    let d = (rotation * view_dir).normalize();
+
+                    let u = d.z.atan2(d.x) / (2.0 * PI) + 0.5;
+                    let v = d.y.clamp(-1.0, 1.0).acos() / PI;
+                    *pixel = self.map.sample_bilinear(u, v).to_linear();
+                }
+            });
+    }
+}
+
+fn equirectangular_direction(u: f32, v: f32) -> Vec3 {
+    let longitude = (u - 0.5) * 2.0 * PI;
+    let latitude = v * PI;
+    Vec3::new(latitude.sin() * longitude.cos(), latitude.cos(), latitude.sin() * longitude.sin())
+}
@@ -0,0 +1,153 @@
+use fastnoise_lite::{FastNoiseLite, FractalType, NoiseType};
+use nalgebra_glm::Vec3;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoiseKind {
+    OpenSimplex2,
+    OpenSimplex2S,
+    Cellular,
+    Perlin,
+    ValueCubic,
+    Value,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct NoiseConfig {
+    pub seed: i32,
+    pub noise_type: NoiseKind,
+    pub frequency: f32,
+    pub octaves: i32,
+}
+
+impl Default for NoiseConfig {
+    fn default() -> Self {
+        NoiseConfig {
+            seed: 1337,
+            noise_type: NoiseKind::OpenSimplex2,
+            frequency: 0.01,
+            octaves: 1,
+        }
+    }
+}
+
+impl NoiseConfig {
+    pub fn build(&self) -> FastNoiseLite {
+        let mut noise = FastNoiseLite::with_seed(self.seed);
+        noise.set_noise_type(Some(self.noise_type.into()));
+        noise.set_frequency(Some(self.frequency));
+
+        // Con más de una octava se usa ruido fractal (fBm)
+        if self.octaves > 1 {
+            noise.set_fractal_type(Some(FractalType::FBm));
+            noise.set_fractal_octaves(Some(self.octaves));
+        }
+
+        noise
+    }
+}
+
+impl From<NoiseKind> for NoiseType {
+    fn from(kind: NoiseKind) -> Self {
+        match kind {
+            NoiseKind::OpenSimplex2 => NoiseType::OpenSimplex2,
+            NoiseKind::OpenSimplex2S => NoiseType::OpenSimplex2S,
+            NoiseKind::Cellular => NoiseType::Cellular,
+            NoiseKind::Perlin => NoiseType::Perlin,
+            NoiseKind::ValueCubic => NoiseType::ValueCubic,
+            NoiseKind::Value => NoiseType::Value,
+        }
+    }
+}
+
+// Parámetros de las sumas de octavas: cada octava multiplica la frecuencia por
+// lacunarity y la amplitud por gain
+#[derive(Debug, Clone, Copy)]
+pub struct Fractal {
+    pub octaves: u32,
+    pub lacunarity: f32,
+    pub gain: f32,
+}
+
+impl Fractal {
+    pub const fn new(octaves: u32) -> Self {
+        Fractal {
+            octaves,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+
+    pub const fn with_lacunarity(mut self, lacunarity: f32) -> Self {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    pub const fn with_gain(mut self, gain: f32) -> Self {
+        self.gain = gain;
+        self
+    }
+}
+
+impl Default for Fractal {
+    fn default() -> Self {
+        Fractal::new(4)
+    }
+}
+
+// Recorre las octavas y normaliza por la suma de amplitudes
+fn sum_octaves(fractal: Fractal, mut octave: impl FnMut(f32) -> f32) -> f32 {
+    let mut value = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut total = 0.0;
+
+    for _ in 0..fractal.octaves.max(1) {
+        value += octave(frequency) * amplitude;
+        total += amplitude;
+        amplitude *= fractal.gain;
+        frequency *= fractal.lacunarity;
+    }
+
+    value / total
+}
+
+// Movimiento browniano fraccional: ruido suave en [-1, 1]
+pub fn fbm(noise: &FastNoiseLite, point: Vec3, fractal: Fractal) -> f32 {
+    sum_octaves(fractal, |frequency| {
+        let p = point * frequency;
+        noise.get_noise_3d(p.x, p.y, p.z)
+    })
+}
+
+// Ruido de crestas en [0, 1]: los cruces por cero se vuelven filos, útil para
+// cordilleras y grietas
+pub fn ridged(noise: &FastNoiseLite, point: Vec3, fractal: Fractal) -> f32 {
+    sum_octaves(fractal, |frequency| {
+        let p = point * frequency;
+        let ridge = 1.0 - noise.get_noise_3d(p.x, p.y, p.z).abs();
+        ridge * ridge
+    })
+}
+
+// Turbulencia en [0, 1]: suma de valores absolutos, con pliegues marcados
+pub fn turbulence(noise: &FastNoiseLite, point: Vec3, fractal: Fractal) -> f32 {
+    sum_octaves(fractal, |frequency| {
+        let p = point * frequency;
+        noise.get_noise_3d(p.x, p.y, p.z).abs()
+    })
+}
+
+// Deformación de dominio: desplaza el punto con tres fBm desfasados antes de
+// muestrear el ruido final, lo que produce remolinos
+pub fn domain_warp(noise: &FastNoiseLite, point: Vec3, strength: f32, fractal: Fractal) -> Vec3 {
+    let offset = Vec3::new(
+        fbm(noise, point, fractal),
+        fbm(noise, point + Vec3::new(5.2, 1.3, 2.8), fractal),
+        fbm(noise, point + Vec3::new(1.7, 9.2, 4.1), fractal),
+    );
+
+    point + offset * strength
+}
@@ -1,62 +1,258 @@
 use tobj;
 use nalgebra_glm::{Vec2, Vec3};
+use thiserror::Error;
+use std::path::Path;
+use crate::color::Color;
+use crate::lighting::{Material, PbrMaterial};
+use crate::mesh::{self, Mesh, NormalWeighting};
+use crate::texture::Texture;
 use crate::vertex::Vertex;
 
+#[derive(Debug, Error)]
+pub enum ObjError {
+    #[error(transparent)]
+    Load(#[from] tobj::LoadError),
+    #[error("no se pudo cargar la textura del material '{0}': {1}")]
+    Texture(String, #[source] image::ImageError),
+}
+
 pub struct Obj {
-    meshes: Vec<Mesh>,
+    meshes: Vec<ObjMesh>,
+    materials: Vec<ObjMaterial>,
 }
 
-struct Mesh {
+struct ObjMesh {
     vertices: Vec<Vec3>,
     normals: Vec<Vec3>,
     texcoords: Vec<Vec2>,
     indices: Vec<u32>,
+    material: Option<usize>,
+}
+
+// Material del archivo .mtl que acompaña al modelo: color difuso (Kd), textura
+// difusa (map_Kd) y brillo especular (Ks y Ns)
+pub struct ObjMaterial {
+    pub name: String,
+    pub diffuse: Color,
+    pub texture: Option<Texture>,
+    pub specular: Material,
+    // Metálico-rugoso del material; None usa el brillo de specular
+    pub pbr: Option<PbrMaterial>,
+    // Si el color de cada vértice tiñe el difuso (los modelos glTF con COLOR_0)
+    pub vertex_colors: bool,
+}
+
+// Parte del modelo que usa un mismo material; None son las caras sin usemtl
+pub struct MaterialGroup {
+    pub mesh: Mesh,
+    pub material: Option<usize>,
+}
+
+// Modelo con sus materiales: cada grupo indexa en materials
+pub struct Model {
+    pub groups: Vec<MaterialGroup>,
+    pub materials: Vec<ObjMaterial>,
+}
+
+impl Model {
+    pub fn material(&self, group: &MaterialGroup) -> Option<&ObjMaterial> {
+        group.material.and_then(|index| self.materials.get(index))
+    }
 }
 
 impl Obj {
-    pub fn load(filename: &str) -> Result<Self, tobj::LoadError> {
-        let (models, _) = tobj::load_obj(filename, &tobj::LoadOptions {
+    // Un .mtl que falta o no se puede leer no es un error: el modelo queda sin
+    // materiales. Una textura que falta sí lo es, para que no pase desapercibida.
+    // tobj parte las caras de cuatro o más vértices en abanico desde el primero y
+    // resuelve los índices negativos (relativos al último vértice leído), así que los
+    // modelos con quads o n-gonos llegan al renderer como triángulos indexados.
+    pub fn load(filename: &str) -> Result<Self, ObjError> {
+        let (models, materials) = tobj::load_obj(filename, &tobj::LoadOptions {
             single_index: true,
             triangulate: true,
             ..Default::default()
-        })?;
+        }).map_err(ObjError::Load)?;
 
         let meshes = models.into_iter().map(|model| {
             let mesh = model.mesh;
-            Mesh {
-                vertices: mesh.positions.chunks(3)
-                    .map(|v| Vec3::new(v[0], v[1], v[2]))
-                    .collect(),
-                normals: mesh.normals.chunks(3)
+            let vertices: Vec<Vec3> = mesh.positions.chunks(3)
+                .map(|v| Vec3::new(v[0], v[1], v[2]))
+                .collect();
+            // Sin vn (o con menos normales que vértices) las normales se calculan al armar la malla
+            let normals = if mesh.normals.len() == mesh.positions.len() {
+                mesh.normals.chunks(3)
                     .map(|n| Vec3::new(n[0], n[1], n[2]))
-                    .collect(),
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            ObjMesh {
+                vertices,
+                normals,
                 texcoords: mesh.texcoords.chunks(2)
                     .map(|t| Vec2::new(t[0], 1.0 - t[1]))
                     .collect(),
                 indices: mesh.indices,
+                material: mesh.material_id,
             }
         }).collect();
 
-        Ok(Obj { meshes })
+        // Las rutas de las texturas son relativas a la carpeta del modelo
+        let directory = Path::new(filename).parent().unwrap_or(Path::new(""));
+        let materials = materials
+            .unwrap_or_default()
+            .into_iter()
+            .map(|material| ObjMaterial::from_mtl(material, directory))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Obj { meshes, materials })
     }
 
-    pub fn get_vertex_array(&self) -> Vec<Vertex> {
-        let mut vertices = Vec::new();
+    // Une todos los objetos del archivo en una sola malla indexada
+    pub fn get_mesh(&self) -> Mesh {
+        merge(self.meshes.iter())
+    }
 
+    // Una malla por material, en el orden en que aparecen en el archivo
+    pub fn material_groups(&self) -> Vec<MaterialGroup> {
+        let mut materials: Vec<Option<usize>> = Vec::new();
         for mesh in &self.meshes {
-            for &index in &mesh.indices {
-                let position = mesh.vertices[index as usize];
-                let normal = mesh.normals.get(index as usize)
-                    .cloned()
-                    .unwrap_or(Vec3::new(0.0, 1.0, 0.0));
-                let tex_coords = mesh.texcoords.get(index as usize)
-                    .cloned()
-                    .unwrap_or(Vec2::new(0.0, 0.0));
-
-                vertices.push(Vertex::new(position, normal, tex_coords));
+            if !materials.contains(&mesh.material) {
+                materials.push(mesh.material);
             }
         }
 
-        vertices
+        materials
+            .into_iter()
+            .map(|material| MaterialGroup {
+                mesh: merge(self.meshes.iter().filter(|mesh| mesh.material == material)),
+                material,
+            })
+            .collect()
+    }
+
+    // El modelo separado por materiales, listo para dibujar cada parte con el suyo
+    pub fn into_model(self) -> Model {
+        Model {
+            groups: self.material_groups(),
+            materials: self.materials,
+        }
+    }
+}
+
+fn merge<'a>(meshes: impl Iterator<Item = &'a ObjMesh>) -> Mesh {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    for mesh in meshes {
+        // Los índices de cada objeto empiezan después de los vértices de los anteriores
+        let offset = vertices.len() as u32;
+
+        let mut object: Vec<Vertex> = mesh.vertices.iter().enumerate().map(|(index, &position)| {
+            let normal = mesh.normals.get(index)
+                .cloned()
+                .unwrap_or(Vec3::new(0.0, 1.0, 0.0));
+            let tex_coords = mesh.texcoords.get(index)
+                .cloned()
+                .unwrap_or(Vec2::new(0.0, 0.0));
+
+            Vertex::new(position, normal, tex_coords)
+        }).collect();
+        let mut object_indices = mesh.indices.clone();
+        // Normales suaves por ángulo, con las aristas vivas marcadas
+        if mesh.normals.is_empty() {
+            mesh::smooth_normals(&mut object, &mut object_indices, NormalWeighting::Angle, Some(mesh::MODEL_CREASE_ANGLE));
+        }
+
+        vertices.extend(object);
+        indices.extend(object_indices.iter().map(|&index| index + offset));
+    }
+
+    Mesh::new(vertices, indices)
+}
+
+impl ObjMaterial {
+    fn from_mtl(material: tobj::Material, directory: &Path) -> Result<Self, ObjError> {
+        let to_color = |[r, g, b]: [f32; 3]| {
+            let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+            Color::new(channel(r), channel(g), channel(b))
+        };
+
+        let texture = match &material.diffuse_texture {
+            Some(file) => {
+                let path = directory.join(file).to_string_lossy().into_owned();
+                Some(Texture::load(&path).map_err(|err| ObjError::Texture(path, err))?)
+            }
+            None => None,
+        };
+
+        // Sin Ks el material es mate; Ns va de 0 a 1000 en el formato
+        let specular = material.specular.map(|[r, g, b]| (r + g + b) / 3.0).unwrap_or(0.0);
+        Ok(ObjMaterial {
+            name: material.name,
+            diffuse: to_color(material.diffuse.unwrap_or([1.0, 1.0, 1.0])),
+            texture,
+            specular: Material {
+                shininess: material.shininess.unwrap_or(32.0).max(1.0),
+                specular,
+            },
+            pbr: None,
+            vertex_colors: false,
+        })
+    }
+
+    // Color difuso en unas coordenadas de textura: la textura teñida por Kd, o solo Kd
+    pub fn albedo(&self, tex_coords: Vec2) -> Color {
+        match &self.texture {
+            Some(texture) => texture.sample_bilinear(tex_coords.x, tex_coords.y).blend_multiply(&self.diffuse),
+            None => self.diffuse,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Un quad, y un pentágono con índices negativos que cuentan desde el último vértice
+    const POLYGONS: &str = "\
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+f 1 2 3 4
+v 2 0 0
+v 3 0 0
+v 3.5 1 0
+v 2.5 2 0
+v 1.5 1 0
+f -5 -4 -3 -2 -1
+";
+
+    fn load_inline(name: &str, contents: &str) -> Obj {
+        let path = std::env::temp_dir().join(format!("{}_{}.obj", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        let obj = Obj::load(&path.to_string_lossy());
+        let _ = std::fs::remove_file(&path);
+        obj.unwrap()
+    }
+
+    #[test]
+    fn polygons_are_fan_triangulated() {
+        let mesh = load_inline("polygons", POLYGONS).get_mesh();
+        let triangles: Vec<[Vec3; 3]> = mesh.indices
+            .chunks(3)
+            .map(|triangle| std::array::from_fn(|corner| mesh.vertices[triangle[corner] as usize].position))
+            .collect();
+
+        let v = |x: f32, y: f32| Vec3::new(x, y, 0.0);
+        // Dos triángulos del quad y tres del pentágono, en abanico desde su primer vértice
+        assert_eq!(triangles, vec![
+            [v(0.0, 0.0), v(1.0, 0.0), v(1.0, 1.0)],
+            [v(0.0, 0.0), v(1.0, 1.0), v(0.0, 1.0)],
+            [v(2.0, 0.0), v(3.0, 0.0), v(3.5, 1.0)],
+            [v(2.0, 0.0), v(3.5, 1.0), v(2.5, 2.0)],
+            [v(2.0, 0.0), v(2.5, 2.0), v(1.5, 1.0)],
+        ]);
     }
 }
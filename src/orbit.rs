@@ -0,0 +1,55 @@
+use nalgebra_glm::Vec3;
+use serde::Deserialize;
+use std::f32::consts::PI;
+
+// Órbita kepleriana alrededor del origen. La inclinación y la fase están en
+// grados; el periodo en las mismas unidades que el tiempo de la simulación.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Orbit {
+    pub semi_major_axis: f32,
+    #[serde(default)]
+    pub eccentricity: f32,
+    #[serde(default)]
+    pub inclination: f32,
+    pub period: f32,
+    #[serde(default)]
+    pub phase: f32,
+}
+
+impl Orbit {
+    pub fn position(&self, time: f32) -> Vec3 {
+        let e = self.eccentricity.clamp(0.0, 0.99);
+        let mean_anomaly = 2.0 * PI * time / self.period + self.phase.to_radians();
+        let eccentric_anomaly = solve_kepler(mean_anomaly, e);
+
+        // Posición en el plano de la órbita, con el Sol en uno de los focos
+        let a = self.semi_major_axis;
+        let x = a * (eccentric_anomaly.cos() - e);
+        let z = a * (1.0 - e * e).sqrt() * eccentric_anomaly.sin();
+
+        // Inclinación del plano de la órbita alrededor del eje x
+        let (sin_i, cos_i) = self.inclination.to_radians().sin_cos();
+        Vec3::new(x, z * sin_i, z * cos_i)
+    }
+
+    // Puntos de la elipse completa, para dibujar la trayectoria
+    pub fn path(&self, segments: usize) -> Vec<Vec3> {
+        (0..=segments)
+            .map(|i| self.position(self.period * i as f32 / segments as f32))
+            .collect()
+    }
+}
+
+// Resuelve M = E - e * sin(E) con el método de Newton
+fn solve_kepler(mean_anomaly: f32, eccentricity: f32) -> f32 {
+    let m = mean_anomaly % (2.0 * PI);
+    let mut e_anomaly = if eccentricity > 0.8 { PI } else { m };
+
+    for _ in 0..8 {
+        let f = e_anomaly - eccentricity * e_anomaly.sin() - m;
+        let f_prime = 1.0 - eccentricity * e_anomaly.cos();
+        e_anomaly -= f / f_prime;
+    }
+
+    e_anomaly
+}
@@ -0,0 +1,243 @@
+use nalgebra_glm::{Mat4, Vec3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+use crate::billboard::{draw_billboard, draw_point, BlendMode, Sprite};
+use crate::color::Color;
+use crate::framebuffer::HdrFramebuffer;
+
+pub struct Particle {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub age: f32,
+    pub lifetime: f32,
+}
+
+impl Particle {
+    // 1 al nacer y 0 al morir
+    pub fn life(&self) -> f32 {
+        (1.0 - self.age / self.lifetime).clamp(0.0, 1.0)
+    }
+}
+
+// Cómo se dibuja cada partícula: un píxel o un billboard de medio lado size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticleShape {
+    Point,
+    Billboard,
+}
+
+// Comportamiento de un emisor. Cada partícula vive entre la mitad y el total de
+// lifetime, sale a entre 0.6 y 1.4 veces speed, y se abre de la dirección de
+// emisión según spread (0 es un chorro recto, 1 abre unos 45°). drag frena las
+// partículas (la velocidad cae a 1/e en 1/drag segundos). El tamaño y el color
+// (con su alfa) pasan del inicial al final a lo largo de la vida.
+#[derive(Debug, Clone, Copy)]
+pub struct EmitterConfig {
+    pub rate: f32,
+    pub lifetime: f32,
+    pub speed: f32,
+    pub spread: f32,
+    pub drag: f32,
+    pub start_size: f32,
+    pub end_size: f32,
+    pub start_color: Color,
+    pub end_color: Color,
+    // Multiplica el color en el espacio lineal; más de 1 alimenta el bloom
+    pub intensity: f32,
+    pub blend: BlendMode,
+    pub shape: ParticleShape,
+    pub max_particles: usize,
+}
+
+impl Default for EmitterConfig {
+    fn default() -> Self {
+        EmitterConfig {
+            rate: 100.0,
+            lifetime: 1.0,
+            speed: 1.0,
+            spread: 0.2,
+            drag: 0.0,
+            start_size: 0.1,
+            end_size: 0.1,
+            start_color: Color::new_rgba(255, 255, 255, 255),
+            end_color: Color::new_rgba(255, 255, 255, 0),
+            intensity: 1.0,
+            blend: BlendMode::Alpha,
+            shape: ParticleShape::Billboard,
+            max_particles: 4000,
+        }
+    }
+}
+
+// Sistema de partículas genérico: colas de cometas, erupciones solares, el escape
+// de la nave o los restos de un choque. Se emite de forma continua con emit o de
+// una vez con burst, y update mueve y envejece lo que ya hay.
+pub struct ParticleSystem {
+    pub config: EmitterConfig,
+    pub particles: Vec<Particle>,
+    pending: f32,
+    rng: StdRng,
+}
+
+impl ParticleSystem {
+    pub fn new(config: EmitterConfig, seed: u64) -> Self {
+        ParticleSystem {
+            config,
+            particles: Vec::new(),
+            pending: 0.0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        let damping = (-self.config.drag * dt).exp();
+        for particle in self.particles.iter_mut() {
+            particle.age += dt;
+            particle.velocity *= damping;
+            particle.position += particle.velocity * dt;
+        }
+        self.particles.retain(|particle| particle.age < particle.lifetime);
+    }
+
+    // Emisión continua durante dt: rate partículas por segundo, multiplicado por
+    // rate_scale (por ejemplo la potencia del motor). Nacen dentro de un cubo de
+    // medio lado radius alrededor de origin y salen hacia direction.
+    pub fn emit(&mut self, dt: f32, origin: Vec3, direction: Vec3, radius: f32, rate_scale: f32) {
+        self.pending += self.config.rate * rate_scale * dt;
+        while self.pending >= 1.0 && self.particles.len() < self.config.max_particles {
+            self.pending -= 1.0;
+            self.spawn(origin, direction, radius);
+        }
+        // Lo que no entró por el límite no se acumula para el cuadro siguiente
+        self.pending = self.pending.min(1.0);
+    }
+
+    // count partículas de una vez; con direction cero salen hacia todos lados
+    pub fn burst(&mut self, count: usize, origin: Vec3, direction: Vec3, radius: f32) {
+        let count = count.min(self.config.max_particles.saturating_sub(self.particles.len()));
+        for _ in 0..count {
+            self.spawn(origin, direction, radius);
+        }
+    }
+
+    fn spawn(&mut self, origin: Vec3, direction: Vec3, radius: f32) {
+        let jitter = Vec3::new(
+            self.rng.gen_range(-1.0..1.0),
+            self.rng.gen_range(-1.0..1.0),
+            self.rng.gen_range(-1.0..1.0),
+        );
+        let heading = direction + jitter * self.config.spread;
+        let heading = heading.try_normalize(f32::EPSILON).unwrap_or(heading);
+
+        self.particles.push(Particle {
+            position: origin + jitter * radius,
+            velocity: heading * self.config.speed * self.rng.gen_range(0.6..1.4),
+            age: 0.0,
+            lifetime: self.config.lifetime * self.rng.gen_range(0.5..1.0),
+        });
+    }
+
+    // Se dibujan en el orden en que nacieron; con mezcla aditiva el orden no importa
+    pub fn render(&self, framebuffer: &mut HdrFramebuffer, view_matrix: &Mat4, projection_matrix: &Mat4, viewport_matrix: &Mat4) {
+        let config = &self.config;
+        for particle in self.particles.iter() {
+            let age = 1.0 - particle.life();
+            let color = config.start_color.lerp(&config.end_color, age);
+            let sprite = Sprite::disc(color).with_intensity(config.intensity).with_blend(config.blend);
+
+            match config.shape {
+                ParticleShape::Point => {
+                    draw_point(framebuffer, particle.position, &sprite, view_matrix, projection_matrix, viewport_matrix);
+                }
+                ParticleShape::Billboard => {
+                    let size = config.start_size + (config.end_size - config.start_size) * age;
+                    draw_billboard(framebuffer, particle.position, size, &sprite, view_matrix, projection_matrix, viewport_matrix);
+                }
+            }
+        }
+    }
+}
+
+// Cola de un cometa: partículas por segundo cerca del Sol, vida en segundos,
+// velocidad de salida, tamaño en unidades de la escena y color RGB
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct TailConfig {
+    pub rate: f32,
+    pub lifetime: f32,
+    pub speed: f32,
+    pub size: f32,
+    pub color: [u8; 3],
+}
+
+impl Default for TailConfig {
+    fn default() -> Self {
+        TailConfig {
+            rate: 120.0,
+            lifetime: 2.5,
+            speed: 1.5,
+            size: 0.15,
+            color: [170, 210, 255],
+        }
+    }
+}
+
+impl TailConfig {
+    // Las partículas se desvanecen y crecen al doble con la edad
+    fn emitter(&self) -> EmitterConfig {
+        let [r, g, b] = self.color;
+        EmitterConfig {
+            rate: self.rate,
+            lifetime: self.lifetime,
+            speed: self.speed,
+            spread: 0.12,
+            start_size: self.size,
+            end_size: self.size * 2.0,
+            start_color: Color::new_rgba(r, g, b, 90),
+            end_color: Color::new_rgba(r, g, b, 0),
+            ..EmitterConfig::default()
+        }
+    }
+}
+
+// A esta distancia del Sol la cola emite rate partículas por segundo; más cerca
+// emite más y más lejos se apaga con el cuadrado de la distancia
+const TAIL_REFERENCE_DISTANCE: f32 = 5.0;
+
+pub struct CometTail {
+    pub config: TailConfig,
+    pub particles: ParticleSystem,
+}
+
+impl CometTail {
+    pub fn new(config: TailConfig, seed: u64) -> Self {
+        CometTail {
+            config,
+            particles: ParticleSystem::new(config.emitter(), seed),
+        }
+    }
+
+    // Envejece las partículas y emite nuevas desde la superficie del núcleo,
+    // empujadas en dirección contraria al Sol
+    pub fn update(&mut self, dt: f32, nucleus: Vec3, radius: f32, sun: Vec3) {
+        self.particles.update(dt);
+
+        let away = nucleus - sun;
+        let distance = away.magnitude();
+        if distance < f32::EPSILON {
+            return;
+        }
+
+        let activity = (TAIL_REFERENCE_DISTANCE / distance).powi(2).min(4.0);
+        self.particles.emit(dt, nucleus, away / distance, radius, activity);
+    }
+
+    pub fn render(&self, framebuffer: &mut HdrFramebuffer, view_matrix: &Mat4, projection_matrix: &Mat4, viewport_matrix: &Mat4) {
+        self.particles.render(framebuffer, view_matrix, projection_matrix, viewport_matrix);
+    }
+}
@@ -0,0 +1,60 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::planet::Planet;
+
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+// Rayo desde la cámara que pasa por el píxel (x, y) de la ventana
+pub fn screen_ray(x: f32, y: f32, width: f32, height: f32, view_matrix: &Mat4, projection_matrix: &Mat4) -> Ray {
+    let ndc_x = 2.0 * x / width - 1.0;
+    let ndc_y = 1.0 - 2.0 * y / height;
+
+    let inverse = (projection_matrix * view_matrix)
+        .try_inverse()
+        .unwrap_or(Mat4::identity());
+
+    let unproject = |z: f32| -> Vec3 {
+        let point = inverse * Vec4::new(ndc_x, ndc_y, z, 1.0);
+        Vec3::new(point.x, point.y, point.z) / point.w
+    };
+
+    let near = unproject(-1.0);
+    let far = unproject(1.0);
+
+    Ray {
+        origin: near,
+        direction: (far - near).normalize(),
+    }
+}
+
+// Distancia a lo largo del rayo hasta la esfera, si la toca
+pub fn intersect_sphere(ray: &Ray, center: Vec3, radius: f32) -> Option<f32> {
+    let oc = ray.origin - center;
+    let b = oc.dot(&ray.direction);
+    let c = oc.dot(&oc) - radius * radius;
+    let discriminant = b * b - c;
+
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let sqrt_d = discriminant.sqrt();
+    let t = if -b - sqrt_d >= 0.0 { -b - sqrt_d } else { -b + sqrt_d };
+
+    if t >= 0.0 { Some(t) } else { None }
+}
+
+// Índice del planeta más cercano que atraviesa el rayo, contra la esfera que
+// envuelve su malla (un modelo o el relieve no tienen por qué medir radio 1)
+pub fn pick_planet(ray: &Ray, planets: &[Planet]) -> Option<usize> {
+    planets
+        .iter()
+        .enumerate()
+        .filter_map(|(index, planet)| {
+            intersect_sphere(ray, planet.translation, planet.mesh.radius() * planet.scale).map(|t| (index, t))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(index, _)| index)
+}
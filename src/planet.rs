@@ -0,0 +1,156 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::TAU;
+use fastnoise_lite::FastNoiseLite;
+use crate::ambience::AmbientConfig;
+use crate::color::{Color, Gradient};
+use crate::lighting::{Light, PbrMaterial};
+use crate::lod::LodMeshes;
+use crate::mesh::Mesh;
+use crate::noise::NoiseConfig;
+use crate::orbit::Orbit;
+use crate::particles::CometTail;
+use crate::prominence::Prominences;
+use crate::script::ScriptShader;
+use crate::shaders::ShaderKind;
+use crate::texture::Texture;
+
+pub struct Planet {
+    pub name: String,
+    // Índice del cuerpo alrededor del que orbita (las lunas); None orbita el origen
+    pub parent: Option<usize>,
+    pub translation: Vec3,
+    pub rotation: Vec3,
+    pub scale: f32,
+    pub orbit: Option<Orbit>,
+    // Inclinación del eje de giro en radianes, respecto a la normal del plano de la órbita
+    pub axial_tilt: f32,
+    // Duración de un día en segundos; negativo gira al revés y 0 no gira
+    pub rotation_period: f32,
+    // Masa para el modo de gravedad
+    pub mass: f32,
+    pub noise_config: NoiseConfig,
+    pub noise: FastNoiseLite,
+    // Ruido cellular propio de los cráteres del shader "moon", con la semilla y la
+    // frecuencia del cuerpo aunque su ruido sea de otro tipo
+    pub craters: FastNoiseLite,
+    // Niveles de detalle, compartidos con los demás cuerpos de la misma forma salvo si tiene relieve
+    pub mesh: LodMeshes,
+    pub shader: ShaderKind,
+    pub texture: Option<Texture>,
+    pub double_sided: bool,
+    pub transparent: bool,
+    pub opacity: f32,
+    pub emissive: bool,
+    // Color de una estrella: tiñe su superficie y su halo
+    pub tint: Option<Color>,
+    // Rampa de colores que reemplaza a la del shader (lava, bandas, sol, luna)
+    pub palette: Option<Gradient>,
+    // Shader en Rhai para ShaderKind::Script, que se recarga al cambiar el archivo
+    pub script: Option<ScriptShader>,
+    pub rings: Option<Rings>,
+    pub clouds: Option<CloudLayer>,
+    pub atmosphere: Option<Atmosphere>,
+    pub city_lights: bool,
+    // Color del crepúsculo en la franja entre el día y la noche
+    pub twilight: Option<Color>,
+    // Material metálico-rugoso para el hielo, el agua y el metal de sus shaders
+    pub pbr: Option<PbrMaterial>,
+    // Luz puntual que sale del cuerpo (una luna brillante); su posición sigue a la del cuerpo
+    pub light: Option<Light>,
+    pub tail: Option<CometTail>,
+    // Arcos de plasma sobre la superficie de una estrella
+    pub prominences: Option<Prominences>,
+    // Sonido que se oye al acercarse al cuerpo
+    pub ambient: Option<AmbientConfig>,
+}
+
+pub struct Rings {
+    pub mesh: Mesh,
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+    pub tilt: f32,
+}
+
+// Esfera translúcida alrededor del planeta; height es el radio relativo
+pub struct CloudLayer {
+    pub mesh: LodMeshes,
+    pub height: f32,
+    pub rotation: f32,
+    pub rotation_speed: f32,
+}
+
+// Capa de aire: se dibuja como una esfera de radio (1 + thickness) que solo
+// es visible cerca del borde, donde la vista es casi tangente a la superficie
+pub struct Atmosphere {
+    pub mesh: LodMeshes,
+    pub color: Color,
+    pub thickness: f32,
+}
+
+// La capa de nubes sobresale un poco de la superficie
+const COLLISION_MARGIN: f32 = 1.1;
+
+impl Planet {
+    // Radio de la esfera de colisión del cuerpo: el de su malla (un modelo o el relieve
+    // desplazado pueden pasarse del radio 1) con un margen
+    pub fn collision_radius(&self) -> f32 {
+        self.mesh.radius() * self.scale * COLLISION_MARGIN
+    }
+
+    // Radio de una esfera que contiene todo lo que se dibuja del cuerpo:
+    // su malla, las nubes, la atmósfera y los anillos
+    pub fn bounding_radius(&self) -> f32 {
+        let mut radius = self.mesh.radius();
+        if let Some(clouds) = &self.clouds {
+            radius = radius.max(clouds.height * clouds.mesh.radius());
+        }
+        if let Some(atmosphere) = &self.atmosphere {
+            radius = radius.max((1.0 + atmosphere.thickness) * atmosphere.mesh.radius());
+        }
+        // La corona de los anillos llega hasta su radio exterior
+        if let Some(rings) = &self.rings {
+            radius = radius.max(rings.outer_radius);
+        }
+        radius * self.scale
+    }
+
+    pub fn update(&mut self, sim_time: f32, dt: f32) {
+        // Los planetas orbitan alrededor del Sol; las lunas, relativo a su planeta
+        if let Some(orbit) = &self.orbit {
+            self.translation = orbit.position(sim_time);
+        }
+        // El giro sale del tiempo absoluto, así pausa y reversa quedan consistentes;
+        // la inclinación (en z) se aplica después, de modo que el eje queda inclinado
+        let spin = if self.rotation_period != 0.0 { TAU * sim_time / self.rotation_period } else { 0.0 };
+        self.rotation = Vec3::new(0.0, spin % TAU, self.axial_tilt);
+        if let Some(clouds) = &mut self.clouds {
+            clouds.rotation += clouds.rotation_speed * dt;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assets::AssetManager;
+    use crate::save::SceneSource;
+
+    #[test]
+    fn collision_radius_follows_the_mesh() {
+        let source = SceneSource::File { path: "assets/scenes/solar_system.toml".to_string() };
+        let mut scene = source.build(&mut AssetManager::new()).unwrap();
+        let planet = &mut scene.planets[1];
+        let radius = planet.collision_radius();
+
+        // Un modelo del doble de tamaño que la esfera unitaria
+        planet.mesh = planet.mesh.map(|level| {
+            let mut mesh = level.clone();
+            for vertex in mesh.vertices.iter_mut() {
+                vertex.position *= 2.0;
+            }
+            mesh
+        });
+        assert!((planet.mesh.radius() - 2.0).abs() < 1e-4);
+        assert!((planet.collision_radius() - radius * 2.0).abs() < 1e-4);
+        assert!(planet.collision_radius() > planet.mesh.radius() * planet.scale);
+    }
+}
@@ -0,0 +1,619 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use rayon::prelude::*;
+use std::str::FromStr;
+use crate::billboard::{draw_sprite, BlendMode, Sprite};
+use crate::color::Color;
+use crate::framebuffer::{Framebuffer, HdrFramebuffer};
+use crate::grading::ColorGrading;
+
+// Tamaño del halo respecto al radio del disco en pantalla
+const GLOW_SIZE: f32 = 2.5;
+// Largo máximo de las estelas del salto, como fracción de la distancia al centro
+const WARP_STREAK_LENGTH: f32 = 0.3;
+// Las muestras van cada un píxel a lo largo de la estela, hasta este máximo
+const WARP_MAX_SAMPLES: usize = 48;
+// Intensidad hacia la que va el destello del salto: lo bastante alta para que
+// quede blanco con cualquier curva de tonos
+const WARP_FLASH_RADIANCE: f32 = 16.0;
+// Reflejos del destello de lente sobre la recta que va del Sol al centro de la
+// pantalla: posición (0 es el Sol, 1 el centro y 2 el punto opuesto), radio como
+// fracción del alto de la imagen, color e intensidad
+const FLARE_GHOSTS: [(f32, f32, Color, f32); 7] = [
+    (0.3, 0.020, Color::new(255, 220, 150), 1.0),
+    (0.55, 0.055, Color::new(255, 170, 90), 0.45),
+    (0.85, 0.015, Color::new(255, 255, 255), 1.2),
+    (1.15, 0.035, Color::new(130, 255, 170), 0.5),
+    (1.4, 0.090, Color::new(120, 150, 255), 0.3),
+    (1.7, 0.030, Color::new(255, 140, 210), 0.6),
+    (2.0, 0.130, Color::new(170, 120, 255), 0.22),
+];
+// Muestras por lado de la grilla con que se mide qué parte del disco se ve
+const FLARE_OCCLUSION_SAMPLES: usize = 5;
+
+// Halo aditivo alrededor de un objeto emisivo, como un billboard circular
+// centrado en su posición proyectada. No se dibuja sobre objetos más cercanos.
+pub fn emissive_glow(
+    framebuffer: &mut HdrFramebuffer,
+    center: Vec3,
+    radius: f32,
+    camera_right: Vec3,
+    view_projection: &Mat4,
+    viewport_matrix: &Mat4,
+    color: Color,
+) {
+    let project = |point: Vec3| -> Option<Vec3> {
+        let clip = view_projection * Vec4::new(point.x, point.y, point.z, 1.0);
+        if clip.w <= 0.0 {
+            return None;
+        }
+        let screen = viewport_matrix * Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+        Some(Vec3::new(screen.x, screen.y, screen.z))
+    };
+
+    let (screen_center, screen_edge) = match (project(center), project(center + camera_right * radius)) {
+        (Some(c), Some(e)) => (c, e),
+        _ => return,
+    };
+
+    let color = color.to_linear();
+    let disc_radius = (screen_edge - screen_center).xy().magnitude();
+    let glow_radius = disc_radius * GLOW_SIZE;
+    if glow_radius < 1.0 {
+        return;
+    }
+
+    let min_x = (screen_center.x - glow_radius).max(0.0) as usize;
+    let max_x = (screen_center.x + glow_radius).min(framebuffer.width as f32 - 1.0).max(0.0) as usize;
+    let min_y = (screen_center.y - glow_radius).max(0.0) as usize;
+    let max_y = (screen_center.y + glow_radius).min(framebuffer.height as f32 - 1.0).max(0.0) as usize;
+
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let index = y * framebuffer.width + x;
+
+            // Algo delante del objeto tapa el halo
+            if framebuffer.depth.get(x, y) < screen_center.z {
+                continue;
+            }
+
+            let dx = x as f32 + 0.5 - screen_center.x;
+            let dy = y as f32 + 0.5 - screen_center.y;
+            let distance = (dx * dx + dy * dy).sqrt() / glow_radius;
+            if distance >= 1.0 {
+                continue;
+            }
+
+            let falloff = (1.0 - distance).powi(3);
+            framebuffer.buffer[index] += color * falloff;
+        }
+    }
+}
+
+// Destello de lente de una estrella: una cadena de reflejos sobre la recta que pasa
+// por su posición en pantalla y el centro de la imagen. El brillo es la fracción del
+// disco que no está tapada según el buffer de profundidad, se apaga cuando la
+// estrella se acerca al borde de la pantalla y cae a lo largo de la cadena.
+pub fn lens_flare(
+    framebuffer: &mut HdrFramebuffer,
+    center: Vec3,
+    radius: f32,
+    camera_eye: Vec3,
+    view_projection: &Mat4,
+    viewport_matrix: &Mat4,
+    color: Color,
+) {
+    let project = |point: Vec3| -> Option<Vec3> {
+        let clip = view_projection * Vec4::new(point.x, point.y, point.z, 1.0);
+        if clip.w <= 0.0 {
+            return None;
+        }
+        let screen = viewport_matrix * Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+        Some(Vec3::new(screen.x, screen.y, screen.z))
+    };
+
+    // El punto del disco más cercano a la cámara: lo que esté delante de él tapa a la estrella
+    let toward_camera = (camera_eye - center).try_normalize(f32::EPSILON).unwrap_or(Vec3::z());
+    let (screen_center, nearest) = match (project(center), project(center + toward_camera * radius)) {
+        (Some(c), Some(n)) => (c, n),
+        _ => return,
+    };
+    let width = framebuffer.width as f32;
+    let height = framebuffer.height as f32;
+    if screen_center.x < 0.0 || screen_center.y < 0.0 || screen_center.x >= width || screen_center.y >= height {
+        return;
+    }
+
+    // Radio del disco en pantalla: un punto del borde visto de costado
+    let side = toward_camera.cross(&Vec3::y()).try_normalize(f32::EPSILON).unwrap_or(Vec3::x());
+    let disc_radius = match project(center + side * radius) {
+        Some(edge) => (edge - screen_center).xy().magnitude().max(1.0),
+        None => return,
+    };
+
+    let mut visible = 0;
+    for i in 0..FLARE_OCCLUSION_SAMPLES {
+        for j in 0..FLARE_OCCLUSION_SAMPLES {
+            let step = |k: usize| (k as f32 + 0.5) / FLARE_OCCLUSION_SAMPLES as f32 * 2.0 - 1.0;
+            let x = screen_center.x + step(i) * disc_radius * 0.7;
+            let y = screen_center.y + step(j) * disc_radius * 0.7;
+            if x < 0.0 || y < 0.0 || x >= width || y >= height {
+                continue;
+            }
+            if framebuffer.depth.get(x as usize, y as usize) >= nearest.z {
+                visible += 1;
+            }
+        }
+    }
+    let visibility = visible as f32 / (FLARE_OCCLUSION_SAMPLES * FLARE_OCCLUSION_SAMPLES) as f32;
+
+    // De 1 en el centro de la pantalla a 0 en el borde más cercano
+    let screen_middle = Vec3::new(width / 2.0, height / 2.0, 0.0);
+    let offset = (screen_center - screen_middle).xy();
+    let edge = (offset.x.abs() / (width / 2.0)).max(offset.y.abs() / (height / 2.0));
+    let strength = visibility * (1.0 - edge * edge).max(0.0);
+    if strength <= 0.0 {
+        return;
+    }
+
+    let axis = screen_middle - screen_center;
+    for (position, size, ghost, intensity) in FLARE_GHOSTS {
+        let falloff = 1.0 / (1.0 + position);
+        let alpha = (strength * 255.0).round() as u8;
+        let sprite = Sprite::disc(ghost.lerp(&color, 0.3).with_alpha(alpha))
+            .with_intensity(intensity * falloff)
+            .with_blend(BlendMode::Additive)
+            .without_depth_test();
+        let ghost_center = screen_center + axis * position;
+        draw_sprite(framebuffer, ghost_center, size * height, &sprite);
+    }
+}
+
+// Efecto del salto entre sistemas: cada píxel toma lo más brillante de las muestras
+// que tiene hacia el centro de la pantalla (estelas radiales, como estrellas que pasan
+// de largo) y la imagen se aclara hasta el blanco cuando intensity llega a 1
+pub fn warp_streaks(framebuffer: &mut HdrFramebuffer, intensity: f32) {
+    if intensity <= 0.0 {
+        return;
+    }
+
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let source = framebuffer.buffer.clone();
+    let (center_x, center_y) = (width as f32 / 2.0, height as f32 / 2.0);
+    let length = WARP_STREAK_LENGTH * intensity;
+    let flash = intensity.powi(6);
+    let white = Vec3::repeat(WARP_FLASH_RADIANCE);
+
+    framebuffer.buffer
+        .par_chunks_mut(width)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let mut streak = source[y * width + x];
+                let distance = (x as f32 - center_x).hypot(y as f32 - center_y);
+                let samples = ((distance * length) as usize).clamp(1, WARP_MAX_SAMPLES);
+                for i in 1..samples {
+                    let t = i as f32 / samples as f32;
+                    let scale = 1.0 - length * t;
+                    let sample_x = ((center_x + (x as f32 - center_x) * scale) as usize).min(width - 1);
+                    let sample_y = ((center_y + (y as f32 - center_y) * scale) as usize).min(height - 1);
+                    let sample = source[sample_y * width + sample_x] * (1.0 - t);
+                    streak = streak.sup(&sample);
+                }
+                *pixel = streak.lerp(&white, flash);
+            }
+        });
+}
+
+// Colores de la vista de sobredibujo según los fragmentos por píxel (el último
+// vale para esa cantidad o más)
+const OVERDRAW_COLORS: [Color; 6] = [
+    Color::new(0, 0, 0),
+    Color::new(0, 40, 160),
+    Color::new(0, 170, 170),
+    Color::new(40, 200, 40),
+    Color::new(240, 220, 0),
+    Color::new(255, 40, 0),
+];
+
+// Convierte las cuentas de fragmentos que dejó la vista de sobredibujo en un mapa de calor
+pub fn overdraw_heatmap(framebuffer: &mut HdrFramebuffer) {
+    framebuffer.buffer.par_iter_mut().for_each(|pixel| {
+        *pixel = OVERDRAW_COLORS[(pixel.x as usize).min(OVERDRAW_COLORS.len() - 1)].to_linear();
+    });
+}
+
+// Curva que lleva el color HDR (lineal, sin tope) al rango de la pantalla
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToneMap {
+    // Solo recorta en 1; para las vistas de depuración y para comparar
+    None,
+    // x / (1 + x): suave, pero apaga los tonos medios
+    Reinhard,
+    // Aproximación de la curva filmica ACES, con más contraste
+    Aces,
+}
+
+impl ToneMap {
+    pub fn apply(self, color: Vec3) -> Vec3 {
+        match self {
+            ToneMap::None => color,
+            ToneMap::Reinhard => color.component_div(&(color + Vec3::repeat(1.0))),
+            ToneMap::Aces => color.map(|x| {
+                let x = x.max(0.0);
+                (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+            }),
+        }
+    }
+}
+
+impl FromStr for ToneMap {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "none" => Ok(ToneMap::None),
+            "reinhard" => Ok(ToneMap::Reinhard),
+            "aces" => Ok(ToneMap::Aces),
+            _ => Err(format!("curva de tonos desconocida '{}' (se espera none, reinhard o aces)", name)),
+        }
+    }
+}
+
+// Matriz de Bayer 8x8 para el tramado ordenado: cada valor aparece una vez, así que
+// en cualquier bloque de 8x8 los umbrales se reparten parejo entre 0 y 1
+const BAYER_8X8: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+fn dither_threshold(x: usize, y: usize) -> f32 {
+    (BAYER_8X8[y % 8][x % 8] as f32 + 0.5) / 64.0
+}
+
+// Lleva el framebuffer interno (HDR) al de salida, que puede tener otro tamaño:
+// cada muestra pasa por la exposición y la curva de tonos y se codifica en sRGB.
+// Si el interno es más grande (supersampling) se promedian las muestras que
+// cubre cada píxel de salida; si es más pequeño se interpola bilinealmente.
+// La corrección de color, si hay una, se aplica a cada píxel ya codificado en sRGB
+// pero antes de pasarlo a 8 bits, así no suma su propio redondeo al de la salida.
+// Con dither la cuantización a 8 bits usa tramado ordenado en lugar de redondear,
+// para que los degradados suaves (hielo, atmósferas) no se vean en escalones.
+pub fn resolve(
+    source: &HdrFramebuffer,
+    target: &mut Framebuffer,
+    tone_map: ToneMap,
+    exposure: f32,
+    dither: bool,
+    grading: Option<&ColorGrading>,
+) {
+    let (source_width, source_height) = (source.width, source.height);
+    let target_width = target.width;
+    if source_width == 0 || source_height == 0 || target_width == 0 {
+        return;
+    }
+
+    let display = |index: usize| tone_map.apply(source.buffer[index] * exposure);
+    let grading = grading.filter(|grading| !grading.is_neutral());
+    let encode = |color: &Vec3, x: usize, y: usize| {
+        let threshold = if dither { dither_threshold(x, y) } else { 0.5 };
+        match grading {
+            Some(grading) => Color::from_srgb_dithered(&grading.grade(Color::encode_srgb(color)), threshold).to_hex(),
+            None => Color::from_linear_dithered(color, threshold).to_hex(),
+        }
+    };
+
+    if source_width == target.width && source_height == target.height {
+        target.buffer
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(index, pixel)| *pixel = encode(&display(index), index % target_width, index / target_width));
+        return;
+    }
+
+    let scale_x = source_width as f32 / target_width as f32;
+    let scale_y = source_height as f32 / target.height as f32;
+
+    target.buffer
+        .par_chunks_mut(target_width)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let color = if scale_x >= 1.0 && scale_y >= 1.0 {
+                    // Filtro de caja sobre los píxeles del framebuffer interno
+                    let x0 = (x as f32 * scale_x) as usize;
+                    let y0 = (y as f32 * scale_y) as usize;
+                    let x1 = (((x + 1) as f32 * scale_x) as usize).clamp(x0 + 1, source_width);
+                    let y1 = (((y + 1) as f32 * scale_y) as usize).clamp(y0 + 1, source_height);
+
+                    let mut sum = Vec3::zeros();
+                    for sy in y0..y1 {
+                        for sx in x0..x1 {
+                            sum += display(sy * source_width + sx);
+                        }
+                    }
+                    sum / ((x1 - x0) * (y1 - y0)) as f32
+                } else {
+                    // Interpolación bilineal entre los cuatro vecinos
+                    let fx = ((x as f32 + 0.5) * scale_x - 0.5).clamp(0.0, (source_width - 1) as f32);
+                    let fy = ((y as f32 + 0.5) * scale_y - 0.5).clamp(0.0, (source_height - 1) as f32);
+                    let (x0, y0) = (fx as usize, fy as usize);
+                    let (x1, y1) = ((x0 + 1).min(source_width - 1), (y0 + 1).min(source_height - 1));
+                    let (tx, ty) = (fx - x0 as f32, fy - y0 as f32);
+
+                    let top = display(y0 * source_width + x0).lerp(&display(y0 * source_width + x1), tx);
+                    let bottom = display(y1 * source_width + x0).lerp(&display(y1 * source_width + x1), tx);
+                    top.lerp(&bottom, ty)
+                };
+                *pixel = encode(&color, x, y);
+            }
+        });
+}
+
+// FXAA: un píxel es borde si el contraste de luminancia con sus vecinos supera
+// el mayor de estos dos umbrales (absoluto y relativo al máximo local)
+const FXAA_EDGE_THRESHOLD: f32 = 0.125;
+const FXAA_EDGE_THRESHOLD_MIN: f32 = 0.0312;
+// Cuánto se suavizan los detalles de un píxel de ancho (0 = nada)
+const FXAA_SUBPIXEL: f32 = 0.75;
+// Pasos máximos al recorrer el borde hacia cada lado para encontrar su fin
+const FXAA_SEARCH_STEPS: usize = 12;
+
+// Suavizado de bordes sobre la imagen final: en cada píxel de borde se busca la
+// dirección y el largo del escalón y se toma una muestra desplazada hacia el otro
+// lado del borde, proporcional a lo cerca que está el píxel de un extremo
+pub fn fxaa(framebuffer: &mut Framebuffer) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    if width < 3 || height < 3 {
+        return;
+    }
+
+    let source = framebuffer.buffer.clone();
+    let luma: Vec<f32> = source.par_iter().map(|&pixel| fxaa_luma(pixel)).collect();
+
+    let luma_at = |x: i32, y: i32| -> f32 {
+        luma[y.clamp(0, height as i32 - 1) as usize * width + x.clamp(0, width as i32 - 1) as usize]
+    };
+    // Luminancia interpolada en una posición continua (centros de píxel en +0.5)
+    let luma_sample = |x: f32, y: f32| -> f32 {
+        let (fx, fy) = (x - 0.5, y - 0.5);
+        let (x0, y0) = (fx.floor(), fy.floor());
+        let (tx, ty) = (fx - x0, fy - y0);
+        let (x0, y0) = (x0 as i32, y0 as i32);
+        let top = luma_at(x0, y0) + (luma_at(x0 + 1, y0) - luma_at(x0, y0)) * tx;
+        let bottom = luma_at(x0, y0 + 1) + (luma_at(x0 + 1, y0 + 1) - luma_at(x0, y0 + 1)) * tx;
+        top + (bottom - top) * ty
+    };
+    let color_sample = |x: f32, y: f32| -> u32 {
+        let fx = (x - 0.5).clamp(0.0, (width - 1) as f32);
+        let fy = (y - 0.5).clamp(0.0, (height - 1) as f32);
+        let (x0, y0) = (fx as usize, fy as usize);
+        let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+        let (tx, ty) = (fx - x0 as f32, fy - y0 as f32);
+
+        let top = Color::from_hex(source[y0 * width + x0]).lerp(&Color::from_hex(source[y0 * width + x1]), tx);
+        let bottom = Color::from_hex(source[y1 * width + x0]).lerp(&Color::from_hex(source[y1 * width + x1]), tx);
+        top.lerp(&bottom, ty).to_hex()
+    };
+
+    framebuffer.buffer
+        .par_chunks_mut(width)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let (xi, yi) = (x as i32, y as i32);
+                let center = luma_at(xi, yi);
+                let (north, south) = (luma_at(xi, yi - 1), luma_at(xi, yi + 1));
+                let (west, east) = (luma_at(xi - 1, yi), luma_at(xi + 1, yi));
+
+                let max = center.max(north).max(south).max(west).max(east);
+                let min = center.min(north).min(south).min(west).min(east);
+                let range = max - min;
+                if range < FXAA_EDGE_THRESHOLD_MIN.max(max * FXAA_EDGE_THRESHOLD) {
+                    continue;
+                }
+
+                let (north_west, north_east) = (luma_at(xi - 1, yi - 1), luma_at(xi + 1, yi - 1));
+                let (south_west, south_east) = (luma_at(xi - 1, yi + 1), luma_at(xi + 1, yi + 1));
+
+                // Un borde horizontal cambia mucho entre filas y poco entre columnas
+                let horizontal = (north_west - 2.0 * west + south_west).abs()
+                    + 2.0 * (north - 2.0 * center + south).abs()
+                    + (north_east - 2.0 * east + south_east).abs();
+                let vertical = (north_west - 2.0 * north + north_east).abs()
+                    + 2.0 * (west - 2.0 * center + east).abs()
+                    + (south_west - 2.0 * south + south_east).abs();
+                let is_horizontal = horizontal >= vertical;
+
+                // Suavizado de subpíxel: cuánto se aparta el píxel del promedio de sus vecinos
+                let average = (2.0 * (north + south + west + east)
+                    + north_west + north_east + south_west + south_east) / 12.0;
+                let subpixel = ((average - center).abs() / range).clamp(0.0, 1.0);
+                let subpixel = (-2.0 * subpixel + 3.0) * subpixel * subpixel;
+                let subpixel_offset = subpixel * subpixel * FXAA_SUBPIXEL;
+
+                // Lado del borde con el escalón más marcado; el paso apunta hacia él
+                let (before, after) = if is_horizontal { (north, south) } else { (west, east) };
+                let (gradient_before, gradient_after) = ((before - center).abs(), (after - center).abs());
+                let (step, side_luma, gradient) = if gradient_before >= gradient_after {
+                    (-1.0, before, gradient_before)
+                } else {
+                    (1.0, after, gradient_after)
+                };
+                let edge_luma = (center + side_luma) * 0.5;
+                let gradient_scaled = gradient * 0.25;
+
+                // Se recorre el borde (a medio píxel, sobre la línea entre los dos lados)
+                // hasta que la luminancia deja de parecerse a la del borde
+                let (origin_x, origin_y) = (x as f32 + 0.5, y as f32 + 0.5);
+                let (start_x, start_y, dx, dy) = if is_horizontal {
+                    (origin_x, origin_y + step * 0.5, 1.0, 0.0)
+                } else {
+                    (origin_x + step * 0.5, origin_y, 0.0, 1.0)
+                };
+                let search = |direction: f32| -> (f32, f32) {
+                    let mut end_luma = 0.0;
+                    let mut distance = 0.0;
+                    for _ in 0..FXAA_SEARCH_STEPS {
+                        distance += 1.0;
+                        end_luma = luma_sample(start_x + dx * direction * distance, start_y + dy * direction * distance) - edge_luma;
+                        if end_luma.abs() >= gradient_scaled {
+                            break;
+                        }
+                    }
+                    (distance, end_luma)
+                };
+                let (distance_negative, end_negative) = search(-1.0);
+                let (distance_positive, end_positive) = search(1.0);
+
+                // Solo se mezcla si el extremo más cercano termina del lado correcto del borde
+                let center_below = center - edge_luma < 0.0;
+                let (distance, end_luma) = if distance_negative < distance_positive {
+                    (distance_negative, end_negative)
+                } else {
+                    (distance_positive, end_positive)
+                };
+                let edge_offset = if (end_luma < 0.0) != center_below {
+                    0.5 - distance / (distance_negative + distance_positive)
+                } else {
+                    0.0
+                };
+
+                let offset = edge_offset.max(subpixel_offset) * step;
+                *pixel = if is_horizontal {
+                    color_sample(origin_x, origin_y + offset)
+                } else {
+                    color_sample(origin_x + offset, origin_y)
+                };
+            }
+        });
+}
+
+// Luminancia perceptual de un píxel, en [0, 1]
+fn fxaa_luma(pixel: u32) -> f32 {
+    let (r, g, b) = ((pixel >> 16) & 0xFF, (pixel >> 8) & 0xFF, pixel & 0xFF);
+    (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32) / 255.0
+}
+
+// Bloom: luminancia (lineal) desde la que un píxel empieza a brillar, cuánto del halo
+// se suma a la imagen y desviación del desenfoque (en píxeles del buffer a media resolución)
+const BLOOM_THRESHOLD: f32 = 0.8;
+const BLOOM_STRENGTH: f32 = 0.6;
+const BLOOM_SIGMA: f32 = 4.0;
+const LUMA: Vec3 = Vec3::new(0.2126, 0.7152, 0.0722);
+
+// Halo alrededor de lo más brillante de la imagen (el Sol, la lava): se separan los
+// píxeles que pasan el umbral a un buffer a media resolución, se desenfocan con una
+// gaussiana separable y se suman de vuelta al HDR. Los buffers se reutilizan entre frames.
+pub struct Bloom {
+    width: usize,
+    height: usize,
+    bright: Vec<Vec3>,
+    blurred: Vec<Vec3>,
+    kernel: Vec<f32>,
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Bloom::new()
+    }
+}
+
+impl Bloom {
+    pub fn new() -> Self {
+        let radius = (BLOOM_SIGMA * 3.0).ceil() as i32;
+        let weights: Vec<f32> = (-radius..=radius)
+            .map(|i| (-((i * i) as f32) / (2.0 * BLOOM_SIGMA * BLOOM_SIGMA)).exp())
+            .collect();
+        let total: f32 = weights.iter().sum();
+
+        Bloom {
+            width: 0,
+            height: 0,
+            bright: Vec::new(),
+            blurred: Vec::new(),
+            kernel: weights.iter().map(|weight| weight / total).collect(),
+        }
+    }
+
+    pub fn apply(&mut self, framebuffer: &mut HdrFramebuffer) {
+        let (source_width, source_height) = (framebuffer.width, framebuffer.height);
+        if source_width == 0 || source_height == 0 {
+            return;
+        }
+
+        let (width, height) = (source_width.div_ceil(2), source_height.div_ceil(2));
+        self.width = width;
+        self.height = height;
+        self.bright.resize(width * height, Vec3::zeros());
+        self.blurred.resize(width * height, Vec3::zeros());
+
+        // Umbral: cada píxel del buffer reducido promedia 2x2 de la imagen y conserva
+        // solo lo que supera el umbral
+        let source = &framebuffer.buffer;
+        self.bright.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let mut sum = Vec3::zeros();
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let sx = (x * 2 + dx).min(source_width - 1);
+                    let sy = (y * 2 + dy).min(source_height - 1);
+                    sum += source[sy * source_width + sx];
+                }
+                let color = sum / 4.0;
+                let luma = color.dot(&LUMA);
+                *pixel = if luma > BLOOM_THRESHOLD {
+                    // Solo la parte que pasa el umbral, para que el halo crezca de a poco
+                    color * ((luma - BLOOM_THRESHOLD) / luma)
+                } else {
+                    Vec3::zeros()
+                };
+            }
+        });
+
+        // Gaussiana separable: primero las filas, después las columnas
+        let kernel = &self.kernel;
+        let radius = kernel.len() as i32 / 2;
+        let bright = &self.bright;
+        self.blurred.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = kernel.iter().enumerate().fold(Vec3::zeros(), |sum, (i, weight)| {
+                    let sx = (x as i32 + i as i32 - radius).clamp(0, width as i32 - 1) as usize;
+                    sum + bright[y * width + sx] * *weight
+                });
+            }
+        });
+        let blurred = &self.blurred;
+        self.bright.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = kernel.iter().enumerate().fold(Vec3::zeros(), |sum, (i, weight)| {
+                    let sy = (y as i32 + i as i32 - radius).clamp(0, height as i32 - 1) as usize;
+                    sum + blurred[sy * width + x] * *weight
+                });
+            }
+        });
+
+        // Se suma el halo, interpolado bilinealmente a la resolución completa
+        let glow = &self.bright;
+        let sample = |x: f32, y: f32| -> Vec3 {
+            let fx = x.clamp(0.0, (width - 1) as f32);
+            let fy = y.clamp(0.0, (height - 1) as f32);
+            let (x0, y0) = (fx as usize, fy as usize);
+            let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+            let (tx, ty) = (fx - x0 as f32, fy - y0 as f32);
+            let top = glow[y0 * width + x0].lerp(&glow[y0 * width + x1], tx);
+            let bottom = glow[y1 * width + x0].lerp(&glow[y1 * width + x1], tx);
+            top.lerp(&bottom, ty)
+        };
+        framebuffer.buffer.par_chunks_mut(source_width).enumerate().for_each(|(y, row)| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let halo = sample((x as f32 + 0.5) / 2.0 - 0.5, (y as f32 + 0.5) / 2.0 - 0.5);
+                *pixel += halo * BLOOM_STRENGTH;
+            }
+        });
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{info, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+// Cada cuánto se promedian los tiempos para el informe
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+// Etapas del render que se miden, cada una con un span del mismo nombre en el
+// renderizador: el fondo, el vertex shader con el ensamblado y el recorte, la
+// rasterización, la luz del G-buffer, el sombreado de los fragmentos (en el dibujo
+// directo de los transparentes la rasterización y el sombreado van juntos y cuentan
+// como fragmentos) y lo que va después (partículas, halos, bloom, curva de tonos)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Background,
+    Vertex,
+    Raster,
+    Lighting,
+    Fragment,
+    Post,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 6] = [Stage::Background, Stage::Vertex, Stage::Raster, Stage::Lighting, Stage::Fragment, Stage::Post];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Background => "background",
+            Stage::Vertex => "vertex",
+            Stage::Raster => "raster",
+            Stage::Lighting => "lighting",
+            Stage::Fragment => "fragment",
+            Stage::Post => "post",
+        }
+    }
+
+    // Nombre en el HUD
+    pub fn label(self) -> &'static str {
+        match self {
+            Stage::Background => "FONDO",
+            Stage::Vertex => "VERTICES",
+            Stage::Raster => "RASTER",
+            Stage::Lighting => "LUZ",
+            Stage::Fragment => "FRAGMENTOS",
+            Stage::Post => "POST",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Stage::ALL.into_iter().find(|stage| stage.name() == name)
+    }
+}
+
+// Tiempo acumulado desde el último frame: el span "frame" completo, cada etapa y
+// los spans "draw" de cada cuerpo (su campo body), que suman lo que tardan sus
+// llamadas de dibujo (el planeta con sus nubes, atmósfera y anillos)
+#[derive(Debug, Clone, Default)]
+struct Times {
+    frame: Duration,
+    stages: [Duration; Stage::ALL.len()],
+    bodies: HashMap<String, Duration>,
+}
+
+impl Times {
+    fn add(&mut self, other: &Times) {
+        self.frame += other.frame;
+        for (total, time) in self.stages.iter_mut().zip(other.stages) {
+            *total += time;
+        }
+        for (body, time) in &other.bodies {
+            *self.bodies.entry(body.clone()).or_default() += *time;
+        }
+    }
+}
+
+// Promedio por frame de un intervalo
+#[derive(Debug, Clone, Default)]
+pub struct ProfileReport {
+    pub frames: u32,
+    pub frame: Duration,
+    pub stages: [Duration; Stage::ALL.len()],
+    // De la más lenta a la más rápida
+    pub bodies: Vec<(String, Duration)>,
+}
+
+impl ProfileReport {
+    pub fn stage(&self, stage: Stage) -> Duration {
+        self.stages[stage as usize]
+    }
+
+    // Un evento con los tiempos de las etapas y uno por cuerpo, en milisegundos
+    pub fn log(&self) {
+        let ms = |time: Duration| time.as_secs_f32() * 1000.0;
+        info!(
+            frames = self.frames,
+            frame_ms = ms(self.frame),
+            background_ms = ms(self.stage(Stage::Background)),
+            vertex_ms = ms(self.stage(Stage::Vertex)),
+            raster_ms = ms(self.stage(Stage::Raster)),
+            lighting_ms = ms(self.stage(Stage::Lighting)),
+            fragment_ms = ms(self.stage(Stage::Fragment)),
+            post_ms = ms(self.stage(Stage::Post)),
+            "frame"
+        );
+        for (body, time) in &self.bodies {
+            info!(body = %body, ms = ms(*time), "draw");
+        }
+    }
+}
+
+// Mide los frames del renderizador con los spans de tracing: el Layer que devuelve
+// layer() se instala en el suscriptor y end_frame(), una vez por frame, cierra las
+// cuentas y cada REPORT_INTERVAL arma un informe con los promedios
+pub struct Profiler {
+    current: Arc<Mutex<Times>>,
+    interval: Times,
+    frames: u32,
+    started: Instant,
+    report: ProfileReport,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler {
+            current: Arc::new(Mutex::new(Times::default())),
+            interval: Times::default(),
+            frames: 0,
+            started: Instant::now(),
+            report: ProfileReport::default(),
+        }
+    }
+
+    pub fn layer(&self) -> ProfileLayer {
+        ProfileLayer { times: Arc::clone(&self.current) }
+    }
+
+    // Devuelve el informe nuevo cuando se completa un intervalo
+    pub fn end_frame(&mut self) -> Option<&ProfileReport> {
+        let frame = std::mem::take(&mut *self.current.lock().unwrap());
+        self.interval.add(&frame);
+        self.frames += 1;
+        if self.started.elapsed() < REPORT_INTERVAL {
+            return None;
+        }
+
+        let interval = std::mem::take(&mut self.interval);
+        let frames = std::mem::replace(&mut self.frames, 0);
+        self.started = Instant::now();
+
+        let mut bodies: Vec<(String, Duration)> = interval.bodies
+            .into_iter()
+            .map(|(body, time)| (body, time / frames))
+            .collect();
+        bodies.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        self.report = ProfileReport {
+            frames,
+            frame: interval.frame / frames,
+            stages: interval.stages.map(|time| time / frames),
+            bodies,
+        };
+        Some(&self.report)
+    }
+
+    // Último informe completo (vacío durante el primer intervalo)
+    pub fn report(&self) -> &ProfileReport {
+        &self.report
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Cuerpo al que pertenece un span "draw"
+struct Body(String);
+
+// Momento en que se entró a un span
+struct Entered(Instant);
+
+struct BodyVisitor(Option<String>);
+
+impl Visit for BodyVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "body" {
+            self.0 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "body" {
+            self.0 = Some(format!("{:?}", value));
+        }
+    }
+}
+
+// Suma el tiempo entre que se entra y se sale de cada span a su etapa o a su cuerpo
+pub struct ProfileLayer {
+    times: Arc<Mutex<Times>>,
+}
+
+impl<S> Layer<S> for ProfileLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attributes: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attributes.metadata().name() != "draw" {
+            return;
+        }
+        let mut visitor = BodyVisitor(None);
+        attributes.record(&mut visitor);
+        if let (Some(body), Some(span)) = (visitor.0, ctx.span(id)) {
+            span.extensions_mut().insert(Body(body));
+        }
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().replace(Entered(Instant::now()));
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let extensions = span.extensions();
+        let Some(Entered(entered)) = extensions.get::<Entered>() else {
+            return;
+        };
+        let elapsed = entered.elapsed();
+
+        let mut times = self.times.lock().unwrap();
+        match span.name() {
+            "frame" => times.frame += elapsed,
+            "draw" => {
+                if let Some(Body(body)) = extensions.get::<Body>() {
+                    match times.bodies.get_mut(body) {
+                        Some(total) => *total += elapsed,
+                        None => {
+                            times.bodies.insert(body.clone(), elapsed);
+                        }
+                    }
+                }
+            }
+            name => {
+                if let Some(stage) = Stage::from_name(name) {
+                    times.stages[stage as usize] += elapsed;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use nalgebra_glm::{Mat4, Vec3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+use std::f32::consts::{PI, TAU};
+use crate::billboard::{draw_billboard, BlendMode, Sprite};
+use crate::color::Color;
+use crate::framebuffer::HdrFramebuffer;
+
+// Protuberancias de una estrella: cuántos arcos hay a la vez, su altura máxima y la
+// separación de sus pies (relativas al radio), cuántos segundos vive cada uno, el
+// tamaño de sus manchas (relativo al radio), la intensidad y el color RGB; sin color
+// se usa el tinte de la estrella
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct ProminenceConfig {
+    pub count: u32,
+    pub height: f32,
+    pub span: f32,
+    pub lifetime: f32,
+    pub size: f32,
+    pub intensity: f32,
+    pub color: Option<[u8; 3]>,
+}
+
+impl Default for ProminenceConfig {
+    fn default() -> Self {
+        ProminenceConfig {
+            count: 6,
+            height: 0.35,
+            span: 0.4,
+            lifetime: 6.0,
+            size: 0.05,
+            intensity: 2.5,
+            color: None,
+        }
+    }
+}
+
+const DEFAULT_COLOR: Color = Color::new(255, 130, 50);
+// Manchas por arco; el plasma corre por ellas desde un pie hasta el otro
+const SAMPLES: usize = 24;
+
+// Cada arco nace en un punto de la superficie, sube hasta su altura y se apaga, y
+// el siguiente del mismo lugar de la lista aparece en otro punto. Todo sale de la
+// semilla y del tiempo de la simulación, así que pausa y reversa quedan consistentes.
+pub struct Prominences {
+    pub config: ProminenceConfig,
+    pub color: Color,
+    seed: u64,
+    // Centro y radio de la estrella en la escena y tiempo de la simulación, del último update
+    center: Vec3,
+    radius: f32,
+    sim_time: f32,
+}
+
+// Un arco en un momento de su vida, sobre la esfera unitaria
+struct Arc {
+    from: Vec3,
+    to: Vec3,
+    height: f32,
+    // De 0 a 1 a lo largo de la vida del arco
+    age: f32,
+}
+
+impl Prominences {
+    pub fn new(config: ProminenceConfig, tint: Option<Color>, seed: u64) -> Self {
+        let color = match (config.color, tint) {
+            (Some([r, g, b]), _) => Color::new(r, g, b),
+            (None, Some(tint)) => tint,
+            (None, None) => DEFAULT_COLOR,
+        };
+        Prominences {
+            config,
+            color,
+            seed,
+            center: Vec3::zeros(),
+            radius: 1.0,
+            sim_time: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, sim_time: f32, center: Vec3, radius: f32) {
+        self.sim_time = sim_time;
+        self.center = center;
+        self.radius = radius;
+    }
+
+    // Los arcos se reparten a lo largo de lifetime para que no nazcan todos juntos
+    fn arc(&self, index: u32) -> Arc {
+        let lifetime = self.config.lifetime.max(0.1);
+        let local = self.sim_time / lifetime + index as f32 / self.config.count as f32;
+        let cycle = local.floor();
+
+        let mut rng = StdRng::seed_from_u64(self.seed ^ ((index as u64) << 32) ^ (cycle as i64 as u64));
+        let from = random_direction(&mut rng);
+        // Pie opuesto: se gira from hacia una dirección tangente al azar
+        let tangent = from.cross(&random_direction(&mut rng)).try_normalize(f32::EPSILON).unwrap_or(from.cross(&Vec3::y()));
+        let angle = self.config.span * rng.gen_range(0.5..1.0);
+        let to = from * angle.cos() + tangent * angle.sin();
+
+        Arc {
+            from,
+            to,
+            height: self.config.height * rng.gen_range(0.5..1.0),
+            age: local - cycle,
+        }
+    }
+
+    // Las manchas suman luz y se prueban contra la profundidad, así la estrella tapa
+    // los arcos que quedan detrás de ella
+    pub fn render(&self, framebuffer: &mut HdrFramebuffer, view_matrix: &Mat4, projection_matrix: &Mat4, viewport_matrix: &Mat4) {
+        let size = self.config.size * self.radius;
+        for index in 0..self.config.count {
+            let arc = self.arc(index);
+            // Sube en el primer 40% de la vida; el brillo aparece y se apaga suave
+            let rise = smoothstep(arc.age / 0.4);
+            let fade = (arc.age * PI).sin();
+            if fade <= 0.0 {
+                continue;
+            }
+
+            for sample in 0..SAMPLES {
+                let s = (sample as f32 + 0.5) / SAMPLES as f32;
+                let direction = (arc.from * (1.0 - s) + arc.to * s).normalize();
+                let elevation = 1.0 + arc.height * rise * (s * PI).sin();
+                let position = self.center + direction * self.radius * elevation;
+
+                // Grumos de plasma que bajan por el arco
+                let flow = 0.6 + 0.4 * (TAU * (s * 3.0 - arc.age * 4.0)).sin();
+                let alpha = (fade * flow * 255.0).clamp(0.0, 255.0) as u8;
+                let sprite = Sprite::disc(self.color.with_alpha(alpha))
+                    .with_intensity(self.config.intensity)
+                    .with_blend(BlendMode::Additive);
+                draw_billboard(framebuffer, position, size, &sprite, view_matrix, projection_matrix, viewport_matrix);
+            }
+        }
+    }
+}
+
+// Dirección uniforme sobre la esfera
+fn random_direction(rng: &mut StdRng) -> Vec3 {
+    let z: f32 = rng.gen_range(-1.0..1.0);
+    let angle: f32 = rng.gen_range(0.0..TAU);
+    let r = (1.0 - z * z).sqrt();
+    Vec3::new(r * angle.cos(), r * angle.sin(), z)
+}
+
+fn smoothstep(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
@@ -0,0 +1,131 @@
+use image::{ImageBuffer, Rgb};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, SyncSender};
+use std::thread::{self, JoinHandle};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{error, info};
+
+// Frames que pueden esperar al hilo que codifica; si se llena, el render espera
+// en lugar de acumular copias del framebuffer en memoria
+const PENDING_FRAMES: usize = 8;
+
+struct FrameJob {
+    path: PathBuf,
+    width: usize,
+    height: usize,
+    pixels: Vec<u32>,
+}
+
+// Graba los frames como PNG numerados. La codificación se hace en un hilo
+// aparte para no frenar el render.
+pub struct Recorder {
+    output_dir: PathBuf,
+    frame_skip: u32,
+    session_dir: Option<PathBuf>,
+    frame_counter: u32,
+    saved_frames: u32,
+    sender: Option<SyncSender<FrameJob>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl Recorder {
+    pub fn new(output_dir: &str, frame_skip: u32) -> Self {
+        Recorder {
+            output_dir: PathBuf::from(output_dir),
+            frame_skip: frame_skip.max(1),
+            session_dir: None,
+            frame_counter: 0,
+            saved_frames: 0,
+            sender: None,
+            writer: None,
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.session_dir.is_some()
+    }
+
+    pub fn toggle(&mut self) {
+        if self.is_recording() {
+            self.stop();
+        } else {
+            self.start();
+        }
+    }
+
+    pub fn start(&mut self) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let session_dir = self.output_dir.join(format!("session_{}", timestamp));
+
+        if let Err(err) = fs::create_dir_all(&session_dir) {
+            error!("No se pudo crear {}: {}", session_dir.display(), err);
+            return;
+        }
+
+        let (sender, receiver) = mpsc::sync_channel::<FrameJob>(PENDING_FRAMES);
+        let writer = thread::spawn(move || {
+            for job in receiver {
+                if let Err(err) = save_png(&job.path, &job.pixels, job.width, job.height) {
+                    error!("No se pudo guardar {}: {}", job.path.display(), err);
+                }
+            }
+        });
+
+        info!("Grabando en {}", session_dir.display());
+        self.session_dir = Some(session_dir);
+        self.frame_counter = 0;
+        self.saved_frames = 0;
+        self.sender = Some(sender);
+        self.writer = Some(writer);
+    }
+
+    // Cierra el canal y espera a que se terminen de escribir los frames pendientes
+    pub fn stop(&mut self) {
+        self.sender = None;
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+        if let Some(session_dir) = self.session_dir.take() {
+            info!("Grabación terminada: {} frames en {}", self.saved_frames, session_dir.display());
+        }
+    }
+
+    pub fn capture(&mut self, pixels: &[u32], width: usize, height: usize) {
+        let (Some(session_dir), Some(sender)) = (&self.session_dir, &self.sender) else {
+            return;
+        };
+
+        self.frame_counter += 1;
+        if !(self.frame_counter - 1).is_multiple_of(self.frame_skip) {
+            return;
+        }
+
+        let job = FrameJob {
+            path: session_dir.join(format!("frame_{:05}.png", self.saved_frames)),
+            width,
+            height,
+            pixels: pixels.to_vec(),
+        };
+        if sender.send(job).is_ok() {
+            self.saved_frames += 1;
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+pub fn save_png(path: &Path, pixels: &[u32], width: usize, height: usize) -> image::ImageResult<()> {
+    let image = ImageBuffer::from_fn(width as u32, height as u32, |x, y| {
+        let pixel = pixels[y as usize * width + x as usize];
+        Rgb([(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8])
+    });
+    image.save(path)
+}
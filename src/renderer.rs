@@ -0,0 +1,1206 @@
+use nalgebra_glm::{Vec2, Vec3, Vec4, Mat4, look_at, perspective};
+use fastnoise_lite::FastNoiseLite;
+use rayon::prelude::*;
+use std::f32::consts::PI;
+use std::str::FromStr;
+use tracing::info_span;
+use crate::belt::AsteroidBelt;
+use crate::billboard::{draw_billboard, Billboard};
+use crate::camera::Camera;
+use crate::clipping::{clip_triangle, Frustum};
+use crate::color::{Color, Gradient};
+use crate::depth::DepthTest;
+use crate::fog::Fog;
+use crate::fragment::Fragment;
+use crate::framebuffer::{Framebuffer, HdrFramebuffer};
+use crate::gbuffer::{GBuffer, LightSample, Surface};
+use crate::grading::ColorGrading;
+use crate::galaxy::Galaxy;
+use crate::lighting::{self, Light, Occluder, PbrMaterial, MAX_LIGHTS};
+use crate::planet::Atmosphere;
+use crate::line;
+use crate::lod;
+use crate::mesh::Mesh;
+use crate::obj::ObjMaterial;
+use crate::postprocess::{self, Bloom, ToneMap};
+use crate::scene::Scene;
+use crate::shader_params::ShaderParams;
+use crate::script::ScriptShader;
+use crate::ssao::Ssao;
+use crate::toon;
+use crate::spectrum::AudioLevels;
+use crate::shaders::{vertex_shader_batch, fragment_shader, ShaderKind, VERTEX_LANES};
+use crate::texture::Texture;
+use crate::tiles::{TileBins, TILE_SIZE};
+use crate::triangle::{triangle, is_backface};
+use crate::vertex::Vertex;
+
+pub struct Uniforms<'a> {
+    pub model_matrix: Mat4,
+    pub view_matrix: Mat4,
+    pub projection_matrix: Mat4,
+    pub viewport_matrix: Mat4,
+    pub camera_position: Vec3,
+    pub lights: &'a [Light],
+    pub occluders: &'a [Occluder],
+    pub sim_time: f32,
+    pub noise: &'a FastNoiseLite,
+    // Ruido cellular de los cráteres (shader "moon"); sin él se usa noise
+    pub craters: Option<&'a FastNoiseLite>,
+    pub texture: Option<&'a Texture>,
+    pub atmosphere: Option<&'a Atmosphere>,
+    pub city_lights: bool,
+    // Color del crepúsculo que tiñe la franja del terminador, en los cuerpos con aire
+    pub twilight: Option<Color>,
+    // Color propio de una estrella, para el shader del Sol
+    pub tint: Option<Color>,
+    // Rampa de colores configurada para el cuerpo, si tiene
+    pub palette: Option<&'a Gradient>,
+    // Niebla de profundidad de la escena
+    pub fog: Option<&'a Fog>,
+    // Shader de script del planeta (ShaderKind::Script)
+    pub script: Option<&'a ScriptShader>,
+    // Constantes ajustables de los shaders integrados
+    pub params: &'a ShaderParams,
+    // Material del modelo OBJ (ShaderKind::Material)
+    pub material: Option<&'a ObjMaterial>,
+    // Metálico-rugoso del cuerpo: con él las superficies que reflejan usan PBR
+    pub pbr: Option<PbrMaterial>,
+    // Niveles de la música que suena, para los shaders que laten con ella
+    pub music: AudioLevels,
+}
+
+// Opciones de visualización que cambian con la interacción del usuario
+pub struct RenderOptions {
+    pub show_orbits: bool,
+    pub selected_planet: Option<usize>,
+    // Intensidad del efecto de salto entre sistemas (0 = sin efecto)
+    pub warp: f32,
+    pub debug_view: DebugView,
+    // Halo sobre las partes más brillantes de la imagen
+    pub bloom: bool,
+    // Reflejos de lente de las estrellas que están en pantalla
+    pub lens_flare: bool,
+    // Curva de tonos y exposición con que el HDR interno pasa a la pantalla
+    pub tone_map: ToneMap,
+    pub exposure: f32,
+    // Corrección de color sobre la imagen final
+    pub grading: ColorGrading,
+    // Tramado al pasar a 8 bits, contra los escalones en los degradados
+    pub dither: bool,
+    // Oclusión ambiental en espacio de pantalla sobre los objetos opacos
+    pub ssao: bool,
+    // Estilo de dibujo animado: luz en escalones y contornos negros
+    pub toon: bool,
+    // Colores, umbrales y escalas de los shaders integrados
+    pub shader_params: ShaderParams,
+    // Análisis de la música de este frame (en cero sin música)
+    pub music: AudioLevels,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            show_orbits: true,
+            selected_planet: None,
+            warp: 0.0,
+            debug_view: DebugView::Off,
+            bloom: true,
+            lens_flare: true,
+            tone_map: ToneMap::Aces,
+            exposure: 1.0,
+            grading: ColorGrading::default(),
+            dither: true,
+            ssao: true,
+            toon: false,
+            shader_params: ShaderParams::default(),
+            music: AudioLevels::default(),
+        }
+    }
+}
+
+// Vistas para depurar shaders y el rasterizador. Fuera de Off solo se dibujan las
+// mallas (sin fondo, colas, órbitas ni halos) y todas como opacas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugView {
+    Off,
+    // Solo las aristas de los triángulos que quedan después del culling
+    Wireframe,
+    // La normal de cada fragmento como color RGB
+    Normals,
+    // Distancia a la cámara en grises, blanco cerca
+    Depth,
+    // Cuántos fragmentos se generan por píxel, tapados o no
+    Overdraw,
+}
+
+impl DebugView {
+    pub fn next(self) -> Self {
+        match self {
+            DebugView::Off => DebugView::Wireframe,
+            DebugView::Wireframe => DebugView::Normals,
+            DebugView::Normals => DebugView::Depth,
+            DebugView::Depth => DebugView::Overdraw,
+            DebugView::Overdraw => DebugView::Off,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DebugView::Off => "NORMAL",
+            DebugView::Wireframe => "ALAMBRE",
+            DebugView::Normals => "NORMALES",
+            DebugView::Depth => "PROFUNDIDAD",
+            DebugView::Overdraw => "SOBREDIBUJO",
+        }
+    }
+}
+
+// Distancia que la vista de profundidad lleva al negro
+const DEBUG_DEPTH_RANGE: f32 = 200.0;
+const WIREFRAME_COLOR: Color = Color::new(159, 224, 255);
+// Intensidad (lineal) de las superficies emisivas: pasa de 1 para que el Sol
+// sature y alimente el bloom
+const EMISSIVE_RADIANCE: f32 = 2.5;
+// Cuánto tiñe el crepúsculo la franja del terminador y cuánto brilla del lado nocturno
+const TWILIGHT_TINT: f32 = 0.4;
+const TWILIGHT_GLOW: f32 = 0.04;
+// Nombres con que el perfilador cuenta los asteroides y la nave
+pub(crate) const BELT_BODY: &str = "Cinturón";
+pub(crate) const SHIP_BODY: &str = "Nave";
+
+// Suavizado de bordes. Supersample dibuja a doble resolución en cada eje (cuatro
+// muestras por píxel, promediadas al resolver) y Fxaa filtra la imagen ya resuelta.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Antialias {
+    None,
+    Supersample,
+    Fxaa,
+}
+
+impl Antialias {
+    // Factor por el que se multiplica render_scale en el framebuffer interno
+    fn scale(self) -> f32 {
+        match self {
+            Antialias::Supersample => 2.0,
+            Antialias::None | Antialias::Fxaa => 1.0,
+        }
+    }
+}
+
+impl FromStr for Antialias {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "none" => Ok(Antialias::None),
+            "ssaa" => Ok(Antialias::Supersample),
+            "fxaa" => Ok(Antialias::Fxaa),
+            _ => Err(format!("antialiasing desconocido '{}' (se espera none, ssaa o fxaa)", name)),
+        }
+    }
+}
+
+// Rasterizador por software: dibuja una escena completa en un framebuffer
+// interno HDR de tamaño (salida * render_scale) y lo resuelve al de salida.
+// Los objetos opacos se dibujan en diferido: primero su superficie en el G-buffer,
+// después la iluminación y el shader una vez por píxel visible.
+pub struct Renderer {
+    pub framebuffer: HdrFramebuffer,
+    pub gbuffer: GBuffer,
+    pub output: Framebuffer,
+    render_scale: f32,
+    antialias: Antialias,
+    context: RenderContext,
+    bloom: Bloom,
+    ssao: Ssao,
+}
+
+// Memoria de trabajo de cada dibujo; se vacía y se reutiliza en lugar de pedir
+// vectores nuevos para cada objeto en cada frame
+struct RenderContext {
+    vertices: Vec<Vertex>,
+    triangles: Vec<[Vertex; 3]>,
+    bins: TileBins,
+}
+
+impl Renderer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Renderer::with_scale(width, height, 1.0)
+    }
+
+    // render_scale > 1 hace supersampling, < 1 reduce la resolución interna
+    pub fn with_scale(width: usize, height: usize, render_scale: f32) -> Self {
+        Renderer::with_antialias(width, height, render_scale, Antialias::None)
+    }
+
+    // Con Supersample el framebuffer interno es el doble de render_scale en cada eje
+    pub fn with_antialias(width: usize, height: usize, render_scale: f32, antialias: Antialias) -> Self {
+        let render_scale = render_scale * antialias.scale();
+        let (internal_width, internal_height) = scaled_size(width, height, render_scale);
+        let framebuffer = HdrFramebuffer::new(internal_width, internal_height);
+        let gbuffer = GBuffer::new(internal_width, internal_height);
+        let mut output = Framebuffer::new(width, height);
+        output.set_background_color(0x000);
+
+        let context = RenderContext {
+            vertices: Vec::new(),
+            triangles: Vec::new(),
+            bins: TileBins::new(internal_width, internal_height),
+        };
+
+        Renderer {
+            framebuffer,
+            gbuffer,
+            output,
+            render_scale,
+            antialias,
+            context,
+            bloom: Bloom::new(),
+            ssao: Ssao::new(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.output.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.output.height
+    }
+
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let (internal_width, internal_height) = scaled_size(width, height, self.render_scale);
+        self.framebuffer.resize(internal_width, internal_height);
+        self.gbuffer.resize(internal_width, internal_height);
+        self.output.resize(width, height);
+    }
+
+    pub fn pixels(&self) -> &[u32] {
+        &self.output.buffer
+    }
+
+    pub fn render(&mut self, scene: &Scene, camera: &Camera, sim_time: f32, options: &RenderOptions) {
+        // Spans para el perfilador: el frame, cada etapa y cada cuerpo (ver profiler)
+        let _frame = info_span!("frame").entered();
+        let framebuffer = &mut self.framebuffer;
+        let planets = &scene.planets;
+
+        framebuffer.clear();
+
+        // Matrices de vista y proyección
+        let view_matrix = create_view_matrix(camera.eye, camera.center, camera.up);
+        let projection_matrix = create_perspective_matrix(framebuffer.width as f32, framebuffer.height as f32);
+        let viewport_matrix = create_viewport_matrix(
+            framebuffer.width as f32,
+            framebuffer.height as f32,
+        );
+
+        let debug_view = options.debug_view;
+
+        // Fondo: nebulosa y estrellas
+        if debug_view == DebugView::Off {
+            let _span = info_span!("background").entered();
+            if let Some(nebula) = &scene.nebula {
+                nebula.render(framebuffer, &view_matrix, &projection_matrix);
+            }
+            scene.starfield.render(framebuffer, &view_matrix, &projection_matrix, &viewport_matrix);
+        }
+
+        // Cada objeto emisivo (las estrellas) es una fuente de luz de su color, igual
+        // que los cuerpos que brillan; después van las luces de la escena. Sin ninguna
+        // la luz queda en el origen.
+        let stars = planets
+            .iter()
+            .filter(|planet| planet.emissive)
+            .map(|planet| Light { color: planet.tint.unwrap_or(Color::new(255, 255, 255)), ..Light::new(planet.translation) });
+        let glowing = planets
+            .iter()
+            .filter_map(|planet| planet.light.map(|light| Light { position: planet.translation, ..light }));
+        let mut lights: Vec<Light> = stars
+            .chain(glowing)
+            .chain(scene.lights.iter().copied())
+            .take(MAX_LIGHTS)
+            .collect();
+        if lights.is_empty() {
+            lights.push(Light::new(Vec3::new(0.0, 0.0, 0.0)));
+        }
+
+        let occluders: Vec<Occluder> = planets
+            .iter()
+            .filter(|planet| !planet.emissive)
+            .map(|planet| Occluder { center: planet.translation, radius: planet.scale })
+            .collect();
+
+        // Los cuerpos que quedan enteros fuera de la vista no llegan al vertex shader
+        let frustum = Frustum::new(&(projection_matrix * view_matrix));
+
+        let mut draw_calls = Vec::new();
+        for (index, planet) in planets.iter().enumerate() {
+            if !frustum.intersects_sphere(&planet.translation, planet.bounding_radius()) {
+                continue;
+            }
+
+            let model_matrix = create_model_matrix(
+                planet.translation,
+                planet.scale,
+                planet.rotation,
+            );
+
+            let uniforms = Uniforms {
+                model_matrix,
+                view_matrix,
+                projection_matrix,
+                viewport_matrix,
+                camera_position: camera.eye,
+                lights: &lights,
+                occluders: &occluders,
+                sim_time,
+                noise: &planet.noise,
+                craters: Some(&planet.craters),
+                texture: planet.texture.as_ref(),
+                atmosphere: planet.atmosphere.as_ref(),
+                city_lights: planet.city_lights,
+                twilight: planet.twilight,
+                tint: planet.tint,
+                palette: planet.palette.as_ref(),
+                fog: scene.fog.as_ref(),
+                script: planet.script.as_ref(),
+                params: &options.shader_params,
+                material: None,
+                pbr: planet.pbr,
+                music: options.music,
+            };
+
+            let highlighted = options.selected_planet == Some(index);
+            let camera_distance = (planet.translation - camera.eye).magnitude();
+            // Nivel de detalle según el tamaño en pantalla de cada capa
+            let screen_radius = |radius: f32| {
+                lod::projected_radius(radius, camera_distance, &projection_matrix, framebuffer.height as f32)
+            };
+
+            // Un cuerpo opaco de pocos píxeles se dibuja como impostor, sin sus nubes
+            // ni atmósfera (los anillos, más grandes, siguen siendo malla)
+            let impostor = (debug_view == DebugView::Off
+                && !planet.transparent
+                && screen_radius(planet.scale) < lod::IMPOSTOR_PIXELS)
+                .then(|| Impostor { center: planet.translation, radius: planet.scale * planet.mesh.radius() });
+
+            // Los anillos son un objeto hijo del planeta: siguen la inclinación de su eje
+            // más la propia, pero no giran con él
+            if let Some(rings) = &planet.rings {
+                let ring_uniforms = Uniforms {
+                    model_matrix: create_model_matrix(
+                        planet.translation,
+                        planet.scale,
+                        Vec3::new(rings.tilt.to_radians(), 0.0, planet.rotation.z),
+                    ),
+                    twilight: None,
+                    ..uniforms
+                };
+
+                draw_calls.push(DrawCall {
+                    body: &planet.name,
+                    uniforms: ring_uniforms,
+                    mesh: &rings.mesh,
+                    shader: ShaderKind::Ring,
+                    double_sided: true,
+                    transparent: true,
+                    opacity: 1.0,
+                    emissive: false,
+                    highlighted,
+                    camera_distance,
+                    impostor: None,
+                });
+            }
+
+            // Las nubes giran alrededor del mismo eje que el planeta, a otra velocidad
+            if let (Some(clouds), None) = (&planet.clouds, &impostor) {
+                let cloud_uniforms = Uniforms {
+                    model_matrix: create_model_matrix(
+                        planet.translation,
+                        planet.scale * clouds.height,
+                        Vec3::new(planet.rotation.x, clouds.rotation, planet.rotation.z),
+                    ),
+                    ..uniforms
+                };
+
+                draw_calls.push(DrawCall {
+                    body: &planet.name,
+                    uniforms: cloud_uniforms,
+                    mesh: clouds.mesh.select(screen_radius(planet.scale * clouds.height)),
+                    shader: ShaderKind::CloudLayer,
+                    double_sided: false,
+                    transparent: true,
+                    opacity: 1.0,
+                    emissive: false,
+                    highlighted,
+                    camera_distance,
+                    impostor: None,
+                });
+            }
+
+            // Atmósfera: cáscara transparente con brillo de Fresnel en el borde
+            if let (Some(atmosphere), None) = (&planet.atmosphere, &impostor) {
+                let atmosphere_uniforms = Uniforms {
+                    model_matrix: create_model_matrix(
+                        planet.translation,
+                        planet.scale * (1.0 + atmosphere.thickness),
+                        planet.rotation,
+                    ),
+                    // La cáscara ya tiene su propia dispersión cerca del terminador
+                    twilight: None,
+                    ..uniforms
+                };
+
+                draw_calls.push(DrawCall {
+                    body: &planet.name,
+                    uniforms: atmosphere_uniforms,
+                    mesh: atmosphere.mesh.select(screen_radius(planet.scale * (1.0 + atmosphere.thickness))),
+                    shader: ShaderKind::Atmosphere,
+                    double_sided: false,
+                    transparent: true,
+                    opacity: 1.0,
+                    emissive: false,
+                    highlighted: false,
+                    camera_distance,
+                    impostor: None,
+                });
+            }
+
+            draw_calls.push(DrawCall {
+                body: &planet.name,
+                uniforms,
+                mesh: planet.mesh.select(screen_radius(planet.scale)),
+                shader: planet.shader,
+                double_sided: planet.double_sided,
+                transparent: planet.transparent,
+                opacity: planet.opacity,
+                emissive: planet.emissive,
+                highlighted,
+                camera_distance,
+                impostor,
+            });
+        }
+
+        if let Some(belt) = &scene.belt {
+            let belt_uniforms = Uniforms {
+                model_matrix: Mat4::identity(),
+                view_matrix,
+                projection_matrix,
+                viewport_matrix,
+                camera_position: camera.eye,
+                lights: &lights,
+                occluders: &occluders,
+                sim_time,
+                noise: &belt.noise,
+                craters: Some(&belt.noise),
+                texture: None,
+                atmosphere: None,
+                city_lights: false,
+                twilight: None,
+                tint: None,
+                palette: None,
+                fog: scene.fog.as_ref(),
+                script: None,
+                params: &options.shader_params,
+                material: None,
+                pbr: None,
+                music: options.music,
+            };
+            push_asteroids(&mut draw_calls, belt, &belt_uniforms, &frustum, framebuffer.height as f32);
+        }
+
+        // La nave del modo piloto: una llamada por material del modelo; las partes
+        // sin material usan el shader metálico
+        if let Some(ship) = &scene.ship {
+            for group in &ship.model.groups {
+                let material = ship.model.material(group);
+                draw_calls.push(DrawCall {
+                    body: SHIP_BODY,
+                    uniforms: Uniforms {
+                        model_matrix: create_model_matrix(ship.position, ship.scale, ship.rotation()),
+                        view_matrix,
+                        projection_matrix,
+                        viewport_matrix,
+                        camera_position: camera.eye,
+                        lights: &lights,
+                        occluders: &occluders,
+                        sim_time,
+                        noise: &ship.noise,
+                        craters: None,
+                        texture: None,
+                        atmosphere: None,
+                        city_lights: false,
+                        twilight: None,
+                        tint: None,
+                        palette: None,
+                        fog: scene.fog.as_ref(),
+                        script: None,
+                        params: &options.shader_params,
+                        material,
+                        pbr: ship.pbr,
+                        music: options.music,
+                    },
+                    mesh: &group.mesh,
+                    shader: if material.is_some() { ShaderKind::Material } else { ShaderKind::Metal },
+                    double_sided: false,
+                    transparent: false,
+                    opacity: 1.0,
+                    emissive: false,
+                    highlighted: false,
+                    camera_distance: (ship.position - camera.eye).magnitude(),
+                    impostor: None,
+                });
+            }
+        }
+
+        // Primero los objetos opacos y luego los transparentes, de atrás hacia adelante
+        // (en las vistas de depuración todo se dibuja como opaco)
+        let (mut transparent, opaque): (Vec<_>, Vec<_>) = draw_calls
+            .into_iter()
+            .partition(|draw| draw.transparent && debug_view == DebugView::Off);
+        transparent.sort_by(|a, b| b.camera_distance.total_cmp(&a.camera_distance));
+
+        framebuffer.depth.set_test(DepthTest::Less);
+        framebuffer.depth.set_write(true);
+        if debug_view != DebugView::Off {
+            for draw in opaque.iter() {
+                let _span = info_span!("draw", body = draw.body).entered();
+                render_draw_call(framebuffer, &mut self.context, draw, debug_view, false);
+            }
+
+            let _span = info_span!("post").entered();
+            if debug_view == DebugView::Overdraw {
+                postprocess::overdraw_heatmap(framebuffer);
+            }
+            postprocess::resolve(&self.framebuffer, &mut self.output, ToneMap::None, 1.0, false, None);
+            return;
+        }
+
+        // Render diferido de los opacos: geometría, iluminación y sombreado por separado
+        self.gbuffer.clear();
+        for (index, draw) in opaque.iter().enumerate() {
+            let _span = info_span!("draw", body = draw.body).entered();
+            render_geometry(framebuffer, &mut self.gbuffer, &mut self.context, draw, index as u32);
+        }
+        {
+            let _span = info_span!("lighting").entered();
+            let occlusion = options.ssao.then(|| {
+                let pixels_per_unit = projection_matrix[(1, 1)] * framebuffer.height as f32 / 2.0;
+                self.ssao.compute(&self.gbuffer, &camera.eye, pixels_per_unit)
+            });
+            light_gbuffer(&mut self.gbuffer, &opaque, occlusion, options.toon);
+        }
+        {
+            let _span = info_span!("fragment").entered();
+            shade_gbuffer(framebuffer, &self.gbuffer, &opaque);
+        }
+
+        // Los transparentes y las líneas se prueban contra la profundidad pero no la
+        // escriben, para no tapar lo que queda detrás de ellos
+        framebuffer.depth.set_write(false);
+        for draw in transparent.iter() {
+            let _span = info_span!("draw", body = draw.body).entered();
+            render_draw_call(framebuffer, &mut self.context, draw, debug_view, options.toon);
+        }
+
+        let _post = info_span!("post").entered();
+        // Los contornos van encima de nubes y atmósferas, que no están en el G-buffer
+        if options.toon {
+            toon::outlines(framebuffer, &self.gbuffer, &camera.eye);
+        }
+
+        // Colas de los cometas, protuberancias de las estrellas y partículas de la nave,
+        // con la misma regla de profundidad que los transparentes
+        for tail in planets.iter().filter_map(|planet| planet.tail.as_ref()) {
+            tail.render(framebuffer, &view_matrix, &projection_matrix, &viewport_matrix);
+        }
+        for prominences in planets.iter().filter_map(|planet| planet.prominences.as_ref()) {
+            prominences.render(framebuffer, &view_matrix, &projection_matrix, &viewport_matrix);
+        }
+        if let Some(ship) = &scene.ship {
+            ship.exhaust.render(framebuffer, &view_matrix, &projection_matrix, &viewport_matrix);
+            ship.debris.render(framebuffer, &view_matrix, &projection_matrix, &viewport_matrix);
+        }
+
+        // Billboards de la escena, de atrás hacia adelante
+        let mut billboards: Vec<&Billboard> = scene.billboards.iter().collect();
+        billboards.sort_by(|a, b| {
+            (b.position - camera.eye).magnitude().total_cmp(&(a.position - camera.eye).magnitude())
+        });
+        for billboard in billboards {
+            draw_billboard(
+                framebuffer,
+                billboard.position,
+                billboard.size,
+                &billboard.sprite(),
+                &view_matrix,
+                &projection_matrix,
+                &viewport_matrix,
+            );
+        }
+
+        // Trayectorias de las órbitas
+        if options.show_orbits && scene.gravity.is_none() {
+            let view_projection = projection_matrix * view_matrix;
+            let orbit_color = Color::new_rgba(120, 160, 255, 70);
+
+            for path in scene.orbit_paths.iter() {
+                for segment in path.windows(2) {
+                    line::draw_line_3d(
+                        framebuffer,
+                        segment[0],
+                        segment[1],
+                        &view_projection,
+                        &viewport_matrix,
+                        orbit_color,
+                    );
+                }
+            }
+        }
+
+        framebuffer.depth.set_write(true);
+
+        // Halo de los objetos emisivos, del color de cada estrella
+        let camera_right = (camera.center - camera.eye).cross(&camera.up).normalize();
+        for planet in planets.iter().filter(|planet| planet.emissive) {
+            postprocess::emissive_glow(
+                framebuffer,
+                planet.translation,
+                planet.scale,
+                camera_right,
+                &(projection_matrix * view_matrix),
+                &viewport_matrix,
+                planet.tint.unwrap_or(Color::new(255, 160, 50)),
+            );
+        }
+
+        if options.bloom {
+            self.bloom.apply(framebuffer);
+        }
+        // Después del bloom, para que los reflejos queden nítidos como los de una lente
+        if options.lens_flare {
+            for planet in planets.iter().filter(|planet| planet.emissive) {
+                postprocess::lens_flare(
+                    framebuffer,
+                    planet.translation,
+                    planet.scale * planet.mesh.radius(),
+                    camera.eye,
+                    &(projection_matrix * view_matrix),
+                    &viewport_matrix,
+                    planet.tint.unwrap_or(Color::new(255, 160, 50)),
+                );
+            }
+        }
+        postprocess::warp_streaks(framebuffer, options.warp);
+        // La corrección de color va sobre la imagen antes de cuantizarla y el FXAA al
+        // final, sobre los bordes tal como se ven
+        postprocess::resolve(
+            &self.framebuffer,
+            &mut self.output,
+            options.tone_map,
+            options.exposure,
+            options.dither,
+            Some(&options.grading),
+        );
+        if self.antialias == Antialias::Fxaa {
+            postprocess::fxaa(&mut self.output);
+        }
+    }
+
+    // Mapa de la galaxia: los sistemas sobre el polvo de los brazos y sus nombres
+    pub fn render_map(&mut self, galaxy: &Galaxy, camera: &Camera, selected: usize, current: Option<usize>) {
+        let framebuffer = &mut self.framebuffer;
+        framebuffer.clear();
+
+        let view_matrix = create_view_matrix(camera.eye, camera.center, camera.up);
+        let projection_matrix = create_perspective_matrix(framebuffer.width as f32, framebuffer.height as f32);
+        let viewport_matrix = create_viewport_matrix(framebuffer.width as f32, framebuffer.height as f32);
+
+        framebuffer.depth.set_write(false);
+        galaxy.render(framebuffer, &view_matrix, &projection_matrix, &viewport_matrix, selected);
+        framebuffer.depth.set_write(true);
+
+        // Los puntos del mapa no pasan de 1: se muestran tal cual, sin curva de tonos
+        postprocess::resolve(&self.framebuffer, &mut self.output, ToneMap::None, 1.0, false, None);
+
+        let output_viewport = create_viewport_matrix(self.output.width as f32, self.output.height as f32);
+        galaxy.draw_labels(&mut self.output, &(projection_matrix * view_matrix), &output_viewport, current);
+    }
+}
+
+fn scaled_size(width: usize, height: usize, render_scale: f32) -> (usize, usize) {
+    (
+        ((width as f32 * render_scale).round() as usize).max(1),
+        ((height as f32 * render_scale).round() as usize).max(1),
+    )
+}
+
+pub fn create_model_matrix(translation: Vec3, scale: f32, rotation: Vec3) -> Mat4 {
+    let (sin_x, cos_x) = rotation.x.sin_cos();
+    let (sin_y, cos_y) = rotation.y.sin_cos();
+    let (sin_z, cos_z) = rotation.z.sin_cos();
+
+    let rotation_matrix_x = Mat4::new(
+        1.0,  0.0,    0.0,   0.0,
+        0.0,  cos_x, -sin_x, 0.0,
+        0.0,  sin_x,  cos_x, 0.0,
+        0.0,  0.0,    0.0,   1.0,
+    );
+
+    let rotation_matrix_y = Mat4::new(
+        cos_y,  0.0,  sin_y, 0.0,
+        0.0,    1.0,  0.0,   0.0,
+        -sin_y, 0.0,  cos_y, 0.0,
+        0.0,    0.0,  0.0,   1.0,
+    );
+
+    let rotation_matrix_z = Mat4::new(
+        cos_z, -sin_z, 0.0, 0.0,
+        sin_z,  cos_z, 0.0, 0.0,
+        0.0,    0.0,  1.0, 0.0,
+        0.0,    0.0,  0.0, 1.0,
+    );
+
+    let rotation_matrix = rotation_matrix_z * rotation_matrix_y * rotation_matrix_x;
+
+    let transform_matrix = Mat4::new(
+        scale, 0.0,   0.0,   translation.x,
+        0.0,   scale, 0.0,   translation.y,
+        0.0,   0.0,   scale, translation.z,
+        0.0,   0.0,   0.0,   1.0,
+    );
+
+    transform_matrix * rotation_matrix
+}
+
+pub fn create_view_matrix(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
+    look_at(&eye, &center, &up)
+}
+
+pub fn create_perspective_matrix(window_width: f32, window_height: f32) -> Mat4 {
+    let fov = 45.0 * PI / 180.0;
+    let aspect_ratio = window_width / window_height;
+    let near = 0.1;
+    let far = 1000.0;
+
+    perspective(aspect_ratio, fov, near, far)
+}
+
+pub fn create_viewport_matrix(width: f32, height: f32) -> Mat4 {
+    Mat4::new(
+        width / 2.0, 0.0, 0.0, width / 2.0,
+        0.0, -height / 2.0, 0.0, height / 2.0,
+        0.0, 0.0, 1.0, 0.0,
+        0.0, 0.0, 0.0, 1.0
+    )
+}
+
+// Las rocas con un radio aparente menor que esto (en píxeles) no se dibujan
+const MIN_ASTEROID_PIXELS: f32 = 0.75;
+
+// Agrega una llamada por asteroide visible; todas comparten las mallas del cinturón.
+// Se descartan las que quedan detrás de la cámara, fuera de la pantalla o son
+// demasiado pequeñas para verse.
+fn push_asteroids<'a>(
+    draw_calls: &mut Vec<DrawCall<'a>>,
+    belt: &'a AsteroidBelt,
+    uniforms: &Uniforms<'a>,
+    frustum: &Frustum,
+    screen_height: f32,
+) {
+    let projection_matrix = &uniforms.projection_matrix;
+    let view_projection = projection_matrix * uniforms.view_matrix;
+    // Escala de un objeto a distancia 1 en píxeles
+    let pixels_per_unit = projection_matrix[(1, 1)] * screen_height / 2.0;
+
+    for asteroid in belt.asteroids.iter() {
+        let clip = view_projection * Vec4::new(asteroid.translation.x, asteroid.translation.y, asteroid.translation.z, 1.0);
+        if clip.w <= 0.0 {
+            continue;
+        }
+
+        let projected_radius = asteroid.scale * pixels_per_unit / clip.w;
+        if projected_radius < MIN_ASTEROID_PIXELS {
+            continue;
+        }
+
+        if !frustum.intersects_sphere(&asteroid.translation, asteroid.scale * belt.shapes[asteroid.shape].radius) {
+            continue;
+        }
+
+        draw_calls.push(DrawCall {
+            body: BELT_BODY,
+            uniforms: Uniforms {
+                model_matrix: create_model_matrix(asteroid.translation, asteroid.scale, asteroid.rotation),
+                ..*uniforms
+            },
+            mesh: &belt.shapes[asteroid.shape].mesh,
+            shader: ShaderKind::Moon,
+            double_sided: false,
+            transparent: false,
+            opacity: 1.0,
+            emissive: false,
+            highlighted: false,
+            camera_distance: (asteroid.translation - uniforms.camera_position).magnitude(),
+            impostor: None,
+        });
+    }
+}
+
+// Vértices por tarea del vertex shader en paralelo; múltiplo de VERTEX_LANES
+const VERTEX_BATCH: usize = VERTEX_LANES * 64;
+
+// Todo lo necesario para dibujar un objeto en un frame
+struct DrawCall<'a> {
+    // Cuerpo al que pertenece, para los tiempos del perfilador
+    body: &'a str,
+    uniforms: Uniforms<'a>,
+    mesh: &'a Mesh,
+    shader: ShaderKind,
+    double_sided: bool,
+    transparent: bool,
+    opacity: f32,
+    emissive: bool,
+    highlighted: bool,
+    camera_distance: f32,
+    // Con Some el objeto no se rasteriza: se dibuja como una esfera vista por rayos
+    impostor: Option<Impostor>,
+}
+
+// Esfera que reemplaza a la malla de un cuerpo lejano, en coordenadas del mundo
+#[derive(Debug, Clone, Copy)]
+struct Impostor {
+    center: Vec3,
+    radius: f32,
+}
+
+// Vertex shader, ensamblado, recorte y backface culling: deja en context.triangles
+// los triángulos en pantalla que quedan por rasterizar
+fn assemble_triangles(context: &mut RenderContext, draw: &DrawCall) {
+    let _span = info_span!("vertex").entered();
+    let uniforms = &draw.uniforms;
+
+    // Vertex Shader (en paralelo por lotes, con SIMD dentro de cada lote), una vez
+    // por vértice aunque lo compartan varios triángulos
+    let transformed_vertices = &mut context.vertices;
+    transformed_vertices.resize(draw.mesh.vertices.len(), Vertex::default());
+    transformed_vertices
+        .par_chunks_mut(VERTEX_BATCH)
+        .zip(draw.mesh.vertices.par_chunks(VERTEX_BATCH))
+        .for_each(|(output, vertices)| vertex_shader_batch(vertices, uniforms, output));
+
+    // Primitive Assembly a partir de los índices (con recorte contra el frustum)
+    let triangles = &mut context.triangles;
+    triangles.clear();
+    for tri in draw.mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|corner| &transformed_vertices[tri[corner] as usize]);
+        clip_triangle(a, b, c, &uniforms.viewport_matrix, triangles);
+    }
+
+    // Backface culling (se desactiva para geometría de doble cara)
+    if !draw.double_sided {
+        triangles.retain(|tri| !is_backface(&tri[0], &tri[1], &tri[2]));
+    }
+}
+
+// Iluminación de un punto de la superficie: el aporte de todas las luces con sus
+// sombras. Los objetos emisivos (como el Sol) no dependen de la luz.
+fn light_sample(draw: &DrawCall, position: &Vec3, normal: &Vec3) -> LightSample {
+    let uniforms = &draw.uniforms;
+    let light_dir = lighting::dominant_light(uniforms.lights, position)
+        .map(|light| light.direction_from(position))
+        .unwrap_or(Vec3::new(0.0, 0.0, 0.0));
+    if draw.emissive {
+        return LightSample { light_dir, ..LightSample::default() };
+    }
+
+    // En geometría de doble cara se ilumina la cara visible
+    let mut normal = *normal;
+    if draw.double_sided && normal.dot(&(uniforms.camera_position - position)) < 0.0 {
+        normal = -normal;
+    }
+
+    // Sombras: otros planetas (o el propio) entre el punto y cada estrella
+    let visibility = lighting::visibilities(uniforms.lights, position, uniforms.occluders);
+    let intensity = lighting::diffuse_lighting(uniforms.lights, position, &normal, &visibility);
+    let color = lighting::light_color(uniforms.lights, position, &normal, &visibility);
+    LightSample { intensity, visibility, light_dir, color }
+}
+
+// Color de un fragmento ya iluminado: el shader, el resaltado de selección, el brillo
+// de los emisivos y la niebla. Devuelve el color lineal y el del shader (por su alfa).
+fn shade_fragment(fragment: &mut Fragment, draw: &DrawCall, light: &LightSample) -> (Vec3, Color) {
+    let uniforms = &draw.uniforms;
+    fragment.view_dir = (uniforms.camera_position - fragment.world_position).normalize();
+    fragment.light_dir = light.light_dir;
+    fragment.intensity = light.intensity;
+    fragment.light_visibility = light.visibility;
+
+    let mut color = fragment_shader(fragment, uniforms, draw.shader);
+    if draw.highlighted {
+        color = color.lerp(&Color::new(255, 255, 255), 0.3);
+    }
+
+    let mut linear = color.to_linear();
+    if draw.emissive {
+        linear *= EMISSIVE_RADIANCE;
+    } else {
+        linear = linear.component_mul(&light.color);
+    }
+    // Crepúsculo: el borde del lado diurno toma el color del aire y un resplandor
+    // tenue del mismo color entra un poco en la noche
+    if let Some(twilight) = uniforms.twilight {
+        let band = lighting::twilight_band(&fragment.normal, &fragment.light_dir);
+        if band > 0.0 {
+            let twilight = twilight.to_linear();
+            linear = linear.component_mul(&Vec3::repeat(1.0).lerp(&twilight, band * TWILIGHT_TINT)) + twilight * band * TWILIGHT_GLOW;
+        }
+    }
+    if let Some(fog) = uniforms.fog {
+        linear = fog.apply(linear, (uniforms.camera_position - fragment.world_position).magnitude());
+    }
+    (linear, color)
+}
+
+// Paso de geometría del render diferido: solo la prueba de profundidad y la
+// superficie visible de cada píxel, sin ejecutar shaders de fragmentos
+fn render_geometry(framebuffer: &mut HdrFramebuffer, gbuffer: &mut GBuffer, context: &mut RenderContext, draw: &DrawCall, index: u32) {
+    if let Some(impostor) = &draw.impostor {
+        let _span = info_span!("raster").entered();
+        render_impostor(framebuffer, gbuffer, draw, impostor, index);
+        return;
+    }
+
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    assemble_triangles(context, draw);
+
+    let _span = info_span!("raster").entered();
+    let triangles = &context.triangles;
+    let bins = &mut context.bins;
+    bins.reset(width, height);
+    bins.bin(triangles);
+
+    framebuffer.depth
+        .bands_mut(TILE_SIZE)
+        .zip(gbuffer.bands_mut(TILE_SIZE))
+        .enumerate()
+        .for_each(|(row, (mut depth, surfaces))| {
+            for (tile, tile_triangles) in bins.row(row) {
+                for &triangle_index in tile_triangles {
+                    let tri = &triangles[triangle_index as usize];
+                    triangle(&tri[0], &tri[1], &tri[2], &tile, |fragment| {
+                        let x = fragment.position.x as usize;
+                        let y = fragment.position.y as usize;
+                        if depth.test_and_write(x, y, fragment.depth) {
+                            surfaces[(y - depth.y) * width + x] = Some(Surface {
+                                draw: index,
+                                depth: fragment.depth,
+                                normal: fragment.normal,
+                                world_position: fragment.world_position,
+                                model_position: fragment.model_position,
+                                tex_coords: fragment.tex_coords,
+                                color: fragment.color,
+                            });
+                        }
+                    });
+                }
+            }
+        });
+}
+
+// Paso de geometría de un impostor: por cada píxel que cubre la esfera se lanza un
+// rayo desde la cámara y el punto donde la toca se guarda en el G-buffer como si
+// viniera de la malla (con su normal, posición en el modelo y coordenadas de la
+// esfera UV), así recibe la misma luz, sombras y shader. Si la esfera no alcanza
+// ningún centro de píxel se marca igual el píxel de su centro, con el punto que mira
+// a la cámara, para que el cuerpo no desaparezca a lo lejos.
+fn render_impostor(framebuffer: &mut HdrFramebuffer, gbuffer: &mut GBuffer, draw: &DrawCall, impostor: &Impostor, index: u32) {
+    let uniforms = &draw.uniforms;
+    let eye = uniforms.camera_position;
+    let screen_matrix = uniforms.viewport_matrix * uniforms.projection_matrix * uniforms.view_matrix;
+    let (Some(inverse_screen), Some(inverse_model)) = (screen_matrix.try_inverse(), uniforms.model_matrix.try_inverse()) else {
+        return;
+    };
+    let to_screen = |point: Vec3| {
+        let screen = screen_matrix * Vec4::new(point.x, point.y, point.z, 1.0);
+        (screen.w > 0.0).then(|| screen.xyz() / screen.w)
+    };
+    let Some(center) = to_screen(impostor.center) else {
+        return;
+    };
+
+    let mut write_surface = |x: usize, y: usize, point: Vec3| {
+        let Some(screen) = to_screen(point) else {
+            return;
+        };
+        if !framebuffer.depth.test_and_write(x, y, screen.z) {
+            return;
+        }
+
+        let model_position = (inverse_model * Vec4::new(point.x, point.y, point.z, 1.0)).xyz();
+        let local = model_position.normalize();
+        let u = ((-local.z).atan2(local.x) / (2.0 * PI)).rem_euclid(1.0);
+        let v = local.y.clamp(-1.0, 1.0).acos() / PI;
+        gbuffer.surfaces[y * gbuffer.width + x] = Some(Surface {
+            draw: index,
+            depth: screen.z,
+            normal: (point - impostor.center) / impostor.radius,
+            world_position: point,
+            model_position,
+            tex_coords: Vec2::new(u, v),
+            color: Color::black(),
+        });
+    };
+
+    let radius = lod::projected_radius(impostor.radius, (impostor.center - eye).magnitude(), &uniforms.projection_matrix, framebuffer.height as f32);
+    let min_x = (center.x - radius - 1.0).floor().max(0.0) as usize;
+    let min_y = (center.y - radius - 1.0).floor().max(0.0) as usize;
+    let max_x = ((center.x + radius + 1.0).ceil().max(0.0) as usize).min(framebuffer.width);
+    let max_y = ((center.y + radius + 1.0).ceil().max(0.0) as usize).min(framebuffer.height);
+
+    let mut covered = false;
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let target = inverse_screen * Vec4::new(x as f32 + 0.5, y as f32 + 0.5, center.z, 1.0);
+            let direction = (target.xyz() / target.w - eye).normalize();
+
+            // Intersección más cercana del rayo con la esfera
+            let offset = eye - impostor.center;
+            let b = offset.dot(&direction);
+            let discriminant = b * b - (offset.magnitude_squared() - impostor.radius * impostor.radius);
+            if discriminant < 0.0 {
+                continue;
+            }
+            let distance = -b - discriminant.sqrt();
+            if distance <= 0.0 {
+                continue;
+            }
+
+            covered = true;
+            write_surface(x, y, eye + direction * distance);
+        }
+    }
+
+    let (x, y) = (center.x.floor(), center.y.floor());
+    if !covered && x >= 0.0 && y >= 0.0 && (x as usize) < framebuffer.width && (y as usize) < framebuffer.height {
+        let facing = (eye - impostor.center).normalize();
+        write_surface(x as usize, y as usize, impostor.center + facing * impostor.radius);
+    }
+}
+
+// Paso de iluminación: recorre las luces una vez por píxel visible. La oclusión
+// ambiental, si la hay, apaga la luz que llega a los objetos no emisivos, y en el
+// modo de dibujo animado la luz resultante se lleva a escalones.
+fn light_gbuffer(gbuffer: &mut GBuffer, draws: &[DrawCall], occlusion: Option<&[f32]>, toon: bool) {
+    gbuffer.lighting
+        .par_iter_mut()
+        .zip(gbuffer.surfaces.par_iter())
+        .enumerate()
+        .for_each(|(index, (light, surface))| {
+            if let Some(surface) = surface {
+                let draw = &draws[surface.draw as usize];
+                *light = light_sample(draw, &surface.world_position, &surface.normal);
+                if let (Some(occlusion), false) = (occlusion, draw.emissive) {
+                    light.intensity *= occlusion[index];
+                }
+                if toon && !draw.emissive {
+                    light.intensity = toon::quantize(light.intensity);
+                }
+            }
+        });
+}
+
+// Paso de sombreado: el shader de cada píxel con la luz que le llegó
+fn shade_gbuffer(framebuffer: &mut HdrFramebuffer, gbuffer: &GBuffer, draws: &[DrawCall]) {
+    let width = gbuffer.width;
+    framebuffer.buffer
+        .par_iter_mut()
+        .enumerate()
+        .zip(gbuffer.surfaces.par_iter().zip(gbuffer.lighting.par_iter()))
+        .for_each(|((index, pixel), (surface, light))| {
+            if let Some(surface) = surface {
+                let mut fragment = surface.fragment(index % width, index / width);
+                *pixel = shade_fragment(&mut fragment, &draws[surface.draw as usize], light).0;
+            }
+        });
+}
+
+// Dibujo directo (forward), para los transparentes y las vistas de depuración
+fn render_draw_call(framebuffer: &mut HdrFramebuffer, context: &mut RenderContext, draw: &DrawCall, debug_view: DebugView, toon: bool) {
+    let uniforms = &draw.uniforms;
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    assemble_triangles(context, draw);
+    let triangles = &context.triangles;
+
+    // La rasterización y el sombreado van juntos: todo cuenta como fragmentos
+    let _span = info_span!("fragment").entered();
+    if debug_view == DebugView::Wireframe {
+        for tri in triangles.iter() {
+            for (a, b) in [(0, 1), (1, 2), (2, 0)] {
+                line::draw_line_screen(framebuffer, tri[a].transformed_position, tri[b].transformed_position, WIREFRAME_COLOR);
+            }
+        }
+        return;
+    }
+
+    // Binning: cada triángulo se anota en los tiles que toca
+    let bins = &mut context.bins;
+    bins.reset(width, height);
+    bins.bin(triangles);
+
+    // Rasterization + Fragment Processing: cada fila de tiles es una franja del
+    // framebuffer que se dibuja en paralelo, escribiendo directo en ella
+    framebuffer.bands_mut(TILE_SIZE).enumerate().for_each(|(row, mut band)| {
+        for (tile, tile_triangles) in bins.row(row) {
+            for &index in tile_triangles {
+                let tri = &triangles[index as usize];
+                triangle(&tri[0], &tri[1], &tri[2], &tile, |mut fragment| {
+                    let x = fragment.position.x as usize;
+                    let y = fragment.position.y as usize;
+
+                    if debug_view == DebugView::Overdraw {
+                        band.count_fragment(x, y);
+                        return;
+                    }
+
+                    // Prueba de profundidad temprana: lo que ya está tapado no se sombrea
+                    if !band.depth.passes(x, y, fragment.depth) {
+                        return;
+                    }
+
+                    match debug_view {
+                        DebugView::Normals => {
+                            let normal = fragment.normal * 0.5 + Vec3::new(0.5, 0.5, 0.5);
+                            let color = Color::new((normal.x * 255.0) as u8, (normal.y * 255.0) as u8, (normal.z * 255.0) as u8);
+                            band.point(x, y, fragment.depth, color.to_linear());
+                            return;
+                        }
+                        DebugView::Depth => {
+                            let distance = (uniforms.camera_position - fragment.world_position).magnitude();
+                            let shade = 1.0 - ((1.0 + distance).ln() / (1.0 + DEBUG_DEPTH_RANGE).ln()).min(1.0);
+                            let gray = (shade * 255.0) as u8;
+                            band.point(x, y, fragment.depth, Color::new(gray, gray, gray).to_linear());
+                            return;
+                        }
+                        _ => {}
+                    }
+
+                    let mut light = light_sample(draw, &fragment.world_position, &fragment.normal);
+                    if toon && !draw.emissive {
+                        light.intensity = toon::quantize(light.intensity);
+                    }
+                    let (linear, color) = shade_fragment(&mut fragment, draw, &light);
+
+                    if draw.transparent {
+                        let alpha = (color.alpha() as f32 / 255.0 * draw.opacity).clamp(0.0, 1.0);
+                        band.blend_point(x, y, fragment.depth, linear, alpha);
+                    } else {
+                        band.point(x, y, fragment.depth, linear);
+                    }
+                });
+            }
+        }
+    });
+}
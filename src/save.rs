@@ -0,0 +1,398 @@
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use std::fs;
+use std::io;
+use crate::assets::AssetManager;
+use crate::camera::Camera;
+use crate::clock::Clock;
+use crate::generator::generate_system;
+use crate::gravity::{Body, NBody};
+use crate::particles::{Particle, ParticleSystem};
+use crate::scene::{Scene, SceneConfig, SceneError};
+
+// De dónde sale la escena: con esto y el tiempo se reconstruye todo lo que no
+// cambia durante la simulación (mallas, ruido, órbitas)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SceneSource {
+    // Archivo de escena
+    File { path: String },
+    // Sistema creado por el generador
+    Generated { seed: u64 },
+}
+
+impl SceneSource {
+    pub fn build(&self, assets: &mut AssetManager) -> Result<Scene, SceneError> {
+        match self {
+            SceneSource::File { path } => Scene::from_config(&SceneConfig::load(path)?, assets),
+            SceneSource::Generated { seed } => Scene::from_config(&generate_system(*seed), assets),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CameraState {
+    pub eye: [f32; 3],
+    pub center: [f32; 3],
+    pub up: [f32; 3],
+}
+
+// Una partícula viva de una cola o de la nave. El generador aleatorio del sistema
+// no se guarda: al retomar, las partículas nuevas salen de otra secuencia.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParticleState {
+    pub position: [f32; 3],
+    pub velocity: [f32; 3],
+    pub age: f32,
+    pub lifetime: f32,
+}
+
+// Estado de un cuerpo; la velocidad solo se usa con la gravedad activada. El nombre
+// confirma que el estado corresponde al mismo cuerpo de la escena.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BodyState {
+    pub name: String,
+    pub position: [f32; 3],
+    pub rotation: [f32; 3],
+    pub velocity: [f32; 3],
+    // Giro acumulado de la capa de nubes, si tiene
+    #[serde(default)]
+    pub cloud_rotation: Option<f32>,
+    // Partículas de la cola de un cometa
+    #[serde(default)]
+    pub tail: Vec<ParticleState>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShipState {
+    pub position: [f32; 3],
+    pub velocity: [f32; 3],
+    pub yaw: f32,
+    pub pitch: f32,
+    #[serde(default)]
+    pub exhaust: Vec<ParticleState>,
+    #[serde(default)]
+    pub debris: Vec<ParticleState>,
+}
+
+// Foto de la simulación en curso, para retomarla más tarde o compartir un sistema
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveState {
+    pub sim_time: f32,
+    pub time_scale: f32,
+    pub paused: bool,
+    pub gravity: bool,
+    pub source: SceneSource,
+    pub camera: CameraState,
+    pub ship: Option<ShipState>,
+    // Cuerpos en el mismo orden que en la escena
+    pub bodies: Vec<BodyState>,
+    // Giro acumulado de cada roca del cinturón (la posición sale de su órbita)
+    #[serde(default)]
+    pub asteroid_rotations: Vec<[f32; 3]>,
+}
+
+#[derive(Debug, Error)]
+pub enum SaveError {
+    #[error("no se pudo acceder a la partida guardada '{0}': {1}")]
+    Io(String, #[source] io::Error),
+    #[error("partida guardada inválida '{0}': {1}")]
+    Parse(String, #[source] toml::de::Error),
+    #[error("no se pudo serializar el estado: {0}")]
+    Serialize(#[source] toml::ser::Error),
+    #[error(transparent)]
+    Scene(Box<SceneError>),
+    // El estado no corresponde a la escena (p. ej. se editó el archivo de escena)
+    #[error("el estado guardado tiene {0} cuerpos pero la escena tiene {1}")]
+    Mismatch(usize, usize),
+    // Posición, nombre guardado y nombre en la escena
+    #[error("el cuerpo guardado {0} es '{1}' pero en la escena es '{2}'")]
+    BodyMismatch(usize, String, String),
+    // Rocas guardadas y rocas del cinturón de la escena
+    #[error("el estado guardado tiene {0} rocas en el cinturón pero la escena tiene {1}")]
+    BeltMismatch(usize, usize),
+}
+
+impl From<SceneError> for SaveError {
+    fn from(err: SceneError) -> Self {
+        SaveError::Scene(Box::new(err))
+    }
+}
+
+impl SaveState {
+    pub fn capture(source: &SceneSource, scene: &Scene, camera: &Camera, clock: &Clock) -> Self {
+        let bodies = scene.planets
+            .iter()
+            .enumerate()
+            .map(|(index, planet)| BodyState {
+                name: planet.name.clone(),
+                position: planet.translation.into(),
+                rotation: planet.rotation.into(),
+                velocity: scene.gravity
+                    .as_ref()
+                    .map(|gravity| gravity.bodies[index].velocity)
+                    .unwrap_or(Vec3::zeros())
+                    .into(),
+                cloud_rotation: planet.clouds.as_ref().map(|clouds| clouds.rotation),
+                tail: planet.tail.as_ref().map(|tail| capture_particles(&tail.particles)).unwrap_or_default(),
+            })
+            .collect();
+
+        SaveState {
+            sim_time: clock.sim_time,
+            time_scale: clock.time_scale,
+            paused: clock.paused,
+            gravity: scene.gravity.is_some(),
+            source: source.clone(),
+            camera: CameraState {
+                eye: camera.eye.into(),
+                center: camera.center.into(),
+                up: camera.up.into(),
+            },
+            ship: scene.ship.as_ref().map(|ship| ShipState {
+                position: ship.position.into(),
+                velocity: ship.velocity.into(),
+                yaw: ship.yaw,
+                pitch: ship.pitch,
+                exhaust: capture_particles(&ship.exhaust),
+                debris: capture_particles(&ship.debris),
+            }),
+            bodies,
+            asteroid_rotations: scene.belt
+                .as_ref()
+                .map(|belt| belt.asteroids.iter().map(|asteroid| asteroid.rotation.into()).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self, SaveError> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| SaveError::Io(path.to_string(), err))?;
+
+        toml::from_str(&contents)
+            .map_err(|err| SaveError::Parse(path.to_string(), err))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), SaveError> {
+        let contents = toml::to_string(self).map_err(SaveError::Serialize)?;
+        fs::write(path, contents).map_err(|err| SaveError::Io(path.to_string(), err))
+    }
+
+    // Pone la escena (construida desde source), la cámara y el reloj en el estado guardado
+    pub fn apply(&self, scene: &mut Scene, camera: &mut Camera, clock: &mut Clock) -> Result<(), SaveError> {
+        if self.bodies.len() != scene.planets.len() {
+            return Err(SaveError::Mismatch(self.bodies.len(), scene.planets.len()));
+        }
+        for (index, (planet, body)) in scene.planets.iter().zip(self.bodies.iter()).enumerate() {
+            if planet.name != body.name {
+                return Err(SaveError::BodyMismatch(index, body.name.clone(), planet.name.clone()));
+            }
+        }
+        let asteroids = scene.belt.as_ref().map_or(0, |belt| belt.asteroids.len());
+        if self.asteroid_rotations.len() != asteroids {
+            return Err(SaveError::BeltMismatch(self.asteroid_rotations.len(), asteroids));
+        }
+
+        clock.sim_time = self.sim_time;
+        clock.time_scale = self.time_scale;
+        clock.paused = self.paused;
+
+        // Primero el estado paramétrico del instante guardado (nubes, anillos, colas);
+        // después las posiciones exactas, que con gravedad no salen de las órbitas
+        scene.update(self.sim_time, 0.0);
+        for (planet, body) in scene.planets.iter_mut().zip(self.bodies.iter()) {
+            planet.translation = Vec3::from(body.position);
+            planet.rotation = Vec3::from(body.rotation);
+            if let (Some(clouds), Some(rotation)) = (&mut planet.clouds, body.cloud_rotation) {
+                clouds.rotation = rotation;
+            }
+            if let Some(tail) = &mut planet.tail {
+                restore_particles(&mut tail.particles, &body.tail);
+            }
+        }
+        if let Some(belt) = &mut scene.belt {
+            for (asteroid, rotation) in belt.asteroids.iter_mut().zip(self.asteroid_rotations.iter()) {
+                asteroid.rotation = Vec3::from(*rotation);
+            }
+        }
+
+        scene.gravity = if self.gravity {
+            let bodies = scene.planets
+                .iter()
+                .zip(self.bodies.iter())
+                .map(|(planet, body)| Body::new(planet.translation, Vec3::from(body.velocity), planet.mass))
+                .collect();
+            Some(NBody::new(bodies))
+        } else {
+            None
+        };
+
+        if let (Some(ship), Some(state)) = (&mut scene.ship, &self.ship) {
+            ship.position = Vec3::from(state.position);
+            ship.velocity = Vec3::from(state.velocity);
+            ship.yaw = state.yaw;
+            ship.pitch = state.pitch;
+            restore_particles(&mut ship.exhaust, &state.exhaust);
+            restore_particles(&mut ship.debris, &state.debris);
+        }
+
+        camera.eye = Vec3::from(self.camera.eye);
+        camera.center = Vec3::from(self.camera.center);
+        camera.up = Vec3::from(self.camera.up);
+        camera.has_changed = true;
+
+        Ok(())
+    }
+}
+
+fn capture_particles(system: &ParticleSystem) -> Vec<ParticleState> {
+    system.particles
+        .iter()
+        .map(|particle| ParticleState {
+            position: particle.position.into(),
+            velocity: particle.velocity.into(),
+            age: particle.age,
+            lifetime: particle.lifetime,
+        })
+        .collect()
+}
+
+fn restore_particles(system: &mut ParticleSystem, states: &[ParticleState]) {
+    system.particles = states
+        .iter()
+        .map(|state| Particle {
+            position: Vec3::from(state.position),
+            velocity: Vec3::from(state.velocity),
+            age: state.age,
+            lifetime: state.lifetime,
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP: f32 = 1.0 / 60.0;
+
+    fn solar_system() -> SceneSource {
+        SceneSource::File { path: "assets/scenes/solar_system.toml".to_string() }
+    }
+
+    fn particles(system: &ParticleSystem) -> Vec<(Vec3, Vec3, f32, f32)> {
+        system.particles
+            .iter()
+            .map(|particle| (particle.position, particle.velocity, particle.age, particle.lifetime))
+            .collect()
+    }
+
+    // Avanza la escena un rato para que las nubes, las rocas y las colas tengan estado
+    fn running_scene(assets: &mut AssetManager) -> Scene {
+        let mut scene = solar_system().build(assets).expect("la escena del sistema solar se carga");
+        for frame in 0..120 {
+            scene.update(frame as f32 * STEP, if frame == 0 { 0.0 } else { STEP });
+        }
+        let ship = scene.ship.as_mut().expect("la escena tiene nave");
+        let (position, forward) = (ship.position, ship.forward());
+        ship.debris.burst(16, position, forward, 0.1);
+        scene
+    }
+
+    #[test]
+    fn save_and_load_restores_the_scene() {
+        let mut assets = AssetManager::new();
+        let scene = running_scene(&mut assets);
+        let camera = Camera::new(Vec3::new(1.0, 2.0, 3.0), Vec3::new(0.5, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let mut clock = Clock::new(STEP);
+        clock.sim_time = 119.0 * STEP;
+        clock.time_scale = 4.0;
+        clock.paused = true;
+
+        let path = std::env::temp_dir().join(format!("planet_renderer_save_{}.toml", std::process::id()));
+        let path = path.to_str().unwrap();
+        SaveState::capture(&solar_system(), &scene, &camera, &clock).save(path).unwrap();
+        let state = SaveState::load(path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        let mut restored = state.source.build(&mut assets).unwrap();
+        let mut restored_camera = Camera::new(Vec3::zeros(), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0));
+        let mut restored_clock = Clock::new(STEP);
+        state.apply(&mut restored, &mut restored_camera, &mut restored_clock).unwrap();
+
+        assert_eq!((restored_clock.sim_time, restored_clock.time_scale, restored_clock.paused), (clock.sim_time, 4.0, true));
+        assert_eq!((restored_camera.eye, restored_camera.center, restored_camera.up), (camera.eye, camera.center, camera.up));
+
+        for (planet, original) in restored.planets.iter().zip(scene.planets.iter()) {
+            assert_eq!(planet.translation, original.translation, "{}", planet.name);
+            assert_eq!(planet.rotation, original.rotation, "{}", planet.name);
+            assert_eq!(
+                planet.clouds.as_ref().map(|clouds| clouds.rotation),
+                original.clouds.as_ref().map(|clouds| clouds.rotation),
+                "{}",
+                planet.name
+            );
+            assert_eq!(
+                planet.tail.as_ref().map(|tail| particles(&tail.particles)),
+                original.tail.as_ref().map(|tail| particles(&tail.particles)),
+                "{}",
+                planet.name
+            );
+        }
+        assert!(scene.planets.iter().any(|planet| planet.clouds.as_ref().is_some_and(|clouds| clouds.rotation != 0.0)));
+        assert!(scene.planets.iter().any(|planet| planet.tail.as_ref().is_some_and(|tail| !tail.particles.is_empty())));
+
+        let rotations = |scene: &Scene| -> Vec<Vec3> {
+            scene.belt.as_ref().unwrap().asteroids.iter().map(|asteroid| asteroid.rotation).collect()
+        };
+        assert_eq!(rotations(&restored), rotations(&scene));
+
+        let (ship, original) = (restored.ship.as_ref().unwrap(), scene.ship.as_ref().unwrap());
+        assert_eq!((ship.position, ship.velocity, ship.yaw, ship.pitch), (original.position, original.velocity, original.yaw, original.pitch));
+        assert_eq!(particles(&ship.exhaust), particles(&original.exhaust));
+        assert!(!original.debris.is_empty());
+        assert_eq!(particles(&ship.debris), particles(&original.debris));
+    }
+
+    #[test]
+    fn renamed_body_is_rejected() {
+        let mut assets = AssetManager::new();
+        let scene = running_scene(&mut assets);
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 30.0), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0));
+        let clock = Clock::new(STEP);
+        let mut state = SaveState::capture(&solar_system(), &scene, &camera, &clock);
+        state.bodies[2].name = "Otro".to_string();
+
+        let mut restored = solar_system().build(&mut assets).unwrap();
+        let (mut camera, mut clock) = (camera, clock);
+        match state.apply(&mut restored, &mut camera, &mut clock) {
+            Err(SaveError::BodyMismatch(2, saved, actual)) => {
+                assert_eq!(saved, "Otro");
+                assert_eq!(actual, scene.planets[2].name);
+            }
+            other => panic!("se esperaba un error de nombre, llegó {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn different_belt_is_rejected() {
+        let mut assets = AssetManager::new();
+        let scene = running_scene(&mut assets);
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 30.0), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0));
+        let clock = Clock::new(STEP);
+        let mut state = SaveState::capture(&solar_system(), &scene, &camera, &clock);
+        let asteroids = state.asteroid_rotations.len();
+        state.asteroid_rotations.pop();
+
+        let mut restored = solar_system().build(&mut assets).unwrap();
+        let rotations: Vec<Vec3> = restored.belt.as_ref().unwrap().asteroids.iter().map(|asteroid| asteroid.rotation).collect();
+        let (mut camera, mut clock) = (camera, clock);
+        assert!(matches!(
+            state.apply(&mut restored, &mut camera, &mut clock),
+            Err(SaveError::BeltMismatch(saved, actual)) if saved == asteroids - 1 && actual == asteroids
+        ));
+        // No se aplicó nada a medias
+        let after: Vec<Vec3> = restored.belt.as_ref().unwrap().asteroids.iter().map(|asteroid| asteroid.rotation).collect();
+        assert_eq!(after, rotations);
+    }
+}
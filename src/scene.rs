@@ -0,0 +1,421 @@
+use serde::Deserialize;
+use nalgebra_glm::Vec3;
+use thiserror::Error;
+use std::f32::consts::TAU;
+use std::fs;
+use std::io;
+use crate::ambience::AmbientConfig;
+use crate::assets::{AssetManager, ModelError};
+use crate::belt::{AsteroidBelt, BeltConfig};
+use crate::billboard::{Billboard, BillboardConfig};
+use crate::fog::Fog;
+use crate::gravity::NBody;
+use crate::color::{Color, Gradient};
+use crate::lighting::{Light, LightConfig, PbrMaterial};
+use crate::planet::{Atmosphere, CloudLayer, Planet, Rings};
+use crate::lod::LodMeshes;
+use crate::mesh::{self, NormalWeighting};
+use crate::noise::{NoiseConfig, NoiseKind};
+use crate::orbit::Orbit;
+use crate::particles::{CometTail, TailConfig};
+use crate::prominence::{ProminenceConfig, Prominences};
+use crate::texture::Texture;
+use crate::script::{ScriptError, ScriptShader};
+use crate::sfx::SoundConfig;
+use crate::ship::{Ship, ShipConfig};
+use crate::shaders::{terrain_elevation, ShaderKind, SEA_LEVEL};
+use crate::starfield::{Starfield, StarfieldConfig};
+use crate::nebula::{Nebula, NebulaConfig};
+
+// Escena lista para simular y dibujar
+pub struct Scene {
+    pub planets: Vec<Planet>,
+    pub starfield: Starfield,
+    pub nebula: Option<Nebula>,
+    pub belt: Option<AsteroidBelt>,
+    pub ship: Option<Ship>,
+    pub billboards: Vec<Billboard>,
+    // Luces de relleno, además de las estrellas y los cuerpos que brillan
+    pub lights: Vec<Light>,
+    pub fog: Option<Fog>,
+    // Volumen de la música, el ambiente y cada efecto de sonido
+    pub sound: SoundConfig,
+    // Con Some las posiciones salen de la simulación de N cuerpos y no de las órbitas
+    pub gravity: Option<NBody>,
+    pub orbit_paths: Vec<Vec<Vec3>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SceneConfig {
+    #[serde(default)]
+    pub starfield: StarfieldConfig,
+    pub nebula: Option<NebulaConfig>,
+    pub belt: Option<BeltConfig>,
+    pub ship: Option<ShipConfig>,
+    #[serde(default)]
+    pub billboards: Vec<BillboardConfig>,
+    #[serde(default)]
+    pub lights: Vec<LightConfig>,
+    pub fog: Option<Fog>,
+    #[serde(default)]
+    pub sound: SoundConfig,
+    pub planets: Vec<PlanetConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlanetConfig {
+    pub name: String,
+    pub parent: Option<String>,
+    pub orbit: Option<Orbit>,
+    pub scale: f32,
+    pub shader: ShaderKind,
+    pub texture: Option<String>,
+    // Se puede indicar la velocidad (rad/s) o el periodo de un día (s); el periodo manda
+    #[serde(default)]
+    pub rotation_speed: f32,
+    pub rotation_period: Option<f32>,
+    #[serde(default)]
+    pub axial_tilt: f32,
+    pub mass: Option<f32>,
+    pub model: Option<String>,
+    // Con subdivisions la esfera generada es una icoesfera; si no, una esfera UV de stacks x slices
+    pub subdivisions: Option<u32>,
+    #[serde(default = "default_resolution")]
+    pub stacks: u32,
+    #[serde(default = "default_resolution")]
+    pub slices: u32,
+    #[serde(default)]
+    pub noise: NoiseConfig,
+    #[serde(default)]
+    pub double_sided: bool,
+    #[serde(default)]
+    pub transparent: bool,
+    #[serde(default = "default_opacity")]
+    pub opacity: f32,
+    #[serde(default)]
+    pub emissive: bool,
+    pub tint: Option<[u8; 3]>,
+    pub palette: Option<Gradient>,
+    // Archivo Rhai con el shader, para shader = "script"
+    pub script: Option<String>,
+    pub rings: Option<RingsConfig>,
+    pub clouds: Option<CloudsConfig>,
+    pub atmosphere: Option<AtmosphereConfig>,
+    #[serde(default)]
+    pub city_lights: bool,
+    // Color RGB del crepúsculo; los cuerpos con atmósfera usan uno anaranjado si no se indica
+    pub twilight: Option<[u8; 3]>,
+    // Sombreado físico de las superficies que reflejan (hielo, agua, metal, materiales)
+    pub pbr: Option<PbrMaterial>,
+    // El cuerpo también ilumina, como una luz puntual en su centro
+    pub light: Option<LightConfig>,
+    #[serde(default)]
+    pub displacement: f32,
+    pub tail: Option<TailConfig>,
+    pub prominences: Option<ProminenceConfig>,
+    pub ambient: Option<AmbientConfig>,
+}
+
+// Radios relativos al radio del planeta; la inclinación está en grados
+#[derive(Debug, Deserialize)]
+pub struct RingsConfig {
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+    #[serde(default = "default_ring_segments")]
+    pub segments: u32,
+    #[serde(default)]
+    pub tilt: f32,
+}
+
+// Capa de nubes: una esfera un poco más grande que gira a su propio ritmo
+#[derive(Debug, Deserialize)]
+pub struct CloudsConfig {
+    #[serde(default = "default_cloud_height")]
+    pub height: f32,
+    pub rotation_speed: f32,
+}
+
+// Color RGB del aire y grosor relativo al radio del planeta
+#[derive(Debug, Deserialize)]
+pub struct AtmosphereConfig {
+    pub color: [u8; 3],
+    #[serde(default = "default_atmosphere_thickness")]
+    pub thickness: f32,
+}
+
+// Masa por defecto: escala al cubo por la densidad; las estrellas son mucho más densas
+const STAR_DENSITY: f32 = 10.0;
+const PLANET_DENSITY: f32 = 0.05;
+// Crepúsculo de los cuerpos con aire que no indican otro
+const DEFAULT_TWILIGHT: Color = Color::new(255, 120, 60);
+
+fn default_atmosphere_thickness() -> f32 {
+    0.08
+}
+
+fn default_cloud_height() -> f32 {
+    1.03
+}
+
+fn default_opacity() -> f32 {
+    1.0
+}
+
+fn default_ring_segments() -> u32 {
+    64
+}
+
+fn default_resolution() -> u32 {
+    32
+}
+
+#[derive(Debug, Error)]
+pub enum SceneError {
+    #[error("no se pudo leer la escena '{0}': {1}")]
+    Io(String, #[source] io::Error),
+    #[error("escena inválida '{0}': {1}")]
+    Parse(String, #[source] toml::de::Error),
+    #[error("no se pudo cargar el modelo '{0}': {1}")]
+    Model(String, #[source] ModelError),
+    #[error("no se pudo cargar la textura '{0}': {1}")]
+    Texture(String, #[source] image::ImageError),
+    #[error("el planeta '{0}' orbita a '{1}', que no está declarado antes")]
+    Parent(String, String),
+    #[error(transparent)]
+    Script(#[from] ScriptError),
+}
+
+impl SceneConfig {
+    pub fn load(path: &str) -> Result<Self, SceneError> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| SceneError::Io(path.to_string(), err))?;
+
+        toml::from_str(&contents)
+            .map_err(|err| SceneError::Parse(path.to_string(), err))
+    }
+
+    pub fn build_planets(&self, assets: &mut AssetManager) -> Result<Vec<Planet>, SceneError> {
+        self.planets.iter().enumerate().map(|(index, config)| {
+            // Una luna orbita a un cuerpo declarado antes, así se actualiza después de él
+            let parent = match &config.parent {
+                Some(parent) => Some(
+                    self.planets[..index]
+                        .iter()
+                        .position(|other| &other.name == parent)
+                        .ok_or_else(|| SceneError::Parent(config.name.clone(), parent.clone()))?
+                ),
+                None => None,
+            };
+
+            // Sin modelo se genera una esfera con la resolución indicada (y sus niveles de detalle)
+            let mut mesh = match &config.model {
+                Some(model) => LodMeshes::single(
+                    assets.model(model).map_err(|err| SceneError::Model(model.clone(), err))?
+                ),
+                None => config.sphere(assets),
+            };
+
+            // Relieve: los vértices suben con la altitud del terreno (el mar queda plano)
+            let noise = config.noise.build();
+            if config.displacement > 0.0 {
+                // El relieve es propio del cuerpo: se desplaza una copia de cada nivel compartido
+                mesh = mesh.map(|level| {
+                    let mut displaced = level.clone();
+                    mesh::displace(&mut displaced.vertices, config.displacement, |position| {
+                        (terrain_elevation(&noise, position) - SEA_LEVEL).max(0.0)
+                    });
+                    mesh::smooth_normals(&mut displaced.vertices, &mut displaced.indices, NormalWeighting::Area, None);
+                    displaced
+                });
+            }
+
+            let texture = match &config.texture {
+                Some(path) => Some(
+                    Texture::load(path).map_err(|err| SceneError::Texture(path.clone(), err))?
+                ),
+                None => None,
+            };
+
+            let script = match &config.script {
+                Some(path) => Some(ScriptShader::load(path, &config.noise).map_err(SceneError::Script)?),
+                None => None,
+            };
+
+            Ok(Planet {
+                name: config.name.clone(),
+                parent,
+                translation: config.orbit
+                    .map(|orbit| orbit.position(0.0))
+                    .unwrap_or(Vec3::new(0.0, 0.0, 0.0)),
+                rotation: Vec3::new(0.0, 0.0, 0.0),
+                scale: config.scale,
+                orbit: config.orbit,
+                axial_tilt: config.axial_tilt.to_radians(),
+                rotation_period: config.rotation_period.unwrap_or(
+                    if config.rotation_speed != 0.0 { TAU / config.rotation_speed } else { 0.0 }
+                ),
+                mass: config.mass.unwrap_or_else(|| {
+                    let density = if config.emissive { STAR_DENSITY } else { PLANET_DENSITY };
+                    density * config.scale.powi(3)
+                }),
+                noise_config: config.noise,
+                noise,
+                craters: NoiseConfig { noise_type: NoiseKind::Cellular, octaves: 1, ..config.noise }.build(),
+                mesh,
+                shader: config.shader,
+                texture,
+                double_sided: config.double_sided,
+                transparent: config.transparent,
+                opacity: config.opacity,
+                emissive: config.emissive,
+                tint: config.tint.map(|[r, g, b]| Color::new(r, g, b)),
+                palette: config.palette.clone(),
+                script,
+                rings: config.rings.as_ref().map(|rings| Rings {
+                    mesh: mesh::generate_annulus(
+                        rings.inner_radius,
+                        rings.outer_radius,
+                        rings.segments,
+                    ),
+                    inner_radius: rings.inner_radius,
+                    outer_radius: rings.outer_radius,
+                    tilt: rings.tilt,
+                }),
+                clouds: config.clouds.as_ref().map(|clouds| CloudLayer {
+                    mesh: config.sphere(assets),
+                    height: clouds.height,
+                    rotation: 0.0,
+                    rotation_speed: clouds.rotation_speed,
+                }),
+                atmosphere: config.atmosphere.as_ref().map(|atmosphere| Atmosphere {
+                    mesh: config.sphere(assets),
+                    color: Color::new(atmosphere.color[0], atmosphere.color[1], atmosphere.color[2]),
+                    thickness: atmosphere.thickness,
+                }),
+                city_lights: config.city_lights,
+                twilight: config.twilight.map(|[r, g, b]| Color::new(r, g, b)).or_else(|| {
+                    config.atmosphere.is_some().then_some(DEFAULT_TWILIGHT)
+                }),
+                pbr: config.pbr,
+                light: config.light.as_ref().map(|light| Light::from(&LightConfig { direction: None, ..light.clone() })),
+                tail: config.tail.map(|tail| CometTail::new(tail, config.noise.seed as u64)),
+                prominences: config.prominences.map(|prominences| {
+                    Prominences::new(prominences, config.tint.map(|[r, g, b]| Color::new(r, g, b)), config.noise.seed as u64)
+                }),
+                ambient: config.ambient.clone(),
+            })
+        }).collect()
+    }
+}
+
+impl PlanetConfig {
+    // Esfera unitaria del cuerpo, que también usan sus nubes y su atmósfera
+    fn sphere(&self, assets: &mut AssetManager) -> LodMeshes {
+        match self.subdivisions {
+            Some(subdivisions) => LodMeshes::icosphere(assets, subdivisions),
+            None => LodMeshes::uv_sphere(assets, self.stacks, self.slices),
+        }
+    }
+}
+
+impl Scene {
+    pub fn load(path: &str, assets: &mut AssetManager) -> Result<Self, SceneError> {
+        Scene::from_config(&SceneConfig::load(path)?, assets)
+    }
+
+    pub fn from_config(config: &SceneConfig, assets: &mut AssetManager) -> Result<Self, SceneError> {
+        let planets = config.build_planets(assets)?;
+        // Las órbitas de las lunas se mueven con su planeta, no se dibujan fijas
+        let orbit_paths = planets
+            .iter()
+            .filter(|planet| planet.parent.is_none())
+            .filter_map(|planet| planet.orbit.map(|orbit| orbit.path(256)))
+            .collect();
+
+        let ship = match &config.ship {
+            Some(ship) => {
+                let model = assets.textured_model(&ship.model)
+                    .map_err(|err| SceneError::Model(ship.model.clone(), err))?;
+                Some(Ship::new(ship, model))
+            }
+            None => None,
+        };
+
+        let billboards = config.billboards
+            .iter()
+            .map(|billboard| {
+                Billboard::load(billboard).map_err(|err| {
+                    SceneError::Texture(billboard.texture.clone().unwrap_or_default(), err)
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        let mut scene = Scene {
+            planets,
+            starfield: Starfield::generate(&config.starfield),
+            nebula: config.nebula.as_ref().map(Nebula::generate),
+            belt: config.belt.as_ref().map(AsteroidBelt::generate),
+            ship,
+            billboards,
+            lights: config.lights.iter().map(Light::from).collect(),
+            fog: config.fog,
+            sound: config.sound,
+            gravity: None,
+            orbit_paths,
+        };
+        scene.update(0.0, 0.0);
+        Ok(scene)
+    }
+
+    // Activa o desactiva la gravedad; al desactivarla los planetas vuelven a sus órbitas
+    pub fn toggle_gravity(&mut self, sim_time: f32) {
+        self.gravity = match self.gravity {
+            Some(_) => None,
+            None => Some(NBody::from_planets(&self.planets, sim_time)),
+        };
+    }
+
+    pub fn update(&mut self, sim_time: f32, dt: f32) {
+        for index in 0..self.planets.len() {
+            self.planets[index].update(sim_time, dt);
+
+            // La órbita de una luna es relativa a su planeta, que ya se movió
+            if let Some(parent) = self.planets[index].parent {
+                let offset = self.planets[parent].translation;
+                self.planets[index].translation += offset;
+            }
+        }
+
+        if let Some(gravity) = &mut self.gravity {
+            gravity.step(dt);
+            for (planet, body) in self.planets.iter_mut().zip(gravity.bodies.iter()) {
+                planet.translation = body.position;
+            }
+        }
+
+        if let Some(belt) = &mut self.belt {
+            belt.update(sim_time, dt);
+        }
+
+        // Las colas de los cometas se alejan de la estrella más cercana; en reversa
+        // las partículas no retroceden, siguen saliendo del núcleo
+        let stars: Vec<Vec3> = self.planets
+            .iter()
+            .filter(|planet| planet.emissive)
+            .map(|planet| planet.translation)
+            .collect();
+        for planet in self.planets.iter_mut() {
+            if let Some(tail) = &mut planet.tail {
+                let sun = stars
+                    .iter()
+                    .copied()
+                    .min_by(|a, b| (a - planet.translation).magnitude().total_cmp(&(b - planet.translation).magnitude()))
+                    .unwrap_or(Vec3::new(0.0, 0.0, 0.0));
+                tail.update(dt.abs(), planet.translation, planet.scale, sun);
+            }
+            if let Some(prominences) = &mut planet.prominences {
+                prominences.update(sim_time, planet.translation, planet.scale * planet.mesh.radius());
+            }
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use nalgebra_glm::Vec3;
+use crate::assets::AssetManager;
+use crate::camera::Camera;
+use crate::save::SceneSource;
+use crate::scene::{Scene, SceneError};
+
+// Duración del salto en segundos; la escena se cambia a la mitad, con la pantalla en blanco
+const WARP_DURATION: f32 = 2.4;
+// Velocidad máxima de la cámara al salir y distancia desde la que llega al nuevo sistema
+const WARP_SPEED: f32 = 120.0;
+const WARP_ARRIVAL_DISTANCE: f32 = 60.0;
+
+struct Warp {
+    target: SceneSource,
+    elapsed: f32,
+    swapped: bool,
+    // Pose de la cámara al terminar la llegada
+    arrival_eye: Vec3,
+    arrival_center: Vec3,
+}
+
+// Dueño de la escena activa, de dónde salió y de las mallas que comparten las
+// escenas; cambia de sistema al instante o con un salto animado
+pub struct SceneManager {
+    pub scene: Scene,
+    pub source: SceneSource,
+    pub assets: AssetManager,
+    warp: Option<Warp>,
+}
+
+impl SceneManager {
+    pub fn new(scene: Scene, source: SceneSource, assets: AssetManager) -> Self {
+        SceneManager { scene, source, assets, warp: None }
+    }
+
+    // Reemplaza la escena de inmediato (y cancela un salto en curso)
+    pub fn replace(&mut self, scene: Scene, source: SceneSource) {
+        self.scene = scene;
+        self.source = source;
+        self.warp = None;
+    }
+
+    // Empieza un salto hacia otro sistema; la cámara termina en la pose de arrival
+    pub fn warp_to(&mut self, target: SceneSource, arrival: &Camera) {
+        self.warp = Some(Warp {
+            target,
+            elapsed: 0.0,
+            swapped: false,
+            arrival_eye: arrival.eye,
+            arrival_center: arrival.center,
+        });
+    }
+
+    pub fn is_warping(&self) -> bool {
+        self.warp.is_some()
+    }
+
+    // Intensidad del efecto de salto para el render: sube hasta 1 en el cambio de
+    // escena y vuelve a 0 al llegar
+    pub fn warp_intensity(&self) -> f32 {
+        match &self.warp {
+            Some(warp) => (warp.elapsed / WARP_DURATION * std::f32::consts::PI).sin().max(0.0),
+            None => 0.0,
+        }
+    }
+
+    // Avanza el salto: primero la cámara acelera hacia adelante, a la mitad se carga el
+    // sistema nuevo y después la cámara frena hasta la pose de llegada. Devuelve true
+    // en el frame en que cambió la escena (los índices de planetas dejan de valer).
+    pub fn update_warp(&mut self, camera: &mut Camera, dt: f32) -> Result<bool, SceneError> {
+        let warp = match &mut self.warp {
+            Some(warp) => warp,
+            None => return Ok(false),
+        };
+        warp.elapsed += dt;
+        let t = (warp.elapsed / WARP_DURATION).min(1.0);
+
+        let mut swapped = false;
+        if t < 0.5 {
+            let forward = (camera.center - camera.eye).normalize();
+            let speed = WARP_SPEED * (t * 2.0).powi(2);
+            camera.eye += forward * speed * dt;
+            camera.center += forward * speed * dt;
+        } else {
+            if !warp.swapped {
+                warp.swapped = true;
+                let scene = match warp.target.build(&mut self.assets) {
+                    Ok(scene) => scene,
+                    Err(err) => {
+                        self.warp = None;
+                        return Err(err);
+                    }
+                };
+                self.scene = scene;
+                self.source = warp.target.clone();
+                swapped = true;
+            }
+
+            // Llega desde atrás de la pose final, frenando
+            let remaining = (1.0 - (t - 0.5) * 2.0).powi(2) * WARP_ARRIVAL_DISTANCE;
+            let forward = (warp.arrival_center - warp.arrival_eye).normalize();
+            camera.eye = warp.arrival_eye - forward * remaining;
+            camera.center = warp.arrival_center - forward * remaining;
+        }
+        camera.has_changed = true;
+
+        if t >= 1.0 {
+            self.warp = None;
+        }
+        Ok(swapped)
+    }
+}
@@ -0,0 +1,177 @@
+use fastnoise_lite::FastNoiseLite;
+use nalgebra_glm::Vec3;
+use rhai::{CallFnOptions, Engine, Scope, AST};
+use thiserror::Error;
+use std::fs;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use crate::color::Color;
+use crate::fragment::Fragment;
+use crate::noise::{fbm, ridged, Fractal, NoiseConfig};
+
+// Color de los fragmentos cuyo script falló, para que se note en pantalla
+const ERROR_COLOR: Color = Color::new(255, 0, 255);
+
+#[derive(Debug, Error)]
+pub enum ScriptError {
+    #[error("no se pudo leer el shader de script '{0}': {1}")]
+    Io(String, #[source] io::Error),
+    #[error("shader de script inválido '{0}': {1}")]
+    Compile(String, #[source] rhai::ParseError),
+}
+
+// Lo que el script ve de cada fragmento (el tipo Fragment en Rhai)
+#[derive(Debug, Clone)]
+struct ScriptFragment {
+    // Punto sobre la esfera unitaria en el espacio del modelo
+    position: Vec3,
+    normal: Vec3,
+    world_position: Vec3,
+    view_dir: Vec3,
+    light_dir: Vec3,
+    u: f32,
+    v: f32,
+    intensity: f32,
+    time: f32,
+}
+
+// Shader de fragmentos escrito en Rhai: el archivo define fn shade(frag) y devuelve
+// el color como vec3(r, g, b) entre 0 y 1. Cada shader tiene su motor, con el
+// ruido del planeta registrado, y se recompila cuando cambia el archivo.
+pub struct ScriptShader {
+    path: String,
+    engine: Engine,
+    ast: AST,
+    modified: Option<SystemTime>,
+    // Primer error de ejecución desde la última carga; se informa una sola vez
+    error: Mutex<Option<String>>,
+}
+
+impl ScriptShader {
+    pub fn load(path: &str, noise: &NoiseConfig) -> Result<Self, ScriptError> {
+        let engine = create_engine(noise.build());
+        let (ast, modified) = compile(&engine, path)?;
+        Ok(ScriptShader {
+            path: path.to_string(),
+            engine,
+            ast,
+            modified,
+            error: Mutex::new(None),
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    // Vuelve a compilar si el archivo cambió desde la última carga. Devuelve si se
+    // recargó; con un error de compilación se sigue usando la versión anterior.
+    pub fn reload_if_changed(&mut self) -> Result<bool, ScriptError> {
+        let modified = fs::metadata(&self.path).and_then(|metadata| metadata.modified()).ok();
+        if modified.is_none() || modified == self.modified {
+            return Ok(false);
+        }
+
+        // Se anota la fecha aunque falle, para no insistir con el mismo error cada frame
+        self.modified = modified;
+        let (ast, _) = compile(&self.engine, &self.path)?;
+        self.ast = ast;
+        *self.error.lock().unwrap() = None;
+        Ok(true)
+    }
+
+    // Error de ejecución pendiente de informar, si hubo alguno
+    pub fn take_error(&self) -> Option<String> {
+        self.error.lock().unwrap().take()
+    }
+
+    pub fn shade(&self, fragment: &Fragment, time: f32) -> Color {
+        let script_fragment = ScriptFragment {
+            position: fragment.model_position.normalize(),
+            normal: fragment.normal,
+            world_position: fragment.world_position,
+            view_dir: fragment.view_dir,
+            light_dir: fragment.light_dir,
+            u: fragment.tex_coords.x,
+            v: fragment.tex_coords.y,
+            intensity: fragment.intensity,
+            time,
+        };
+
+        // Sin volver a evaluar el código global del archivo en cada llamada
+        let options = CallFnOptions::new().eval_ast(false).rewind_scope(false);
+        let result = self.engine.call_fn_with_options::<Vec3>(options, &mut Scope::new(), &self.ast, "shade", (script_fragment,));
+        match result {
+            Ok(color) => {
+                let color = color.map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round());
+                Color::new(color.x as u8, color.y as u8, color.z as u8)
+            }
+            Err(err) => {
+                let mut error = self.error.lock().unwrap();
+                if error.is_none() {
+                    *error = Some(format!("{}: {}", self.path, err));
+                }
+                ERROR_COLOR
+            }
+        }
+    }
+}
+
+fn compile(engine: &Engine, path: &str) -> Result<(AST, Option<SystemTime>), ScriptError> {
+    let source = fs::read_to_string(path).map_err(|err| ScriptError::Io(path.to_string(), err))?;
+    let modified = fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+    let ast = engine.compile(source).map_err(|err| ScriptError::Compile(path.to_string(), err))?;
+    Ok((ast, modified))
+}
+
+// Motor con los tipos y funciones disponibles para los scripts: vectores, ruido del
+// planeta y las utilidades de siempre de los shaders
+fn create_engine(noise: FastNoiseLite) -> Engine {
+    let mut engine = Engine::new();
+    let noise = Arc::new(noise);
+
+    engine
+        .register_type_with_name::<Vec3>("Vec3")
+        .register_fn("vec3", Vec3::new)
+        .register_get("x", |v: &mut Vec3| v.x)
+        .register_get("y", |v: &mut Vec3| v.y)
+        .register_get("z", |v: &mut Vec3| v.z)
+        .register_fn("+", |a: Vec3, b: Vec3| a + b)
+        .register_fn("-", |a: Vec3, b: Vec3| a - b)
+        .register_fn("*", |a: Vec3, b: Vec3| a.component_mul(&b))
+        .register_fn("*", |a: Vec3, s: f32| a * s)
+        .register_fn("*", |s: f32, a: Vec3| a * s)
+        .register_fn("/", |a: Vec3, s: f32| a / s)
+        .register_fn("dot", |a: Vec3, b: Vec3| a.dot(&b))
+        .register_fn("length", |a: Vec3| a.magnitude())
+        .register_fn("normalize", |a: Vec3| a.normalize())
+        .register_fn("mix", |a: Vec3, b: Vec3, t: f32| a.lerp(&b, t))
+        .register_fn("mix", |a: f32, b: f32, t: f32| a + (b - a) * t)
+        .register_fn("clamp", |x: f32, min: f32, max: f32| x.clamp(min, max))
+        .register_fn("smoothstep", |edge0: f32, edge1: f32, x: f32| {
+            let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+            t * t * (3.0 - 2.0 * t)
+        });
+
+    engine
+        .register_type_with_name::<ScriptFragment>("Fragment")
+        .register_get("position", |f: &mut ScriptFragment| f.position)
+        .register_get("normal", |f: &mut ScriptFragment| f.normal)
+        .register_get("world_position", |f: &mut ScriptFragment| f.world_position)
+        .register_get("view_dir", |f: &mut ScriptFragment| f.view_dir)
+        .register_get("light_dir", |f: &mut ScriptFragment| f.light_dir)
+        .register_get("u", |f: &mut ScriptFragment| f.u)
+        .register_get("v", |f: &mut ScriptFragment| f.v)
+        .register_get("intensity", |f: &mut ScriptFragment| f.intensity)
+        .register_get("time", |f: &mut ScriptFragment| f.time);
+
+    // Ruido del planeta: simple en [-1, 1], fBm y crestas con la cantidad de octavas indicada
+    let simple = Arc::clone(&noise);
+    engine.register_fn("noise", move |p: Vec3| simple.get_noise_3d(p.x, p.y, p.z));
+    let smooth = Arc::clone(&noise);
+    engine.register_fn("fbm", move |p: Vec3, octaves: i64| fbm(&smooth, p, Fractal::new(octaves.max(1) as u32)));
+    engine.register_fn("ridged", move |p: Vec3, octaves: i64| ridged(&noise, p, Fractal::new(octaves.max(1) as u32)));
+
+    engine
+}
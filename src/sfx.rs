@@ -0,0 +1,163 @@
+use rodio::Source;
+use serde::Deserialize;
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+const SAMPLE_RATE: u32 = 44_100;
+
+// Efectos de sonido de la interfaz: el salto a otro sistema, elegir un planeta o un
+// sistema del mapa, generar un sistema nuevo y los clics de las teclas que cambian
+// opciones. Se generan al vuelo, sin archivos.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sfx {
+    Warp,
+    Select,
+    Generate,
+    Click,
+}
+
+impl Sfx {
+    // Duración en segundos
+    fn duration(self) -> f32 {
+        match self {
+            Sfx::Warp => 1.6,
+            Sfx::Select => 0.16,
+            Sfx::Generate => 0.6,
+            Sfx::Click => 0.03,
+        }
+    }
+}
+
+// Volumen de cada tipo de sonido, de 0 en adelante; se multiplica por el volumen
+// general (F7/F8). En la escena va en la tabla [sound] y lo que falta vale 1.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct SoundConfig {
+    #[serde(default = "default_volume")]
+    pub music: f32,
+    #[serde(default = "default_volume")]
+    pub ambient: f32,
+    #[serde(default = "default_volume")]
+    pub warp: f32,
+    #[serde(default = "default_volume")]
+    pub select: f32,
+    #[serde(default = "default_volume")]
+    pub generate: f32,
+    #[serde(default = "default_volume")]
+    pub click: f32,
+}
+
+fn default_volume() -> f32 {
+    1.0
+}
+
+impl Default for SoundConfig {
+    fn default() -> Self {
+        SoundConfig {
+            music: 1.0,
+            ambient: 1.0,
+            warp: 1.0,
+            select: 1.0,
+            generate: 1.0,
+            click: 1.0,
+        }
+    }
+}
+
+impl SoundConfig {
+    pub fn effect(&self, sfx: Sfx) -> f32 {
+        match sfx {
+            Sfx::Warp => self.warp,
+            Sfx::Select => self.select,
+            Sfx::Generate => self.generate,
+            Sfx::Click => self.click,
+        }
+    }
+}
+
+// Un efecto en mono que termina solo. El salto es un tono que sube mezclado con
+// ruido, como un silbido que toma velocidad; la selección, dos notas cortas; el
+// sistema nuevo, un arpegio; el clic, un golpe agudo que se apaga enseguida.
+pub struct SfxSource {
+    kind: Sfx,
+    sample: u32,
+    length: u32,
+    phase: f32,
+    seed: u32,
+}
+
+impl SfxSource {
+    pub fn new(kind: Sfx) -> Self {
+        SfxSource {
+            kind,
+            sample: 0,
+            length: (kind.duration() * SAMPLE_RATE as f32) as u32,
+            phase: 0.0,
+            seed: 0x9E37_79B9,
+        }
+    }
+
+    fn white(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        self.seed as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+
+    // Avanza un oscilador de frecuencia variable y devuelve su seno
+    fn oscillate(&mut self, frequency: f32) -> f32 {
+        self.phase = (self.phase + frequency / SAMPLE_RATE as f32).fract();
+        (TAU * self.phase).sin()
+    }
+}
+
+impl Iterator for SfxSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.sample >= self.length {
+            return None;
+        }
+        let t = self.sample as f32 / SAMPLE_RATE as f32;
+        let progress = self.sample as f32 / self.length as f32;
+        self.sample += 1;
+
+        let sample = match self.kind {
+            Sfx::Warp => {
+                let frequency = 80.0 * (900.0f32 / 80.0).powf(progress);
+                let envelope = (progress * 4.0).min(1.0) * (1.0 - progress).powi(2);
+                (0.6 * self.oscillate(frequency) + 0.25 * self.white()) * envelope
+            }
+            Sfx::Select => {
+                let frequency = if progress < 0.4 { 660.0 } else { 990.0 };
+                let note = if progress < 0.4 { progress / 0.4 } else { (progress - 0.4) / 0.6 };
+                0.5 * self.oscillate(frequency) * (1.0 - note)
+            }
+            Sfx::Generate => {
+                const NOTES: [f32; 4] = [523.25, 659.25, 783.99, 1046.5];
+                let step = ((progress * NOTES.len() as f32) as usize).min(NOTES.len() - 1);
+                let note = progress * NOTES.len() as f32 - step as f32;
+                0.4 * self.oscillate(NOTES[step]) * (-4.0 * note).exp()
+            }
+            Sfx::Click => 0.6 * self.oscillate(1800.0) * (-t * 150.0).exp(),
+        };
+        Some(sample)
+    }
+}
+
+impl Source for SfxSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some((self.length - self.sample) as usize)
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(self.kind.duration()))
+    }
+}
@@ -0,0 +1,346 @@
+use serde::Deserialize;
+use thiserror::Error;
+use std::fs;
+use std::io;
+use std::time::SystemTime;
+use crate::color::{Color, Gradient};
+
+#[derive(Debug, Error)]
+pub enum ShaderParamsError {
+    #[error("no se pudieron leer los parámetros de los shaders '{0}': {1}")]
+    Io(String, #[source] io::Error),
+    #[error("parámetros de los shaders inválidos '{0}': {1}")]
+    Parse(String, #[source] toml::de::Error),
+}
+
+// Rampas de color por defecto de los shaders; la escena puede reemplazarlas con "palette"
+const LAVA_RAMP: [Color; 2] = [
+    Color::new(130, 20, 0),  // Costra rojo oscuro
+    Color::new(255, 240, 0), // Lava brillante
+];
+const SUN_RAMP: [Color; 3] = [
+    Color::new(190, 60, 0),    // Bordes entre gránulos
+    Color::new(255, 170, 30),  // Superficie
+    Color::new(255, 250, 210), // Centro de los gránulos
+];
+const MOON_RAMP: [Color; 2] = [
+    Color::new(165, 162, 155), // Tierras altas
+    Color::new(95, 95, 100),   // Mares
+];
+const JUPITER_RAMP: [Color; 4] = [
+    Color::new(205, 133, 63),  // Marrón claro
+    Color::new(255, 165, 0),   // Naranja
+    Color::new(245, 222, 179), // Beige
+    Color::new(139, 69, 19),   // Marrón oscuro
+];
+
+// Constantes ajustables de los shaders integrados (colores, umbrales, escalas del
+// ruido y velocidades). Se leen de un TOML con una tabla por shader; lo que falta
+// queda con el valor de siempre, así un archivo vacío no cambia nada.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ShaderParams {
+    pub lava: LavaParams,
+    pub sun: SunParams,
+    pub ice: IceParams,
+    pub cloud: CloudParams,
+    pub earth: EarthParams,
+    pub cloud_layer: CloudLayerParams,
+    pub moon: MoonParams,
+    pub jupiter: JupiterParams,
+    pub ring: RingParams,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LavaParams {
+    pub ramp: Gradient,
+    pub zoom: f32,
+    pub speed: f32,
+    // Frecuencia y amplitud del latido que agranda y achica las manchas
+    pub pulse_frequency: f32,
+    pub pulse_amplitude: f32,
+    // Cuánto se hunde la lava brillante respecto de la costra
+    pub bump: f32,
+    // Cuánto laten las manchas y brilla la lava con los graves de la música (0 = nada)
+    pub music_response: f32,
+}
+
+impl Default for LavaParams {
+    fn default() -> Self {
+        LavaParams {
+            ramp: Gradient::new(LAVA_RAMP.to_vec()),
+            zoom: 500.0,
+            speed: 0.6,
+            pulse_frequency: 0.2,
+            pulse_amplitude: 0.5,
+            bump: 0.03,
+            music_response: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SunParams {
+    pub ramp: Gradient,
+    pub zoom: f32,
+    pub speed: f32,
+    // Cuánto se oscurece el borde del disco (0 = nada)
+    pub limb_darkening: f32,
+    // Cuánto se aviva la estrella con el volumen de la música (0 = nada)
+    pub music_response: f32,
+}
+
+impl Default for SunParams {
+    fn default() -> Self {
+        SunParams {
+            ramp: Gradient::new(SUN_RAMP.to_vec()),
+            zoom: 300.0,
+            speed: 0.3,
+            limb_darkening: 0.6,
+            music_response: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct IceParams {
+    pub ripple_frequency: f32,
+    pub ripple_speed: f32,
+}
+
+impl Default for IceParams {
+    fn default() -> Self {
+        IceParams {
+            ripple_frequency: 8.0,
+            ripple_speed: 6.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CloudParams {
+    pub zoom: f32,
+    pub speed: f32,
+    // Por encima del umbral del ruido hay nubes o tierra
+    pub cloud_threshold: f32,
+    pub land_threshold: f32,
+    pub cloud_color: Color,
+    pub sky_color: Color,
+    pub land_color: Color,
+}
+
+impl Default for CloudParams {
+    fn default() -> Self {
+        CloudParams {
+            zoom: 100.0,
+            speed: 0.3,
+            cloud_threshold: 0.5,
+            land_threshold: 0.1,
+            cloud_color: Color::new(255, 255, 255),
+            sky_color: Color::new(30, 97, 145),
+            land_color: Color::new(0, 100, 0),
+        }
+    }
+}
+
+// La altitud del terreno no se configura aquí: la comparte el desplazamiento de
+// vértices y cambiarla en vivo dejaría la malla desfasada de los colores
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EarthParams {
+    // Latitud (0 a 1) donde empiezan los casquetes polares
+    pub ice_line: f32,
+    // Profundidad hasta la que el agua se ve clara cerca de la costa
+    pub shallow_depth: f32,
+    pub ice_color: Color,
+    pub deep_color: Color,
+    pub ocean_color: Color,
+    pub shallow_color: Color,
+    pub beach_color: Color,
+    pub desert_color: Color,
+    pub grass_color: Color,
+    pub forest_color: Color,
+    pub tundra_color: Color,
+    pub mountain_color: Color,
+    pub snow_color: Color,
+    pub city_color: Color,
+    pub bump: f32,
+}
+
+impl Default for EarthParams {
+    fn default() -> Self {
+        EarthParams {
+            ice_line: 0.82,
+            shallow_depth: 0.08,
+            ice_color: Color::new(235, 245, 255),
+            deep_color: Color::new(10, 35, 100),
+            ocean_color: Color::new(25, 80, 160),
+            shallow_color: Color::new(60, 170, 190),
+            beach_color: Color::new(210, 195, 140),
+            desert_color: Color::new(205, 170, 100),
+            grass_color: Color::new(90, 145, 55),
+            forest_color: Color::new(30, 95, 35),
+            tundra_color: Color::new(125, 125, 100),
+            mountain_color: Color::new(115, 95, 75),
+            snow_color: Color::new(245, 245, 250),
+            city_color: Color::new(255, 190, 100),
+            bump: 0.15,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CloudLayerParams {
+    pub color: Color,
+    pub zoom: f32,
+    // Cuánto enrosca el dominio deformado las nubes
+    pub warp: f32,
+    // Densidad a partir de la cual aparecen nubes y cuánto más hace falta para que sean opacas
+    pub coverage: f32,
+    pub softness: f32,
+    pub max_alpha: u8,
+}
+
+impl Default for CloudLayerParams {
+    fn default() -> Self {
+        CloudLayerParams {
+            color: Color::new(250, 250, 255),
+            zoom: 200.0,
+            warp: 0.6,
+            coverage: 0.5,
+            softness: 0.2,
+            max_alpha: 230,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MoonParams {
+    pub ramp: Gradient,
+    // Escala de los cráteres grandes; los pequeños son tres veces más densos
+    pub zoom: f32,
+    pub mare_zoom: f32,
+}
+
+impl Default for MoonParams {
+    fn default() -> Self {
+        MoonParams {
+            ramp: Gradient::new(MOON_RAMP.to_vec()),
+            zoom: 300.0,
+            mare_zoom: 60.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct JupiterParams {
+    pub ramp: Gradient,
+    pub zoom: f32,
+    // Velocidad de los vientos zonales que desplazan las bandas
+    pub drift_speed: f32,
+    // Cuánto deforma la turbulencia la latitud antes de elegir la banda
+    pub turbulence: f32,
+    pub band_frequency: f32,
+    // El ruido de las bandas entre -band_offset y band_range - band_offset recorre la rampa
+    pub band_offset: f32,
+    pub band_range: f32,
+    // Gran Mancha Roja: centro y semiejes en radianes, deriva hacia el oeste
+    pub storm_latitude: f32,
+    pub storm_longitude: f32,
+    pub storm_speed: f32,
+    pub storm_width: f32,
+    pub storm_height: f32,
+    pub storm_edge: Color,
+    pub storm_core: Color,
+}
+
+impl Default for JupiterParams {
+    fn default() -> Self {
+        JupiterParams {
+            ramp: Gradient::new(JUPITER_RAMP.to_vec()),
+            zoom: 150.0,
+            drift_speed: 0.05,
+            turbulence: 0.12,
+            band_frequency: 400.0,
+            band_offset: 0.15,
+            band_range: 0.9,
+            storm_latitude: -0.35,
+            storm_longitude: 0.3,
+            storm_speed: 0.02,
+            storm_width: 0.28,
+            storm_height: 0.12,
+            storm_edge: Color::new(200, 70, 30),
+            storm_core: Color::new(235, 120, 70),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RingParams {
+    pub width: f32,
+    pub spacing: f32,
+    pub ring_color: Color,
+    pub gap_color: Color,
+}
+
+impl Default for RingParams {
+    fn default() -> Self {
+        RingParams {
+            width: 0.02,
+            spacing: 0.08,
+            ring_color: Color::new(200, 200, 200),
+            gap_color: Color::new(100, 50, 200),
+        }
+    }
+}
+
+impl ShaderParams {
+    pub fn load(path: &str) -> Result<Self, ShaderParamsError> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| ShaderParamsError::Io(path.to_string(), err))?;
+        toml::from_str(&contents).map_err(|err| ShaderParamsError::Parse(path.to_string(), err))
+    }
+}
+
+// Archivo de parámetros que se vuelve a leer al guardarlo, para ajustar los shaders
+// con el programa corriendo
+pub struct ShaderParamsFile {
+    path: String,
+    modified: Option<SystemTime>,
+}
+
+impl ShaderParamsFile {
+    pub fn open(path: &str) -> Result<(Self, ShaderParams), ShaderParamsError> {
+        let modified = modified_time(path);
+        let params = ShaderParams::load(path)?;
+        Ok((ShaderParamsFile { path: path.to_string(), modified }, params))
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    // Los parámetros nuevos si el archivo cambió desde la última lectura. Con un error
+    // se siguen usando los anteriores hasta que se vuelva a guardar.
+    pub fn reload_if_changed(&mut self) -> Result<Option<ShaderParams>, ShaderParamsError> {
+        let modified = modified_time(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return Ok(None);
+        }
+
+        self.modified = modified;
+        ShaderParams::load(&self.path).map(Some)
+    }
+}
+
+fn modified_time(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
@@ -1,9 +1,15 @@
 
-use nalgebra_glm::{mat4_to_mat3, Mat3, Vec2, Vec3, Vec4};
+use nalgebra_glm::{mat4_to_mat3, Mat3, Mat4, Vec3, Vec4};
+use fastnoise_lite::FastNoiseLite;
+use wide::f32x4;
 use crate::vertex::Vertex;
-use crate::Uniforms;
+use crate::renderer::Uniforms;
 use crate::fragment::Fragment;
-use crate::color::Color;
+use crate::color::{sample_ramp, Color, Gradient};
+use crate::lighting::{diffuse_lighting, pbr_specular, specular_lighting, Material, PbrMaterial};
+use crate::noise::{domain_warp, fbm, ridged, Fractal};
+use serde::Deserialize;
+use std::f32::consts::PI;
 
 pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
     let position = Vec4::new(
@@ -13,7 +19,8 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
         1.0
     );
 
-    let transformed = uniforms.projection_matrix * uniforms.view_matrix * uniforms.model_matrix * position;
+    let world = uniforms.model_matrix * position;
+    let transformed = uniforms.projection_matrix * uniforms.view_matrix * world;
 
     let w = transformed.w;
     let transformed_position = Vec4::new(
@@ -25,36 +32,161 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
 
     let screen_position = uniforms.viewport_matrix * transformed_position;
 
-    let model_mat3 = mat4_to_mat3(&uniforms.model_matrix);
-    let normal_matrix = model_mat3.transpose().try_inverse().unwrap_or(Mat3::identity());
-
-    let transformed_normal = normal_matrix * vertex.normal;
+    let transformed_normal = normal_matrix(&uniforms.model_matrix) * vertex.normal;
 
     Vertex {
         position: vertex.position,
         normal: vertex.normal,
         tex_coords: vertex.tex_coords,
         color: vertex.color,
+        clip_position: transformed,
+        world_position: Vec3::new(world.x, world.y, world.z),
         transformed_position: Vec3::new(screen_position.x, screen_position.y, screen_position.z),
-        transformed_normal: transformed_normal
+        transformed_normal
     }
 }
 
-pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms, shader_type: &str) -> Color {
-  match shader_type {
-      "cloud" => cloud_shader(fragment, uniforms),
-      "lava" => lava_shader(fragment, uniforms),
-      "ice" => ice_shader(fragment, uniforms),
-      "jupiter" => jupiter_shader(fragment, uniforms),
-      "ring" => ring_shader(fragment, uniforms),
-      "metal" => metal_shader(fragment, uniforms),
-      _ => combined_shader(fragment, uniforms), // Default shader
+// Vértices que el camino SIMD transforma a la vez
+pub const VERTEX_LANES: usize = 4;
+
+// Lo mismo que vertex_shader para un lote de vértices: cada registro lleva la misma
+// componente de VERTEX_LANES vértices y las matrices (también la de normales) se
+// preparan una vez por lote. Los que sobran al final pasan por vertex_shader.
+pub fn vertex_shader_batch(vertices: &[Vertex], uniforms: &Uniforms, output: &mut [Vertex]) {
+    let view_projection = uniforms.projection_matrix * uniforms.view_matrix;
+    let normal_matrix = normal_matrix(&uniforms.model_matrix);
+    let one = f32x4::splat(1.0);
+
+    let mut chunks = vertices.chunks_exact(VERTEX_LANES);
+    let mut outputs = output.chunks_exact_mut(VERTEX_LANES);
+    for (chunk, out) in (&mut chunks).zip(&mut outputs) {
+        let position = [
+            gather(chunk, |vertex| vertex.position.x),
+            gather(chunk, |vertex| vertex.position.y),
+            gather(chunk, |vertex| vertex.position.z),
+            one,
+        ];
+        let normal = [
+            gather(chunk, |vertex| vertex.normal.x),
+            gather(chunk, |vertex| vertex.normal.y),
+            gather(chunk, |vertex| vertex.normal.z),
+        ];
+
+        let world = transform_lanes(&uniforms.model_matrix, &position);
+        let clip = transform_lanes(&view_projection, &world);
+        let ndc = [clip[0] / clip[3], clip[1] / clip[3], clip[2] / clip[3], one];
+        let screen = transform_lanes(&uniforms.viewport_matrix, &ndc);
+        let normal = [0, 1, 2].map(|row| {
+            f32x4::splat(normal_matrix[(row, 0)]) * normal[0]
+                + f32x4::splat(normal_matrix[(row, 1)]) * normal[1]
+                + f32x4::splat(normal_matrix[(row, 2)]) * normal[2]
+        });
+
+        let [world, clip, screen] = [world, clip, screen].map(|lanes| lanes.map(|lane| lane.to_array()));
+        let normal = normal.map(|lane| lane.to_array());
+        for (lane, (vertex, out)) in chunk.iter().zip(out.iter_mut()).enumerate() {
+            *out = Vertex {
+                position: vertex.position,
+                normal: vertex.normal,
+                tex_coords: vertex.tex_coords,
+                color: vertex.color,
+                clip_position: Vec4::new(clip[0][lane], clip[1][lane], clip[2][lane], clip[3][lane]),
+                world_position: Vec3::new(world[0][lane], world[1][lane], world[2][lane]),
+                transformed_position: Vec3::new(screen[0][lane], screen[1][lane], screen[2][lane]),
+                transformed_normal: Vec3::new(normal[0][lane], normal[1][lane], normal[2][lane]),
+            };
+        }
+    }
+
+    for (vertex, out) in chunks.remainder().iter().zip(outputs.into_remainder()) {
+        *out = vertex_shader(vertex, uniforms);
+    }
+}
+
+// Las normales se transforman con la inversa transpuesta de la parte 3x3 del modelo
+fn normal_matrix(model_matrix: &Mat4) -> Mat3 {
+    mat4_to_mat3(model_matrix).transpose().try_inverse().unwrap_or(Mat3::identity())
+}
+
+fn gather(vertices: &[Vertex], component: impl Fn(&Vertex) -> f32) -> f32x4 {
+    f32x4::new([
+        component(&vertices[0]),
+        component(&vertices[1]),
+        component(&vertices[2]),
+        component(&vertices[3]),
+    ])
+}
+
+// Matriz por vector para cada carril, sumando en el mismo orden que nalgebra
+fn transform_lanes(matrix: &Mat4, vector: &[f32x4; 4]) -> [f32x4; 4] {
+    [0, 1, 2, 3].map(|row| {
+        f32x4::splat(matrix[(row, 0)]) * vector[0]
+            + f32x4::splat(matrix[(row, 1)]) * vector[1]
+            + f32x4::splat(matrix[(row, 2)]) * vector[2]
+            + f32x4::splat(matrix[(row, 3)]) * vector[3]
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShaderKind {
+  Lava,
+  Ice,
+  Cloud,
+  Jupiter,
+  Ring,
+  Metal,
+  Textured,
+  Sun,
+  Earth,
+  CloudLayer,
+  Atmosphere,
+  Moon,
+  Combined,
+  // Shader en un archivo Rhai (el "script" del planeta)
+  Script,
+  // Color y textura del material .mtl de un modelo OBJ
+  Material,
+}
+
+pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms, shader: ShaderKind) -> Color {
+  match shader {
+      ShaderKind::Cloud => cloud_shader(fragment, uniforms),
+      ShaderKind::Lava => lava_shader(fragment, uniforms),
+      ShaderKind::Ice => ice_shader(fragment, uniforms),
+      ShaderKind::Jupiter => jupiter_shader(fragment, uniforms),
+      ShaderKind::Ring => ring_shader(fragment, uniforms),
+      ShaderKind::Metal => metal_shader(fragment, uniforms),
+      ShaderKind::Textured => textured_shader(fragment, uniforms),
+      ShaderKind::Sun => sun_shader(fragment, uniforms),
+      ShaderKind::Earth => earth_shader(fragment, uniforms),
+      ShaderKind::CloudLayer => cloud_layer_shader(fragment, uniforms),
+      ShaderKind::Atmosphere => atmosphere_shader(fragment, uniforms),
+      ShaderKind::Moon => moon_shader(fragment, uniforms),
+      ShaderKind::Combined => combined_shader(fragment, uniforms),
+      ShaderKind::Script => match uniforms.script {
+          Some(script) => script.shade(fragment, uniforms.sim_time),
+          None => static_pattern_shader(fragment) * fragment.intensity,
+      },
+      ShaderKind::Material => material_shader(fragment, uniforms),
   }
 }
 
+// Punto sobre la esfera unitaria en el espacio del modelo: el ruido muestreado
+// aquí queda pegado a la superficie aunque el planeta o la cámara se muevan
+fn surface_point(fragment: &Fragment) -> Vec3 {
+  fragment.model_position.normalize()
+}
+
+// Rotación alrededor del eje polar, para desplazar patrones en longitud
+fn rotate_y(point: Vec3, angle: f32) -> Vec3 {
+  let (sin, cos) = angle.sin_cos();
+  Vec3::new(point.x * cos + point.z * sin, point.y, -point.x * sin + point.z * cos)
+}
+
 fn static_pattern_shader(fragment: &Fragment) -> Color {
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
+    let x = fragment.model_position.x;
+    let y = fragment.model_position.y;
   
     let pattern = ((x * 10.0).sin() * (y * 10.0).sin()).abs();
   
@@ -65,177 +197,562 @@ fn static_pattern_shader(fragment: &Fragment) -> Color {
     Color::new(r, g, b)
 }
 
-fn lava_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-  // Base colors for the lava effect
-  let bright_color = Color::new(255, 240, 0); // Bright orange (lava-like)
-  let dark_color = Color::new(130, 20, 0);   // Darker red-orange  
-
-  // Get fragment position
-  let position = Vec3::new(
-    fragment.vertex_position.x,
-    fragment.vertex_position.y,
-    fragment.depth
-  );
+// La rampa configurada para el cuerpo o, si no tiene, la del shader
+fn ramp<'a>(uniforms: &Uniforms<'a>, default: &'a [Color]) -> &'a [Color] {
+  uniforms.palette.map(Gradient::colors).unwrap_or(default)
+}
+
+// Altura del relieve que simula el shader en un punto de la esfera unitaria, para
+// exportarla como mapa de alturas. None si el shader no tiene relieve.
+pub fn surface_height(point: Vec3, uniforms: &Uniforms, shader: ShaderKind) -> Option<f32> {
+  match shader {
+    // Lo mismo que desplaza los vértices de un planeta con relieve
+    ShaderKind::Earth => Some((terrain_elevation(uniforms.noise, point) - SEA_LEVEL).max(0.0)),
+    ShaderKind::Lava => Some(-lava_noise(uniforms, point)),
+    ShaderKind::Moon => Some(moon_relief(uniforms, point)),
+    _ => None,
+  }
+}
+
+fn lava_noise(uniforms: &Uniforms, point: Vec3) -> f32 {
+  let params = &uniforms.params.lava;
 
   // Base frequency and amplitude for the pulsating effect
-  let base_frequency = 0.2;
-  let pulsate_amplitude = 0.5;
-  let t = uniforms.time as f32 * 0.01;
+  let base_frequency = params.pulse_frequency;
+  let pulsate_amplitude = params.pulse_amplitude;
+  let t = uniforms.sim_time * params.speed;
 
-  // Pulsate on the z-axis to change spot size
-  let pulsate = (t * base_frequency).sin() * pulsate_amplitude;
+  // Pulsate on the z-axis to change spot size; los graves de la música lo empujan
+  let beat = uniforms.music.bass() * params.music_response;
+  let pulsate = (t * base_frequency).sin() * pulsate_amplitude + beat * 0.3;
 
   // Apply noise to coordinates with subtle pulsating on z-axis
-  let zoom = 1000.0; // Constant zoom factor
-  let noise_value1 = uniforms.noise.get_noise_3d(
-    position.x * zoom,
-    position.y * zoom,
-    (position.z + pulsate) * zoom
+  let pulsed = Vec3::new(point.x, point.y, point.z + pulsate) * params.zoom;
+  fbm(uniforms.noise, pulsed, Fractal::new(3))
+}
+
+fn lava_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+  let params = &uniforms.params.lava;
+
+  // Get fragment position on the unit sphere
+  let position = surface_point(fragment);
+  let noise_value = lava_noise(uniforms, position);
+
+  // De la costra a la lava según el ruido
+  let color = sample_ramp(ramp(uniforms, params.ramp.colors()), noise_value);
+
+  // La lava brillante está hundida y la costra oscura sobresale
+  let normal = bumped_normal(fragment, uniforms, params.bump, |point| -lava_noise(uniforms, point));
+  let intensity = diffuse_lighting(uniforms.lights, &fragment.world_position, &normal, &fragment.light_visibility);
+
+  // Con cada golpe de graves la lava brilla más que la costra
+  let glow = 1.0 + uniforms.music.bass() * params.music_response * noise_value.clamp(0.0, 1.0);
+  color * (intensity * glow)
+}
+
+// Normal en espacio de mundo perturbada por el gradiente de height, calculado con
+// diferencias finitas sobre la esfera unitaria: da relieve sin agregar geometría
+fn bumped_normal(fragment: &Fragment, uniforms: &Uniforms, strength: f32, height: impl Fn(Vec3) -> f32) -> Vec3 {
+  let position = surface_point(fragment);
+  let epsilon = 0.002;
+
+  // Base tangente en el espacio del modelo
+  let reference = if position.y.abs() < 0.99 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+  let tangent = reference.cross(&position).normalize();
+  let bitangent = position.cross(&tangent);
+
+  let center = height(position);
+  let slope_t = (height((position + tangent * epsilon).normalize()) - center) / epsilon;
+  let slope_b = (height((position + bitangent * epsilon).normalize()) - center) / epsilon;
+
+  // La misma base llevada al espacio de mundo, ortogonal a la normal interpolada
+  let normal = fragment.normal.normalize();
+  let model_mat3 = mat4_to_mat3(&uniforms.model_matrix);
+  let world_tangent = model_mat3 * tangent;
+  let world_tangent = (world_tangent - normal * normal.dot(&world_tangent)).normalize();
+  let world_bitangent = normal.cross(&world_tangent);
+
+  (normal - (world_tangent * slope_t + world_bitangent * slope_b) * strength).normalize()
+}
+
+fn sun_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+  let params = &uniforms.params.sun;
+
+  // Una estrella con tinte propio (p. ej. la compañera roja de un sistema binario)
+  // arma la rampa a partir de ese color
+  let tinted = uniforms.tint.map(|tint| [tint * 0.55, tint, tint.lerp(&Color::new(255, 255, 255), 0.7)]);
+  let colors = match &tinted {
+    Some(tinted) => ramp(uniforms, tinted),
+    None => ramp(uniforms, params.ramp.colors()),
+  };
+
+  let position = surface_point(fragment);
+  let t = uniforms.sim_time * params.speed;
+
+  // Granulación: fBm que se desplaza lentamente con el tiempo
+  let zoom = params.zoom;
+  // La música agita la granulación y aviva el disco al ritmo del volumen
+  let beat = uniforms.music.amplitude * params.music_response;
+  let granulation = fbm(
+    uniforms.noise,
+    position * zoom + Vec3::new(t * 20.0, beat * 0.5, -t * 15.0),
+    Fractal::new(4),
   );
-  let noise_value2 = uniforms.noise.get_noise_3d(
-    (position.x + 1000.0) * zoom,
-    (position.y + 1000.0) * zoom,
-    (position.z + 1000.0 + pulsate) * zoom
+  let granulation = (granulation * 0.5 + 0.5).clamp(0.0, 1.0);
+
+  let color = sample_ramp(colors, granulation);
+
+  // Oscurecimiento hacia el borde del disco (limb darkening)
+  let mu = fragment.normal.normalize().dot(&fragment.view_dir).max(0.0);
+  let limb = 1.0 - params.limb_darkening * (1.0 - mu);
+
+  color * (limb * fragment.intensity * (1.0 + beat * 0.5))
+}
+
+const ICE_MATERIAL: Material = Material { shininess: 64.0, specular: 0.6 };
+const WATER_MATERIAL: Material = Material { shininess: 48.0, specular: 0.4 };
+const METAL_MATERIAL: Material = Material { shininess: 24.0, specular: 0.9 };
+
+// Reflejo especular de las estrellas con el color dado
+fn specular_highlight(fragment: &Fragment, uniforms: &Uniforms, material: &Material, color: Color) -> Color {
+  let normal = fragment.normal.normalize();
+  let specular = specular_lighting(
+    uniforms.lights,
+    &fragment.world_position,
+    &normal,
+    &fragment.view_dir,
+    material,
+    &fragment.light_visibility,
   );
-  let noise_value = (noise_value1 + noise_value2) * 0.5;  // Averaging noise for smoother transitions
+  color * specular
+}
 
-  // Use lerp for color blending based on noise value
-  let color = dark_color.lerp(&bright_color, noise_value);
+// Superficie que refleja la luz: con un material PBR en el cuerpo, difuso de Lambert
+// y reflejo GGX; si no, el difuso de siempre más el reflejo con el color dado
+fn reflective(fragment: &Fragment, uniforms: &Uniforms, albedo: Color, material: &Material, highlight: Color) -> Color {
+  match uniforms.pbr {
+    Some(pbr) => physical_shading(fragment, uniforms, albedo, &pbr),
+    None => albedo * fragment.intensity + specular_highlight(fragment, uniforms, material, highlight),
+  }
+}
 
-  color * fragment.intensity
+// El difuso usa fragment.intensity (con sombras y oclusión ambiental) y se apaga con
+// metallic; el metal toma el ambiente con el color de su reflejo
+fn physical_shading(fragment: &Fragment, uniforms: &Uniforms, albedo: Color, pbr: &PbrMaterial) -> Color {
+  let albedo = albedo.to_linear();
+  let f0 = pbr.f0(&albedo);
+  let metallic = pbr.metallic.clamp(0.0, 1.0);
+  let ambient = uniforms.lights.iter().map(|light| light.ambient).fold(0.0, f32::max);
+
+  let specular = pbr_specular(
+    uniforms.lights,
+    &fragment.world_position,
+    &fragment.normal.normalize(),
+    &fragment.view_dir,
+    &f0,
+    pbr.roughness,
+    &fragment.light_visibility,
+  );
+  let diffuse = albedo * ((1.0 - metallic) * fragment.intensity);
+  Color::from_linear(&(diffuse + specular + f0 * (ambient * metallic)))
 }
 
 fn ice_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-  let ripple_pattern = (fragment.vertex_position.x * 8.0 + uniforms.time as f32 * 0.1).sin().abs();
+  let params = &uniforms.params.ice;
+  let ripple_pattern = (fragment.model_position.x * params.ripple_frequency + uniforms.sim_time * params.ripple_speed).sin().abs();
   let intensity = (ripple_pattern * 255.0) as u8;
-  Color::new(0, intensity, 255) * fragment.intensity // Azul agua
+  let albedo = Color::new(0, intensity, 255); // Azul agua
+
+  reflective(fragment, uniforms, albedo, &ICE_MATERIAL, Color::new(255, 255, 255))
 }
 
 fn cloud_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-  let zoom = 100.0;  // Escala del mapa de ruido
-  let ox = 100.0; // Offset en el eje x
-  let oy = 100.0; // Offset en el eje y
+  let params = &uniforms.params.cloud;
+  let zoom = params.zoom; // Escala del mapa de ruido
+  let offset = 100.0; // Separa el ruido de las nubes del del terreno
 
-  // Posición del fragmento en el espacio de coordenadas
-  let x = fragment.vertex_position.x;
-  let y = fragment.vertex_position.y;
+  // Posición del fragmento sobre la esfera unitaria
+  let position = surface_point(fragment);
 
-  // Tiempos diferentes para las nubes y el terreno
-  let cloud_time = uniforms.time as f32 * 0.5;  // Las nubes se mueven a un ritmo
-  let land_time = uniforms.time as f32 * 0.2;   // El terreno se mueve a otro ritmo
+  // Las nubes giran alrededor del eje del planeta; el terreno queda fijo
+  let cloud_position = rotate_y(position, uniforms.sim_time * params.speed);
 
-  // Obtener el valor de ruido para las nubes y el terreno con sus respectivos tiempos
-  let cloud_noise = uniforms.noise.get_noise_2d(x * zoom + ox + cloud_time, y * zoom + oy);
-  let land_noise = uniforms.noise.get_noise_2d(x * zoom + ox + land_time, y * zoom + oy);
+  // Obtener el valor de ruido para las nubes y el terreno
+  let cloud_noise = uniforms.noise.get_noise_3d(
+    cloud_position.x * zoom + offset,
+    cloud_position.y * zoom + offset,
+    cloud_position.z * zoom + offset,
+  );
+  let land_noise = uniforms.noise.get_noise_3d(position.x * zoom, position.y * zoom, position.z * zoom);
 
   // Umbrales de nubes y tierra
-  let cloud_threshold = 0.5;
-  let land_threshold = 0.1;
+  let cloud_threshold = params.cloud_threshold;
+  let land_threshold = params.land_threshold;
 
   // Colores para nubes, cielo y tierra
-  let cloud_color = Color::new(255, 255, 255); // Blanco para nubes
-  let sky_color = Color::new(30, 97, 145);     // Azul para el cielo
-  let land_color = Color::new(0, 100, 0);      // Verde para tierra
+  let cloud_color = params.cloud_color;
+  let sky_color = params.sky_color;
+  let land_color = params.land_color;
 
-  // Decidir el color final basado en los umbrales
-  let final_color = if cloud_noise > cloud_threshold {
-      cloud_color  // Color de nubes
+  // Decidir el color final basado en los umbrales; solo el agua refleja la luz
+  if cloud_noise > cloud_threshold {
+      cloud_color * fragment.intensity  // Color de nubes
   } else if land_noise > land_threshold {
-      land_color   // Color de tierra
+      land_color * fragment.intensity   // Color de tierra
   } else {
-      sky_color    // Color del cielo
+      reflective(fragment, uniforms, sky_color, &WATER_MATERIAL, Color::new(255, 255, 240)) // Color del cielo
+  }
+}
+
+// Nivel del mar del terreno procedural: por debajo la superficie es agua
+pub const SEA_LEVEL: f32 = 0.05;
+
+// Altitud del terreno en un punto de la esfera unitaria. La comparten el shader
+// de la Tierra y el desplazamiento de vértices, así el relieve coincide con los colores.
+pub fn terrain_elevation(noise: &FastNoiseLite, position: Vec3) -> f32 {
+  let zoom = 150.0;
+  let base = fbm(noise, position * zoom, Fractal::new(5));
+
+  // Las crestas solo se suman tierra adentro, donde se forman las cordilleras
+  let ridges = ridged(noise, position * zoom * 2.0 + Vec3::new(900.0, 0.0, 0.0), Fractal::new(3));
+  base + ridges * base.max(0.0) * 0.3
+}
+
+fn earth_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+  let params = &uniforms.params.earth;
+
+  // Posición sobre la esfera unitaria: el patrón queda fijo a la superficie
+  let position = surface_point(fragment);
+  let latitude = position.y.abs();
+
+  // Altitud y humedad a partir de ruido 3D
+  let zoom = 150.0;
+  let elevation = terrain_elevation(uniforms.noise, position);
+  let moisture = fbm(uniforms.noise, position * zoom + Vec3::new(500.0, 500.0, 500.0), Fractal::new(3));
+
+  let sea_level = SEA_LEVEL;
+  let shallow_depth = params.shallow_depth;
+
+  // Casquetes polares, con un borde irregular
+  let ice_line = params.ice_line + moisture * 0.08;
+  if latitude > ice_line {
+    return match uniforms.pbr {
+      Some(pbr) => physical_shading(fragment, uniforms, params.ice_color, &pbr),
+      None => params.ice_color * fragment.intensity
+        + specular_highlight(fragment, uniforms, &ICE_MATERIAL, Color::new(255, 255, 255)) * 0.5,
+    };
+  }
+
+  if elevation < sea_level {
+    // Océano: más claro cerca de la costa
+    let (deep_color, ocean_color, shallow_color) = (params.deep_color, params.ocean_color, params.shallow_color);
+
+    let depth = sea_level - elevation;
+    let water_color = if depth < shallow_depth {
+      ocean_color.lerp(&shallow_color, 1.0 - depth / shallow_depth)
+    } else {
+      deep_color.lerp(&ocean_color, 1.0 - ((depth - shallow_depth) / 0.3).min(1.0))
+    };
+
+    return reflective(fragment, uniforms, water_color, &WATER_MATERIAL, Color::new(255, 255, 240));
+  }
+
+  // Tierra: playa, biomas según latitud y humedad, montañas y nieve en altura
+  let altitude = elevation - sea_level;
+  let land_color = if altitude < 0.02 {
+    params.beach_color
+  } else if altitude > 0.4 {
+    params.snow_color
+  } else if altitude > 0.25 {
+    params.mountain_color.lerp(&params.snow_color, (altitude - 0.25) / 0.15)
+  } else if latitude > 0.65 {
+    params.tundra_color
+  } else if moisture < -0.1 && latitude < 0.45 {
+    params.desert_color
+  } else if moisture > 0.15 {
+    params.forest_color
+  } else {
+    params.grass_color.lerp(&params.forest_color, ((moisture + 0.1) / 0.25).clamp(0.0, 1.0))
   };
 
-  final_color * fragment.intensity
+  // Las ciudades no se construyen en las montañas
+  let night_lights = if uniforms.city_lights && altitude < 0.25 {
+    city_lights(fragment, uniforms, position)
+  } else {
+    Color::black()
+  };
+
+  // Relieve fino de la tierra a partir del gradiente de la altitud
+  let normal = bumped_normal(fragment, uniforms, params.bump, |point| terrain_elevation(uniforms.noise, point));
+  let intensity = diffuse_lighting(uniforms.lights, &fragment.world_position, &normal, &fragment.light_visibility);
+
+  land_color * intensity + night_lights
 }
 
-fn metal_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-  let position = fragment.vertex_position;
-  let normal = fragment.normal.normalize();
+// Luces de ciudades en el lado nocturno: grupos de puntos cálidos que aparecen
+// gradualmente al cruzar el terminador. Son emisivas, no dependen de la iluminación.
+fn city_lights(fragment: &Fragment, uniforms: &Uniforms, position: Vec3) -> Color {
+  let sun_angle = fragment.normal.normalize().dot(&fragment.light_dir);
+  let night = ((0.1 - sun_angle) / 0.3).clamp(0.0, 1.0);
+  if night <= 0.0 {
+    return Color::black();
+  }
+
+  // Regiones pobladas: manchas de ruido de baja frecuencia
+  let region = uniforms.noise.get_noise_3d(
+    position.x * 250.0 + 900.0,
+    position.y * 250.0,
+    position.z * 250.0,
+  );
+  let population = ((region + 0.05) / 0.3).clamp(0.0, 1.0);
+  if population <= 0.0 {
+    return Color::black();
+  }
+
+  // Dentro de cada región, puntos brillantes de alta frecuencia
+  let sparks = uniforms.noise.get_noise_3d(
+    position.x * 3000.0,
+    position.y * 3000.0 + 900.0,
+    position.z * 3000.0,
+  );
+  let glow = ((sparks - 0.2) / 0.4).clamp(0.0, 1.0) * population;
 
-  // Luz direccional
-  let light_dir = Vec3::new(0.5, 0.5, 1.0).normalize();
-  let dot_product = normal.dot(&light_dir).max(0.0);
+  uniforms.params.earth.city_color * (glow * night)
+}
+
+// Capa de nubes translúcida: el alfa sale del ruido, el resto del cielo queda despejado
+fn cloud_layer_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+  let params = &uniforms.params.cloud_layer;
+  let position = surface_point(fragment);
+
+  // Desplazado respecto al terreno para que las nubes no copien los continentes
+  let zoom = params.zoom;
+  let offset = 300.0;
+  // El dominio deformado enrosca las nubes en remolinos
+  let point = domain_warp(
+    uniforms.noise,
+    position * zoom + Vec3::new(offset, offset, offset),
+    params.warp,
+    Fractal::new(2),
+  );
+  let density = fbm(uniforms.noise, point, Fractal::new(4)) * 0.5 + 0.5;
+
+  // Cobertura: por debajo del umbral no hay nubes, luego se vuelven opacas
+  let coverage = ((density - params.coverage) / params.softness).clamp(0.0, 1.0);
+  let alpha = (coverage * params.max_alpha as f32) as u8;
+
+  (params.color * fragment.intensity).with_alpha(alpha)
+}
+
+// Brillo atmosférico (Fresnel): casi invisible de frente y cada vez más opaco
+// donde la vista es tangente a la superficie; solo el lado iluminado brilla
+fn atmosphere_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+  let color = match uniforms.atmosphere {
+    Some(atmosphere) => atmosphere.color,
+    None => Color::new(120, 170, 255),
+  };
+
+  let facing = fragment.normal.normalize().dot(&fragment.view_dir).clamp(0.0, 1.0);
+  let fresnel = (1.0 - facing).powf(3.0);
 
+  // La luz se dispersa un poco más allá del terminador
+  let scattering = (fragment.intensity * 1.5).min(1.0);
+  let alpha = (fresnel * scattering * 255.0).clamp(0.0, 255.0) as u8;
+
+  (color * (0.4 + scattering)).with_alpha(alpha)
+}
+
+// Superficie rocosa con cráteres sobre el ruido cellular propio del cuerpo: cada
+// celda es un cráter, la frecuencia controla la densidad y la semilla su ubicación.
+fn moon_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+  let params = &uniforms.params.moon;
+  let position = surface_point(fragment);
+
+  // Mares: manchas oscuras amplias con variación suave
+  let mare = fbm(crater_noise(uniforms), position * params.mare_zoom + Vec3::new(500.0, 0.0, 0.0), Fractal::new(3));
+  let base_color = sample_ramp(ramp(uniforms, params.ramp.colors()), (mare + 0.3) * 1.5);
+
+  let relief = 1.0 + moon_relief(uniforms, position);
+  base_color * (relief * fragment.intensity)
+}
+
+// Cráteres grandes y una segunda capa de cráteres pequeños
+fn moon_relief(uniforms: &Uniforms, position: Vec3) -> f32 {
+  let noise = crater_noise(uniforms);
+  let zoom = uniforms.params.moon.zoom;
+  let large = crater_profile(cell_distance(noise, position * zoom));
+  let small = crater_profile(cell_distance(noise, position * zoom * 3.0 + Vec3::new(71.0, 13.0, 37.0)));
+  large + small * 0.5
+}
+
+// El ruido cellular del cuerpo, que no depende del tipo de ruido configurado
+fn crater_noise<'a>(uniforms: &Uniforms<'a>) -> &'a FastNoiseLite {
+  uniforms.craters.unwrap_or(uniforms.noise)
+}
+
+// Distancia al centro de la celda más cercana (el ruido cellular devuelve d² - 1)
+fn cell_distance(noise: &FastNoiseLite, point: Vec3) -> f32 {
+  (noise.get_noise_3d(point.x, point.y, point.z) + 1.0).clamp(0.0, 1.0).sqrt()
+}
+
+// Perfil radial del cráter: fondo oscuro en forma de cuenco y borde elevado y brillante
+fn crater_profile(distance: f32) -> f32 {
+  let floor_radius = 0.35;
+  let rim_radius = 0.5;
+
+  if distance < floor_radius {
+    -0.35 * (1.0 - (distance / floor_radius).powi(2))
+  } else if distance < rim_radius {
+    let rim_center = (floor_radius + rim_radius) * 0.5;
+    let half_width = (rim_radius - floor_radius) * 0.5;
+    0.3 * (1.0 - ((distance - rim_center) / half_width).abs())
+  } else {
+    0.0
+  }
+}
+
+fn metal_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
   // Colores base
   let base_color = Color::new(100, 100, 120); // Gris metálico
   let highlight_color = Color::new(220, 220, 255); // Azul brillante
 
-  // Mezclar en función del ángulo con la luz
-  base_color.lerp(&highlight_color, dot_product) * fragment.intensity
+  // Difuso tenue y un reflejo intenso teñido del color del metal. Con PBR el color
+  // pasa al del reflejo a medida que el material es más metálico.
+  let albedo = match uniforms.pbr {
+    Some(pbr) => base_color.lerp(&highlight_color, pbr.metallic.clamp(0.0, 1.0)),
+    None => base_color,
+  };
+  reflective(fragment, uniforms, albedo, &METAL_MATERIAL, highlight_color)
 }
 
 
-fn jupiter_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-  let zoom = 100.0;  // to move our values 
-  let x = fragment.vertex_position.x;
-  let y = fragment.vertex_position.y;
-
-  let band_noise = uniforms.noise.get_noise_2d(y * zoom, 0.0);// Desplazamiento para el movimiento de bandas
-
-  // Definir colores para las diferentes bandas de gas
-  let dark_brown = Color::new(139, 69, 19);
-  let light_brown = Color::new(205, 133, 63);
-  let orange = Color::new(255, 165, 0);
-  let beige = Color::new(245, 222, 179);
-
-  // Crear bandas con variación de color usando `band_noise`
-  let band_color = if band_noise > 0.6 {
-      dark_brown
-  } else if band_noise > 0.3 {
-      beige
-  } else if band_noise > 0.0 {
-      orange
-  } else {
-      light_brown
+// Difuso con el color (o la textura) del material y el reflejo de su Ks y Ns, o su
+// metálico-rugoso si lo trae (glTF) o si el cuerpo tiene uno
+fn material_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+  let Some(material) = uniforms.material else {
+    return Color::new(255, 0, 255);
   };
 
-  // Agregar una "mancha" similar a la Gran Mancha Roja
-  let storm_position = Vec2::new(0.3, -0.3); // Posición en el planeta
-  let storm_radius = 0.15; // Tamaño de la tormenta
-  let distance_to_storm = ((x - storm_position.x).powi(2) + (y - storm_position.y).powi(2)).sqrt();
+  let mut albedo = material.albedo(fragment.tex_coords);
+  if material.vertex_colors {
+    albedo = albedo.blend_multiply(&fragment.color);
+  }
+  match material.pbr.or(uniforms.pbr) {
+    Some(pbr) => physical_shading(fragment, uniforms, albedo, &pbr),
+    None => albedo * fragment.intensity + specular_highlight(fragment, uniforms, &material.specular, Color::new(255, 255, 255)),
+  }
+}
 
-  let storm_color = Color::new(255, 69, 0); // Rojo anaranjado brillante para la tormenta
-  let final_color = if distance_to_storm < storm_radius {
-      storm_color // La región de la tormenta
+fn textured_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+  // Sin textura asignada se pinta de magenta para que el error sea evidente
+  let albedo = match uniforms.texture {
+    Some(texture) => texture.sample_bilinear(fragment.tex_coords.x, fragment.tex_coords.y),
+    None => Color::new(255, 0, 255),
+  };
+
+  albedo * fragment.intensity
+}
+
+fn jupiter_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
+  let params = &uniforms.params.jupiter;
+  let position = surface_point(fragment);
+  let t = uniforms.sim_time;
+
+  // Coordenadas esféricas del fragmento
+  let latitude = position.y.clamp(-1.0, 1.0).asin();
+  let longitude = (-position.z).atan2(position.x);
+
+  // Cada banda deriva en longitud a su propia velocidad (vientos zonales alternos)
+  let drift = (latitude * 6.0).sin() * t * params.drift_speed;
+  let drifted = rotate_y(position, drift);
+
+  // Turbulencia: la latitud se deforma con ruido antes de elegir la banda
+  let zoom = params.zoom;
+  let warp = fbm(
+    uniforms.noise,
+    Vec3::new(drifted.x * zoom, drifted.y * zoom * 0.3, drifted.z * zoom),
+    Fractal::new(4),
+  );
+  let band_coord = latitude + warp * params.turbulence;
+  let band_noise = uniforms.noise.get_noise_2d(band_coord * params.band_frequency, 0.0);
+
+  // Gradiente entre bandas según `band_noise`, sin bordes duros
+  let band_color = sample_ramp(ramp(uniforms, params.ramp.colors()), (band_noise + params.band_offset) / params.band_range);
+
+  // Gran Mancha Roja: vórtice elíptico que migra lentamente hacia el oeste
+  let storm_latitude = params.storm_latitude;
+  let storm_longitude = params.storm_longitude - t * params.storm_speed;
+  let storm_width = params.storm_width;   // Semieje en longitud (radianes)
+  let storm_height = params.storm_height; // Semieje en latitud (radianes)
+
+  let mut delta_longitude = (longitude - storm_longitude) % (2.0 * PI);
+  if delta_longitude > PI {
+    delta_longitude -= 2.0 * PI;
+  } else if delta_longitude < -PI {
+    delta_longitude += 2.0 * PI;
+  }
+  let dx = delta_longitude * latitude.cos() / storm_width;
+  let dy = (latitude - storm_latitude) / storm_height;
+  let storm_distance = (dx * dx + dy * dy).sqrt();
+
+  let final_color = if storm_distance < 1.0 {
+    // Remolino: el ruido se muestrea girando más rápido cerca del centro
+    let swirl = (1.0 - storm_distance) * 4.0 + t * 0.3;
+    let (sin, cos) = swirl.sin_cos();
+    let sx = dx * cos - dy * sin;
+    let sy = dx * sin + dy * cos;
+    let swirl_noise = uniforms.noise.get_noise_2d(sx * 80.0, sy * 80.0) * 0.5 + 0.5;
+
+    let storm_color = params.storm_edge.lerp(&params.storm_core, swirl_noise * (1.0 - storm_distance));
+
+    // Borde suave hacia las bandas que lo rodean
+    band_color.lerp(&storm_color, ((1.0 - storm_distance) / 0.2).min(1.0))
   } else {
-      band_color // Colores de bandas para el resto
+    band_color
   };
 
   final_color * fragment.intensity
 }
 
 fn ring_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-  let x = fragment.vertex_position.x;
-  let y = fragment.vertex_position.y;
-  let z = fragment.vertex_position.z;
+  let params = &uniforms.params.ring;
 
-  // Coordenadas polares
-  let distance = (x.powi(2) + y.powi(2)).sqrt();
-  let angle = y.atan2(x);
+  // El anillo es una malla plana en el plano XZ del modelo
+  let x = fragment.model_position.x;
+  let z = fragment.model_position.z;
+
+  // Distancia radial al centro del planeta
+  let distance = (x.powi(2) + z.powi(2)).sqrt();
 
   // Parámetros del anillo
-  let ring_width = 0.02; // Ancho del anillo
-  let ring_spacing = 0.08; // Espaciado entre anillos
+  let ring_width = params.width; // Ancho del anillo
+  let ring_spacing = params.spacing; // Espaciado entre anillos
 
   // Crear patrón de anillos
   let ring_pattern = ((distance % ring_spacing) / ring_width).abs();
   let ring_intensity = if ring_pattern < 1.0 { 1.0 - ring_pattern } else { 0.0 };
 
-  // Definir colores del anillo y del planeta
-  let ring_color = Color::new(200, 200, 200); // Gris para los anillos
-  let planet_color = Color::new(100, 50, 200); // Morado para el planeta
+  // Variación fina de densidad según el radio, fija al anillo
+  let grain = uniforms.noise.get_noise_2d(distance * 2000.0, 0.0) * 0.5 + 0.5;
+  let ring_intensity = ring_intensity * (0.6 + 0.4 * grain);
+
+  // Definir colores de las bandas y de los huecos entre ellas
+  let ring_color = params.ring_color; // Gris para los anillos
+  let gap_color = params.gap_color; // Morado para los huecos
 
-  // Interpolar entre el color del planeta y el de los anillos
-  ring_color.lerp(&planet_color, 1.0 - ring_intensity) * fragment.intensity
+  // Los huecos entre bandas son casi transparentes
+  let alpha = ((0.15 + 0.85 * ring_intensity) * 255.0) as u8;
+
+  // Interpolar entre el color de los huecos y el de las bandas
+  (ring_color.lerp(&gap_color, 1.0 - ring_intensity) * fragment.intensity).with_alpha(alpha)
 }
 
 
 fn moving_circles_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
-    let x = fragment.vertex_position.x;
-    let y = fragment.vertex_position.y;
+    let x = fragment.model_position.x;
+    let y = fragment.model_position.y;
   
-    let time = uniforms.time as f32 * 0.05;
+    let time = uniforms.sim_time * 3.0;
     let circle1_x = (time.sin() * 0.4 + 0.5) % 1.0;
     let circle2_x = (time.cos() * 0.4 + 0.5) % 1.0;
   
@@ -265,4 +782,71 @@ pub fn combined_shader(fragment: &Fragment, uniforms: &Uniforms) -> Color {
     } else {
       base_color * fragment.intensity
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use nalgebra_glm::Vec2;
+  use rand::rngs::StdRng;
+  use rand::{Rng, SeedableRng};
+  use crate::renderer::{create_model_matrix, create_perspective_matrix, create_view_matrix, create_viewport_matrix};
+  use crate::shader_params::ShaderParams;
+  use crate::spectrum::AudioLevels;
+
+  fn assert_close(batch: &[f32], scalar: &[f32], what: &str, index: usize) {
+    for (b, s) in batch.iter().zip(scalar) {
+      assert!((b - s).abs() <= 1e-4 * s.abs().max(1.0), "{} del vértice {}: {:?} != {:?}", what, index, batch, scalar);
+    }
+  }
+
+  #[test]
+  fn batch_matches_scalar_vertex_shader() {
+    let mut rng = StdRng::seed_from_u64(814);
+    let random_vec3 = |rng: &mut StdRng| Vec3::new(rng.gen_range(-2.0..2.0), rng.gen_range(-2.0..2.0), rng.gen_range(-2.0..2.0));
+    // Dos lotes completos y uno a medias, que pasa por el camino escalar
+    let vertices: Vec<Vertex> = (0..VERTEX_LANES * 2 + 3)
+      .map(|_| {
+        let position = random_vec3(&mut rng);
+        let normal = random_vec3(&mut rng).normalize();
+        Vertex::new(position, normal, Vec2::new(rng.gen(), rng.gen()))
+      })
+      .collect();
+
+    let noise = FastNoiseLite::new();
+    let params = ShaderParams::default();
+    let uniforms = Uniforms {
+      model_matrix: create_model_matrix(Vec3::new(3.0, -1.0, 2.0), 1.5, Vec3::new(0.3, 1.2, -0.4)),
+      view_matrix: create_view_matrix(Vec3::new(0.0, 4.0, 20.0), Vec3::zeros(), Vec3::y()),
+      projection_matrix: create_perspective_matrix(800.0, 600.0),
+      viewport_matrix: create_viewport_matrix(800.0, 600.0),
+      camera_position: Vec3::new(0.0, 4.0, 20.0),
+      lights: &[],
+      occluders: &[],
+      sim_time: 0.0,
+      noise: &noise,
+      craters: None,
+      texture: None,
+      atmosphere: None,
+      city_lights: false,
+      twilight: None,
+      tint: None,
+      palette: None,
+      fog: None,
+      script: None,
+      params: &params,
+      material: None,
+      pbr: None,
+      music: AudioLevels::default(),
+    };
+
+    let mut batch = vertices.clone();
+    vertex_shader_batch(&vertices, &uniforms, &mut batch);
+    for (index, (vertex, batched)) in vertices.iter().zip(&batch).enumerate() {
+      let scalar = vertex_shader(vertex, &uniforms);
+      assert_close(batched.clip_position.as_slice(), scalar.clip_position.as_slice(), "clip_position", index);
+      assert_close(batched.world_position.as_slice(), scalar.world_position.as_slice(), "world_position", index);
+      assert_close(batched.transformed_position.as_slice(), scalar.transformed_position.as_slice(), "transformed_position", index);
+      assert_close(batched.transformed_normal.as_slice(), scalar.transformed_normal.as_slice(), "transformed_normal", index);
+    }
+  }
+}
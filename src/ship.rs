@@ -0,0 +1,195 @@
+use fastnoise_lite::FastNoiseLite;
+use nalgebra_glm::Vec3;
+use serde::Deserialize;
+use std::sync::Arc;
+use crate::billboard::BlendMode;
+use crate::camera::Camera;
+use crate::collision::push_out_of_planets;
+use crate::lighting::PbrMaterial;
+use crate::color::Color;
+use crate::particles::{EmitterConfig, ParticleSystem};
+use crate::planet::Planet;
+use crate::obj::Model;
+
+// Aceleración del motor y freno por rozamiento (la velocidad cae a 1/e en 1/DRAG segundos)
+const ACCELERATION: f32 = 6.0;
+const DRAG: f32 = 0.8;
+const TURN_RATE: f32 = 1.5;
+const MAX_PITCH: f32 = 1.4;
+// Distancia del centro del modelo a la punta más lejana (la nariz), antes de escalar
+const HULL_RADIUS: f32 = 1.8;
+// Distancia del centro a las toberas, en la cola del modelo, antes de escalar
+const ENGINE_OFFSET: f32 = 1.15;
+// Velocidad contra la superficie a partir de la cual un choque levanta restos
+const IMPACT_SPEED: f32 = 1.0;
+
+// La cámara va detrás y un poco arriba de la nave, y se acerca a esa posición
+// con un resorte para que los giros se sientan suaves
+const CHASE_DISTANCE: f32 = 3.0;
+const CHASE_HEIGHT: f32 = 0.8;
+const CHASE_LOOK_AHEAD: f32 = 2.0;
+const CHASE_STIFFNESS: f32 = 6.0;
+
+// Modelo de la nave y punto de partida en la escena
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ShipConfig {
+    pub model: String,
+    pub scale: f32,
+    pub position: [f32; 3],
+    // Metálico-rugoso del casco; los materiales glTF traen el suyo
+    pub pbr: Option<PbrMaterial>,
+}
+
+impl Default for ShipConfig {
+    fn default() -> Self {
+        ShipConfig {
+            model: "assets/models/ship.obj".to_string(),
+            scale: 0.15,
+            position: [0.0, 2.0, 22.0],
+            pbr: None,
+        }
+    }
+}
+
+// Mandos del piloto: thrust y turn van de -1 a 1
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ShipControls {
+    pub thrust: f32,
+    pub turn: f32,
+}
+
+pub struct Ship {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub scale: f32,
+    pub controls: ShipControls,
+    // Modelo separado por materiales
+    pub model: Arc<Model>,
+    pub pbr: Option<PbrMaterial>,
+    pub noise: FastNoiseLite,
+    // Escape del motor mientras acelera y restos de los choques
+    pub exhaust: ParticleSystem,
+    pub debris: ParticleSystem,
+}
+
+impl Ship {
+    pub fn new(config: &ShipConfig, model: Arc<Model>) -> Self {
+        let [x, y, z] = config.position;
+        Ship {
+            position: Vec3::new(x, y, z),
+            velocity: Vec3::zeros(),
+            // Mirando hacia el Sol
+            yaw: std::f32::consts::PI,
+            pitch: 0.0,
+            scale: config.scale,
+            controls: ShipControls::default(),
+            model,
+            pbr: config.pbr,
+            noise: FastNoiseLite::new(),
+            exhaust: ParticleSystem::new(exhaust_config(config.scale), 1),
+            debris: ParticleSystem::new(debris_config(config.scale), 2),
+        }
+    }
+
+    // La nariz del modelo apunta a +z; es el mismo orden de giros que create_model_matrix
+    pub fn forward(&self) -> Vec3 {
+        Vec3::new(
+            self.yaw.sin() * self.pitch.cos(),
+            -self.pitch.sin(),
+            self.yaw.cos() * self.pitch.cos(),
+        )
+    }
+
+    pub fn rotation(&self) -> Vec3 {
+        Vec3::new(self.pitch, self.yaw, 0.0)
+    }
+
+    // Giro directo, por ejemplo con el mouse
+    pub fn steer(&mut self, yaw: f32, pitch: f32) {
+        self.yaw += yaw;
+        self.pitch = (self.pitch + pitch).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.yaw -= self.controls.turn * TURN_RATE * dt;
+
+        self.velocity += self.forward() * self.controls.thrust * ACCELERATION * dt;
+        self.velocity *= (-DRAG * dt).exp();
+        self.position += self.velocity * dt;
+
+        self.exhaust.update(dt);
+        self.debris.update(dt);
+        if self.controls.thrust > 0.0 {
+            let forward = self.forward();
+            let engine = self.position - forward * ENGINE_OFFSET * self.scale;
+            self.exhaust.emit(dt, engine, -forward, self.scale * 0.1, self.controls.thrust);
+        }
+    }
+
+    // Si la nave entra en un planeta se la saca a la superficie y se descarta la
+    // parte de la velocidad que apunta hacia adentro, así se desliza sobre él
+    pub fn collide(&mut self, planets: &[Planet]) {
+        if let Some(normal) = push_out_of_planets(&mut self.position, self.scale * HULL_RADIUS, planets) {
+            let into = self.velocity.dot(&normal);
+            if into < 0.0 {
+                self.velocity -= normal * into;
+            }
+            // Los restos saltan del punto de contacto, más cuanto más fuerte el golpe
+            if into < -IMPACT_SPEED {
+                let contact = self.position - normal * self.scale * HULL_RADIUS;
+                self.debris.burst((-into * 15.0).min(60.0) as usize, contact, normal, self.scale * 0.3);
+            }
+        }
+    }
+
+    // Cámara de persecución: detrás de la nave, mirando un poco por delante de ella
+    pub fn chase(&self, camera: &mut Camera, dt: f32) {
+        let forward = self.forward();
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        let target_eye = self.position - forward * CHASE_DISTANCE + up * CHASE_HEIGHT;
+        let t = 1.0 - (-CHASE_STIFFNESS * dt).exp();
+
+        camera.eye += (target_eye - camera.eye) * t;
+        camera.center = self.position + forward * CHASE_LOOK_AHEAD;
+        camera.up = up;
+        camera.has_changed = true;
+    }
+}
+
+// Llama del motor: chispas cortas que suman luz y pasan de amarillo a rojo
+fn exhaust_config(scale: f32) -> EmitterConfig {
+    EmitterConfig {
+        rate: 150.0,
+        lifetime: 0.4,
+        speed: 2.0,
+        spread: 0.15,
+        drag: 2.0,
+        start_size: scale * 0.4,
+        end_size: scale * 1.2,
+        start_color: Color::new_rgba(255, 210, 130, 200),
+        end_color: Color::new_rgba(255, 70, 20, 0),
+        intensity: 3.0,
+        blend: BlendMode::Additive,
+        max_particles: 400,
+        ..EmitterConfig::default()
+    }
+}
+
+// Polvo y piedras que salen hacia todos lados desde la superficie
+fn debris_config(scale: f32) -> EmitterConfig {
+    EmitterConfig {
+        lifetime: 1.5,
+        speed: 1.5,
+        spread: 1.0,
+        drag: 1.0,
+        start_size: scale * 0.3,
+        end_size: scale * 0.2,
+        start_color: Color::new_rgba(150, 140, 130, 255),
+        end_color: Color::new_rgba(90, 80, 70, 0),
+        max_particles: 300,
+        ..EmitterConfig::default()
+    }
+}
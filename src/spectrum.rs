@@ -0,0 +1,272 @@
+use rodio::{Sample, Source};
+use std::collections::VecDeque;
+use std::f32::consts::TAU;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// Muestras de cada análisis (potencia de dos para la FFT): a 44,1 kHz son unos 23 ms
+const WINDOW: usize = 1024;
+// La música pasa las muestras al análisis de a tandas, para no bloquear por cada una
+const CHUNK: usize = 256;
+pub const BANDS: usize = 4;
+// Límites de las bandas en Hz: graves, medios graves, medios agudos y agudos
+const BAND_EDGES: [f32; BANDS + 1] = [20.0, 150.0, 600.0, 2500.0, 10000.0];
+const ANALYSIS_INTERVAL: Duration = Duration::from_millis(15);
+// Sin muestras nuevas durante este tiempo la música se considera detenida
+const SILENCE_AFTER: Duration = Duration::from_millis(100);
+// Llevan los valores típicos de una canción a un rango de 0 a 1
+const AMPLITUDE_GAIN: f32 = 3.0;
+const BAND_GAIN: f32 = 2.0;
+// Suavizado entre análisis: los niveles suben rápido con un golpe y bajan despacio
+const ATTACK: f32 = 0.6;
+const RELEASE: f32 = 0.1;
+
+// Cuánto suena la música en este momento, de 0 a 1: el volumen en general y la
+// energía en cada banda de frecuencias, de la más grave a la más aguda. No depende
+// del volumen elegido con F7/F8.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AudioLevels {
+    pub amplitude: f32,
+    pub bands: [f32; BANDS],
+}
+
+impl AudioLevels {
+    pub fn bass(&self) -> f32 {
+        self.bands[0]
+    }
+}
+
+// Últimas muestras que sonaron, mezcladas a mono
+struct Recent {
+    samples: VecDeque<f32>,
+    sample_rate: u32,
+    updated: Instant,
+}
+
+// Análisis de la música en un hilo aparte: la fuente que suena pasa por un Tap que
+// copia sus muestras, y el hilo calcula los niveles cada ANALYSIS_INTERVAL. El hilo
+// termina al soltar el analizador.
+pub struct Analyzer {
+    recent: Arc<Mutex<Recent>>,
+    levels: Arc<Mutex<AudioLevels>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Analyzer {
+    pub fn new() -> Self {
+        let recent = Arc::new(Mutex::new(Recent {
+            samples: VecDeque::with_capacity(WINDOW),
+            sample_rate: 44_100,
+            updated: Instant::now(),
+        }));
+        let levels = Arc::new(Mutex::new(AudioLevels::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let (recent, levels, stop) = (Arc::clone(&recent), Arc::clone(&levels), Arc::clone(&stop));
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    thread::sleep(ANALYSIS_INTERVAL);
+                    let target = {
+                        let recent = recent.lock().unwrap();
+                        if recent.samples.len() < WINDOW || recent.updated.elapsed() > SILENCE_AFTER {
+                            AudioLevels::default()
+                        } else {
+                            let samples: Vec<f32> = recent.samples.iter().copied().collect();
+                            analyze(&samples, recent.sample_rate)
+                        }
+                    };
+                    let mut levels = levels.lock().unwrap();
+                    *levels = smooth(&levels, &target);
+                }
+            })
+        };
+
+        Analyzer { recent, levels, stop, thread: Some(thread) }
+    }
+
+    // Envuelve una fuente para que lo que suena llegue al análisis
+    pub fn tap<S>(&self, source: S) -> Tap<S>
+    where
+        S: Source,
+        S::Item: Sample,
+    {
+        Tap {
+            inner: source,
+            recent: Arc::clone(&self.recent),
+            chunk: Vec::with_capacity(CHUNK),
+            frame: 0.0,
+            channel: 0,
+        }
+    }
+
+    pub fn levels(&self) -> AudioLevels {
+        *self.levels.lock().unwrap()
+    }
+}
+
+impl Default for Analyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Analyzer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// Fuente que deja pasar las muestras sin tocarlas y copia cada cuadro, promediado
+// entre los canales, para el analizador
+pub struct Tap<S> {
+    inner: S,
+    recent: Arc<Mutex<Recent>>,
+    chunk: Vec<f32>,
+    frame: f32,
+    channel: u16,
+}
+
+impl<S> Tap<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    fn flush(&mut self) {
+        let mut recent = self.recent.lock().unwrap();
+        recent.samples.extend(self.chunk.drain(..));
+        let excess = recent.samples.len().saturating_sub(WINDOW);
+        recent.samples.drain(..excess);
+        recent.sample_rate = self.inner.sample_rate();
+        recent.updated = Instant::now();
+    }
+}
+
+impl<S> Iterator for Tap<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        let sample = self.inner.next()?;
+        let channels = self.inner.channels().max(1);
+        self.frame += sample.to_f32();
+        self.channel += 1;
+        if self.channel >= channels {
+            self.chunk.push(self.frame / channels as f32);
+            self.frame = 0.0;
+            self.channel = 0;
+            if self.chunk.len() >= CHUNK {
+                self.flush();
+            }
+        }
+        Some(sample)
+    }
+}
+
+impl<S> Source for Tap<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+// Volumen (RMS) de la ventana y energía de cada banda según su FFT
+fn analyze(samples: &[f32], sample_rate: u32) -> AudioLevels {
+    let rms = (samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32).sqrt();
+
+    // Ventana de Hann, para que los bordes de la ventana no ensucien el espectro
+    let count = samples.len();
+    let mut real: Vec<f32> = samples
+        .iter()
+        .enumerate()
+        .map(|(index, sample)| sample * (0.5 - 0.5 * (TAU * index as f32 / count as f32).cos()))
+        .collect();
+    let mut imaginary = vec![0.0; count];
+    fft(&mut real, &mut imaginary);
+
+    // Con la ventana, un seno de amplitud A da un pico de A * count / 4
+    let scale = 4.0 / count as f32;
+    let bin_width = sample_rate as f32 / count as f32;
+    let mut bands = [0.0; BANDS];
+    for (band, level) in bands.iter_mut().enumerate() {
+        let first = ((BAND_EDGES[band] / bin_width) as usize).max(1);
+        let last = ((BAND_EDGES[band + 1] / bin_width) as usize).min(count / 2);
+        let energy: f32 = (first..last)
+            .map(|bin| (real[bin] * real[bin] + imaginary[bin] * imaginary[bin]) * scale * scale)
+            .sum();
+        *level = (energy.sqrt() * BAND_GAIN).min(1.0);
+    }
+
+    AudioLevels { amplitude: (rms * AMPLITUDE_GAIN).min(1.0), bands }
+}
+
+fn smooth(current: &AudioLevels, target: &AudioLevels) -> AudioLevels {
+    let step = |from: f32, to: f32| from + (to - from) * if to > from { ATTACK } else { RELEASE };
+    let mut bands = current.bands;
+    for (band, target) in bands.iter_mut().zip(target.bands) {
+        *band = step(*band, target);
+    }
+    AudioLevels { amplitude: step(current.amplitude, target.amplitude), bands }
+}
+
+// FFT iterativa de Cooley-Tukey, en el lugar; el largo tiene que ser potencia de dos
+fn fft(real: &mut [f32], imaginary: &mut [f32]) {
+    let count = real.len();
+
+    // Reordena por índices con los bits invertidos
+    let mut j = 0;
+    for i in 1..count {
+        let mut bit = count >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            real.swap(i, j);
+            imaginary.swap(i, j);
+        }
+    }
+
+    let mut length = 2;
+    while length <= count {
+        let angle = -TAU / length as f32;
+        for start in (0..count).step_by(length) {
+            for k in 0..length / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + length / 2);
+                let twiddled_real = real[b] * cos - imaginary[b] * sin;
+                let twiddled_imaginary = real[b] * sin + imaginary[b] * cos;
+                real[b] = real[a] - twiddled_real;
+                imaginary[b] = imaginary[a] - twiddled_imaginary;
+                real[a] += twiddled_real;
+                imaginary[a] += twiddled_imaginary;
+            }
+        }
+        length <<= 1;
+    }
+}
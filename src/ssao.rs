@@ -0,0 +1,122 @@
+use nalgebra_glm::Vec3;
+use rayon::prelude::*;
+use crate::gbuffer::GBuffer;
+
+// Radio de la búsqueda de oclusión en unidades de la escena: del orden del relieve
+// del terreno desplazado y de las rocas del cinturón
+const SSAO_RADIUS: f32 = 0.05;
+const SSAO_SAMPLES: usize = 8;
+// Con menos de esto en pantalla no hay relieve que oscurecer
+const SSAO_MIN_PIXELS: f32 = 1.5;
+const SSAO_MAX_PIXELS: f32 = 32.0;
+// Las muestras casi en el plano de la superficie no ocluyen (evita que las
+// caras planas de la malla se oscurezcan a sí mismas)
+const SSAO_BIAS: f32 = 0.03;
+const SSAO_STRENGTH: f32 = 2.5;
+// Giro del patrón de muestras en bloques de 4x4, que el desenfoque promedia después
+const SSAO_ROTATIONS: [[u8; 4]; 4] = [
+    [0, 8, 2, 10],
+    [12, 4, 14, 6],
+    [3, 11, 1, 9],
+    [15, 7, 13, 5],
+];
+const GOLDEN_ANGLE: f32 = 2.399_963;
+
+// Oclusión ambiental en espacio de pantalla a partir del G-buffer: cada píxel mira
+// las superficies vecinas en un disco de radio fijo en el mundo y se oscurece según
+// cuántas quedan por encima de su plano (grietas, cráteres, rocas que se tocan).
+// El resultado (1 = sin oclusión) se suaviza con un desenfoque de 4x4.
+pub struct Ssao {
+    raw: Vec<f32>,
+    occlusion: Vec<f32>,
+}
+
+impl Default for Ssao {
+    fn default() -> Self {
+        Ssao::new()
+    }
+}
+
+impl Ssao {
+    pub fn new() -> Self {
+        Ssao {
+            raw: Vec::new(),
+            occlusion: Vec::new(),
+        }
+    }
+
+    // pixels_per_unit: tamaño en píxeles de una unidad a distancia 1 de la cámara
+    pub fn compute(&mut self, gbuffer: &GBuffer, camera_position: &Vec3, pixels_per_unit: f32) -> &[f32] {
+        let (width, height) = (gbuffer.width, gbuffer.height);
+        self.raw.resize(width * height, 1.0);
+        self.occlusion.resize(width * height, 1.0);
+
+        self.raw.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+            for (x, value) in row.iter_mut().enumerate() {
+                *value = match gbuffer.surface(x, y) {
+                    Some(surface) => {
+                        let distance = (surface.world_position - camera_position).magnitude();
+                        let radius = SSAO_RADIUS * pixels_per_unit / distance.max(f32::EPSILON);
+                        if radius < SSAO_MIN_PIXELS {
+                            1.0
+                        } else {
+                            let rotation = SSAO_ROTATIONS[y % 4][x % 4] as f32 / 16.0 * std::f32::consts::TAU;
+                            occlusion_at(gbuffer, x, y, &surface.world_position, &surface.normal, radius.min(SSAO_MAX_PIXELS), rotation)
+                        }
+                    }
+                    None => 1.0,
+                };
+            }
+        });
+
+        // Desenfoque de caja del tamaño del patrón, solo entre píxeles con superficie
+        let raw = &self.raw;
+        self.occlusion.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+            for (x, value) in row.iter_mut().enumerate() {
+                if gbuffer.surface(x, y).is_none() {
+                    *value = 1.0;
+                    continue;
+                }
+
+                let (mut sum, mut count) = (0.0, 0.0);
+                for sy in y.saturating_sub(1)..(y + 3).min(height) {
+                    for sx in x.saturating_sub(1)..(x + 3).min(width) {
+                        if gbuffer.surface(sx, sy).is_some() {
+                            sum += raw[sy * width + sx];
+                            count += 1.0;
+                        }
+                    }
+                }
+                *value = sum / count;
+            }
+        });
+
+        &self.occlusion
+    }
+}
+
+// Fracción de luz ambiental que llega a un punto, muestreando en espiral dentro de
+// un disco de radius píxeles
+fn occlusion_at(gbuffer: &GBuffer, x: usize, y: usize, position: &Vec3, normal: &Vec3, radius: f32, rotation: f32) -> f32 {
+    let mut occlusion = 0.0;
+    for i in 0..SSAO_SAMPLES {
+        let t = (i as f32 + 0.5) / SSAO_SAMPLES as f32;
+        let (sin, cos) = (i as f32 * GOLDEN_ANGLE + rotation).sin_cos();
+        let sx = x as f32 + cos * radius * t.sqrt();
+        let sy = y as f32 + sin * radius * t.sqrt();
+        if sx < 0.0 || sy < 0.0 {
+            continue;
+        }
+
+        if let Some(sample) = gbuffer.surface(sx as usize, sy as usize) {
+            let offset = sample.world_position - position;
+            let distance = offset.magnitude();
+            if distance <= f32::EPSILON || distance > SSAO_RADIUS * 2.0 {
+                continue;
+            }
+            let falloff = 1.0 - distance / (SSAO_RADIUS * 2.0);
+            occlusion += (normal.dot(&(offset / distance)) - SSAO_BIAS).max(0.0) * falloff;
+        }
+    }
+    (1.0 - SSAO_STRENGTH * occlusion / SSAO_SAMPLES as f32).clamp(0.0, 1.0)
+}
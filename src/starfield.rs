@@ -0,0 +1,111 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+use crate::color::Color;
+use crate::framebuffer::HdrFramebuffer;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct StarfieldConfig {
+    pub seed: u64,
+    pub count: usize,
+}
+
+impl Default for StarfieldConfig {
+    fn default() -> Self {
+        StarfieldConfig {
+            seed: 2024,
+            count: 3000,
+        }
+    }
+}
+
+pub struct Star {
+    pub direction: Vec3,
+    pub brightness: f32,
+    pub color: Color,
+}
+
+pub struct Starfield {
+    stars: Vec<Star>,
+}
+
+impl Starfield {
+    pub fn generate(config: &StarfieldConfig) -> Self {
+        let mut rng = StdRng::seed_from_u64(config.seed);
+
+        let stars = (0..config.count)
+            .map(|_| {
+                // Dirección uniforme sobre la esfera
+                let z: f32 = rng.gen_range(-1.0..1.0);
+                let angle: f32 = rng.gen_range(0.0..std::f32::consts::TAU);
+                let r = (1.0 - z * z).sqrt();
+                let direction = Vec3::new(r * angle.cos(), r * angle.sin(), z);
+
+                // Muchas estrellas tenues y pocas brillantes
+                let brightness = rng.gen_range(0.0f32..1.0).powi(3) * 0.85 + 0.15;
+                let temperature = rng.gen_range(3000.0..12000.0);
+
+                Star {
+                    direction,
+                    brightness,
+                    color: temperature_to_color(temperature),
+                }
+            })
+            .collect();
+
+        Starfield { stars }
+    }
+
+    // Dibuja las estrellas como fondo, antes de la geometría. Al estar en el
+    // infinito solo les afecta la rotación de la cámara, no su posición.
+    pub fn render(&self, framebuffer: &mut HdrFramebuffer, view_matrix: &Mat4, projection_matrix: &Mat4, viewport_matrix: &Mat4) {
+        let view_projection = projection_matrix * view_matrix;
+
+        for star in &self.stars {
+            let d = star.direction;
+            let clip = view_projection * Vec4::new(d.x, d.y, d.z, 0.0);
+            if clip.w <= 0.0 {
+                continue;
+            }
+
+            let ndc = Vec4::new(clip.x / clip.w, clip.y / clip.w, 0.0, 1.0);
+            if ndc.x.abs() > 1.0 || ndc.y.abs() > 1.0 {
+                continue;
+            }
+
+            let screen = viewport_matrix * ndc;
+            let (x, y) = (screen.x as usize, screen.y as usize);
+            let color = (star.color * star.brightness).to_linear();
+
+            framebuffer.set_pixel(x, y, color);
+
+            // Las más brillantes ocupan una pequeña cruz
+            if star.brightness > 0.8 {
+                let dim = (star.color * (star.brightness * 0.4)).to_linear();
+                framebuffer.set_pixel(x + 1, y, dim);
+                framebuffer.set_pixel(x, y + 1, dim);
+                if x > 0 {
+                    framebuffer.set_pixel(x - 1, y, dim);
+                }
+                if y > 0 {
+                    framebuffer.set_pixel(x, y - 1, dim);
+                }
+            }
+        }
+    }
+}
+
+// Aproximación del color de un cuerpo negro: rojizo, blanco o azulado
+fn temperature_to_color(kelvin: f32) -> Color {
+    let warm = Color::new(255, 180, 120);
+    let white = Color::new(255, 250, 240);
+    let cool = Color::new(170, 190, 255);
+
+    if kelvin < 6500.0 {
+        warm.lerp(&white, (kelvin - 3000.0) / 3500.0)
+    } else {
+        white.lerp(&cool, (kelvin - 6500.0) / 5500.0)
+    }
+}
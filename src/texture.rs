@@ -0,0 +1,78 @@
+use image::ImageError;
+use crate::color::Color;
+
+pub struct Texture {
+    pub width: usize,
+    pub height: usize,
+    data: Vec<Color>,
+}
+
+impl Texture {
+    // Carga una imagen PNG/JPEG; el formato se detecta por la extensión
+    pub fn load(path: &str) -> Result<Self, ImageError> {
+        let image = image::open(path)?.to_rgb8();
+        let (width, height) = image.dimensions();
+
+        let data = image
+            .pixels()
+            .map(|pixel| Color::new(pixel[0], pixel[1], pixel[2]))
+            .collect();
+
+        Ok(Texture {
+            width: width as usize,
+            height: height as usize,
+            data,
+        })
+    }
+
+    // Como load, pero conserva la transparencia de la imagen en el alfa de cada
+    // color (sprites y billboards); sin canal alfa queda opaca
+    pub fn load_rgba(path: &str) -> Result<Self, ImageError> {
+        let image = image::open(path)?.to_rgba8();
+        let (width, height) = image.dimensions();
+
+        let data = image
+            .pixels()
+            .map(|pixel| Color::new_rgba(pixel[0], pixel[1], pixel[2], pixel[3]))
+            .collect();
+
+        Ok(Texture {
+            width: width as usize,
+            height: height as usize,
+            data,
+        })
+    }
+
+    // Textura generada en memoria (p. ej. un mapa procedural horneado)
+    pub fn from_colors(width: usize, height: usize, data: Vec<Color>) -> Self {
+        assert_eq!(data.len(), width * height);
+        Texture { width, height, data }
+    }
+
+    // u se repite horizontalmente, v se limita a [0, 1]
+    fn texel(&self, x: i64, y: i64) -> Color {
+        let x = x.rem_euclid(self.width as i64) as usize;
+        let y = y.clamp(0, self.height as i64 - 1) as usize;
+        self.data[y * self.width + x]
+    }
+
+    pub fn sample_nearest(&self, u: f32, v: f32) -> Color {
+        let x = (u * self.width as f32).floor() as i64;
+        let y = (v * self.height as f32).floor() as i64;
+        self.texel(x, y)
+    }
+
+    pub fn sample_bilinear(&self, u: f32, v: f32) -> Color {
+        let x = u * self.width as f32 - 0.5;
+        let y = v * self.height as f32 - 0.5;
+
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self.texel(x0, y0).lerp(&self.texel(x0 + 1, y0), fx);
+        let bottom = self.texel(x0, y0 + 1).lerp(&self.texel(x0 + 1, y0 + 1), fx);
+
+        top.lerp(&bottom, fy)
+    }
+}
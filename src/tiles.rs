@@ -0,0 +1,72 @@
+use crate::triangle::{bounding_rect, ScreenRect};
+use crate::vertex::Vertex;
+
+// Lado de los tiles en píxeles; una fila de tiles es una franja que se dibuja en paralelo
+pub const TILE_SIZE: usize = 32;
+
+// Triángulos repartidos en tiles de TILE_SIZE x TILE_SIZE: cada tile guarda los índices
+// de los que lo tocan, en el orden original (importa para las transparencias)
+pub struct TileBins {
+    width: usize,
+    height: usize,
+    tiles_x: usize,
+    bins: Vec<Vec<u32>>,
+}
+
+impl TileBins {
+    pub fn new(width: usize, height: usize) -> Self {
+        let tiles_x = width.div_ceil(TILE_SIZE);
+        let tiles_y = height.div_ceil(TILE_SIZE);
+        TileBins {
+            width,
+            height,
+            tiles_x,
+            bins: vec![Vec::new(); tiles_x * tiles_y],
+        }
+    }
+
+    // Vacía los tiles para otro dibujo, conservando la memoria ya reservada salvo
+    // que cambie el tamaño de la pantalla
+    pub fn reset(&mut self, width: usize, height: usize) {
+        if (width, height) != (self.width, self.height) {
+            *self = TileBins::new(width, height);
+        }
+        for bin in self.bins.iter_mut() {
+            bin.clear();
+        }
+    }
+
+    // Agrega cada triángulo a los tiles que cubre su caja envolvente
+    pub fn bin(&mut self, triangles: &[[Vertex; 3]]) {
+        let screen = ScreenRect::new(0, 0, self.width as i32 - 1, self.height as i32 - 1);
+
+        for (index, tri) in triangles.iter().enumerate() {
+            let rect = bounding_rect(&tri[0], &tri[1], &tri[2]).intersect(&screen);
+            if rect.is_empty() {
+                continue;
+            }
+
+            for tile_y in rect.min_y as usize / TILE_SIZE..=rect.max_y as usize / TILE_SIZE {
+                for tile_x in rect.min_x as usize / TILE_SIZE..=rect.max_x as usize / TILE_SIZE {
+                    self.bins[tile_y * self.tiles_x + tile_x].push(index as u32);
+                }
+            }
+        }
+    }
+
+    // Tiles de una fila con su rectángulo en pantalla y sus triángulos
+    pub fn row(&self, row: usize) -> impl Iterator<Item = (ScreenRect, &[u32])> {
+        let y = (row * TILE_SIZE) as i32;
+        let max_y = (y + TILE_SIZE as i32).min(self.height as i32) - 1;
+
+        self.bins[row * self.tiles_x..(row + 1) * self.tiles_x]
+            .iter()
+            .enumerate()
+            .filter(|(_, bin)| !bin.is_empty())
+            .map(move |(tile_x, bin)| {
+                let x = (tile_x * TILE_SIZE) as i32;
+                let max_x = (x + TILE_SIZE as i32).min(self.width as i32) - 1;
+                (ScreenRect::new(x, y, max_x, max_y), bin.as_slice())
+            })
+    }
+}
@@ -0,0 +1,58 @@
+use nalgebra_glm::Vec3;
+use rayon::prelude::*;
+use crate::framebuffer::HdrFramebuffer;
+use crate::gbuffer::{GBuffer, Surface};
+
+// Escalones de la luz difusa en el modo de dibujo animado
+const TOON_BANDS: f32 = 3.0;
+// El lado oscuro queda en un tono plano, no negro, para que el contorno se distinga
+const TOON_SHADOW: f32 = 0.08;
+// Un contorno aparece donde la distancia a la cámara salta más que esta fracción...
+const OUTLINE_DEPTH: f32 = 0.03;
+// ...o donde las normales de dos píxeles vecinos se separan más (coseno del ángulo)
+const OUTLINE_NORMAL: f32 = 0.5;
+const NEIGHBORS: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+// Luz difusa en escalones parejos en lugar de un degradado
+pub fn quantize(intensity: f32) -> f32 {
+    ((intensity * TOON_BANDS).round() / TOON_BANDS).max(TOON_SHADOW)
+}
+
+// Contornos a partir del G-buffer: un píxel se pinta de negro si limita con el fondo,
+// con una superficie bastante más lejana o con una que mira hacia otro lado. Solo se
+// marca el más cercano de los dos, así la línea mide un píxel y cae sobre el objeto.
+pub fn outlines(framebuffer: &mut HdrFramebuffer, gbuffer: &GBuffer, eye: &Vec3) {
+    let (width, height) = (gbuffer.width, gbuffer.height);
+    framebuffer.buffer
+        .par_iter_mut()
+        .enumerate()
+        .for_each(|(index, pixel)| {
+            let Some(surface) = &gbuffer.surfaces[index] else {
+                return;
+            };
+            let (x, y) = ((index % width) as isize, (index / width) as isize);
+            let edge = NEIGHBORS.iter().any(|&(dx, dy)| {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= width as isize || ny >= height as isize {
+                    return false;
+                }
+                match &gbuffer.surfaces[ny as usize * width + nx as usize] {
+                    Some(neighbor) => is_edge(surface, neighbor, eye),
+                    None => true,
+                }
+            });
+            if edge {
+                *pixel = Vec3::zeros();
+            }
+        });
+}
+
+fn is_edge(surface: &Surface, neighbor: &Surface, eye: &Vec3) -> bool {
+    let distance = (surface.world_position - eye).magnitude();
+    let neighbor_distance = (neighbor.world_position - eye).magnitude();
+    if neighbor_distance < distance {
+        return false;
+    }
+    neighbor_distance - distance > OUTLINE_DEPTH * distance
+        || surface.normal.normalize().dot(&neighbor.normal.normalize()) < OUTLINE_NORMAL
+}
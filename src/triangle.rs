@@ -1,15 +1,54 @@
-use nalgebra_glm::{Vec3, dot};
+use nalgebra_glm::{Vec2, Vec3};
 use crate::fragment::Fragment;
 use crate::vertex::Vertex;
 use crate::color::Color;
+use crate::lighting::MAX_LIGHTS;
+
+// Rectángulo de píxeles, con los dos extremos incluidos
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScreenRect {
+  pub min_x: i32,
+  pub min_y: i32,
+  pub max_x: i32,
+  pub max_y: i32,
+}
 
-pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, shader_selection: u32) -> Vec<Fragment> {
-  let mut fragments = Vec::new();
-  let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position);
+impl ScreenRect {
+  pub fn new(min_x: i32, min_y: i32, max_x: i32, max_y: i32) -> Self {
+    ScreenRect { min_x, min_y, max_x, max_y }
+  }
+
+  pub fn intersect(&self, other: &ScreenRect) -> ScreenRect {
+    ScreenRect {
+      min_x: self.min_x.max(other.min_x),
+      min_y: self.min_y.max(other.min_y),
+      max_x: self.max_x.min(other.max_x),
+      max_y: self.max_y.min(other.max_y),
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.min_x > self.max_x || self.min_y > self.max_y
+  }
+}
+
+// Caja envolvente del triángulo en pantalla
+pub fn bounding_rect(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> ScreenRect {
+  let (min_x, min_y, max_x, max_y) = calculate_bounding_box(
+    &v1.transformed_position,
+    &v2.transformed_position,
+    &v3.transformed_position,
+  );
+  ScreenRect::new(min_x, min_y, max_x, max_y)
+}
 
-  let (min_x, min_y, max_x, max_y) = calculate_bounding_box(&a, &b, &c);
+// Recorre los píxeles del triángulo dentro de bounds (un tile o la pantalla) y entrega
+// cada fragmento a emit, sin juntarlos en un vector
+pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, bounds: &ScreenRect, mut emit: impl FnMut(Fragment)) {
+  let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position);
 
-  let light_dir = Vec3::new(0.0, 0.0, 1.0);
+  let rect = bounding_rect(v1, v2, v3).intersect(bounds);
+  let (min_x, min_y, max_x, max_y) = (rect.min_x, rect.min_y, rect.max_x, rect.max_y);
 
   let triangle_area = edge_function(&a, &b, &c);
 
@@ -19,42 +58,64 @@ pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, shader_selection: u32) ->
 
       let (w1, w2, w3) = barycentric_coordinates(&point, &a, &b, &c, triangle_area);
 
-      if w1 >= 0.0 && w1 <= 1.0 && 
-         w2 >= 0.0 && w2 <= 1.0 &&
-         w3 >= 0.0 && w3 <= 1.0 {
+      if (0.0..=1.0).contains(&w1) &&
+         (0.0..=1.0).contains(&w2) &&
+         (0.0..=1.0).contains(&w3) {
 
         let normal = v1.transformed_normal * w1 + v2.transformed_normal * w2 + v3.transformed_normal * w3;
         let normal = normal.normalize();
 
-        let mut intensity = dot(&normal, &light_dir).max(0.0);
-
-        let base_color = Color::new(100, 100, 100);
-        let lit_color = base_color * intensity;
+        // La iluminación se calcula en la etapa de fragmentos
+        let intensity = 1.0;
 
         let depth = a.z * w1 + b.z * w2 + c.z * w3;
 
-        let vertex_position = v1.position * w1 + v2.position * w2 + v3.position * w3;
-
-        if shader_selection == 0 {
-          intensity = 0.7 * (dot(&normal, &light_dir).abs() + 0.3);
-        }
-
-        fragments.push(
-            Fragment::new(
-                x as f32,
-                y as f32,
-                lit_color,
-                depth,
-                normal,
-                intensity,
-                vertex_position,
-            )
-        );
+        // Interpolación con corrección de perspectiva para los atributos de
+        // superficie, así los patrones no se deslizan al mover la cámara
+        let (p1, p2, p3) = perspective_weights(v1, v2, v3, w1, w2, w3);
+        let model_position = v1.position * p1 + v2.position * p2 + v3.position * p3;
+        let world_position = v1.world_position * p1 + v2.world_position * p2 + v3.world_position * p3;
+        let tex_coords = v1.tex_coords * p1 + v2.tex_coords * p2 + v3.tex_coords * p3;
+        let color = Color::weighted([&v1.color, &v2.color, &v3.color], [p1, p2, p3]);
+
+        emit(Fragment {
+          position: Vec2::new(x as f32, y as f32),
+          color,
+          depth,
+          normal,
+          intensity,
+          light_visibility: [1.0; MAX_LIGHTS],
+          model_position,
+          world_position,
+          view_dir: Vec3::new(0.0, 0.0, 1.0),
+          light_dir: Vec3::zeros(),
+          tex_coords,
+        });
       }
     }
   }
+}
+
+// Pesos baricéntricos de pantalla convertidos a pesos en el espacio del modelo,
+// dividiendo por la w de clip de cada vértice
+fn perspective_weights(v1: &Vertex, v2: &Vertex, v3: &Vertex, w1: f32, w2: f32, w3: f32) -> (f32, f32, f32) {
+  let q1 = w1 / v1.clip_position.w;
+  let q2 = w2 / v2.clip_position.w;
+  let q3 = w3 / v3.clip_position.w;
+  let sum = q1 + q2 + q3;
+
+  if sum.abs() < f32::EPSILON {
+    return (w1, w2, w3);
+  }
 
-  fragments
+  (q1 / sum, q2 / sum, q3 / sum)
+}
+
+// Los triángulos frontales están en sentido antihorario en el modelo; tras el
+// viewport (que invierte y) su área con signo en pantalla queda positiva.
+pub fn is_backface(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> bool {
+  let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position);
+  edge_function(&a, &b, &c) <= 0.0
 }
 
 fn calculate_bounding_box(v1: &Vec3, v2: &Vec3, v3: &Vec3) -> (i32, i32, i32, i32) {
@@ -0,0 +1,168 @@
+// Pruebas de regresión por imagen: cada caso renderiza sin ventana una escena fija
+// (semillas de la escena, tiempo y cámara fijos) y la compara con su referencia en
+// tests/golden/<caso>.png. Pequeñas diferencias de redondeo entre máquinas se
+// toleran; un cambio en el rasterizador o en los shaders que mueva la imagen de
+// verdad hace fallar la prueba y deja la imagen obtenida y la diferencia en
+// target/tmp/golden para compararlas.
+//
+// Después de un cambio buscado en la imagen, las referencias se regeneran con
+//     GOLDEN_UPDATE=1 cargo test --release --test golden
+
+use image::RgbImage;
+use nalgebra_glm::Vec3;
+use std::path::{Path, PathBuf};
+use planet_renderer::assets::AssetManager;
+use planet_renderer::recorder;
+use planet_renderer::save::SceneSource;
+use planet_renderer::{Camera, DebugView, Renderer, RenderOptions, Scene};
+
+const WIDTH: usize = 320;
+const HEIGHT: usize = 240;
+// Se simula con el mismo paso fijo que el modo headless hasta este frame
+const STEP: f32 = 1.0 / 60.0;
+const FRAMES: u32 = 90;
+// Diferencia por canal (de 0 a 255) que todavía cuenta como el mismo color
+const CHANNEL_TOLERANCE: u8 = 2;
+// Fracción de píxeles que pueden pasarse de la tolerancia
+const MAX_DIFFERENT_PIXELS: f32 = 0.001;
+// Cuánto se amplifica la diferencia en la imagen que se guarda al fallar
+const DIFF_GAIN: u8 = 8;
+
+const SOLAR_SYSTEM: &str = "assets/scenes/solar_system.toml";
+
+fn solar_system() -> SceneSource {
+    SceneSource::File { path: SOLAR_SYSTEM.to_string() }
+}
+
+// La misma cámara con que arranca el simulador
+fn overview_camera(_: &Scene) -> Camera {
+    Camera::new(Vec3::new(0.0, 0.0, 30.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0))
+}
+
+// De cerca, con la malla de más detalle y el relieve a la vista
+fn terra_camera(scene: &Scene) -> Camera {
+    let terra = scene.planets
+        .iter()
+        .find(|planet| planet.name == "Terra")
+        .expect("la escena del sistema solar tiene a Terra");
+    let eye = terra.translation + Vec3::new(0.5, 0.8, 3.0) * terra.scale;
+    Camera::new(eye, terra.translation, Vec3::new(0.0, 1.0, 0.0))
+}
+
+fn render(source: &SceneSource, camera: fn(&Scene) -> Camera, options: &RenderOptions) -> Vec<u32> {
+    let mut assets = AssetManager::new();
+    let mut scene = source.build(&mut assets).expect("la escena de la prueba se carga");
+    for frame in 0..FRAMES {
+        scene.update(frame as f32 * STEP, if frame == 0 { 0.0 } else { STEP });
+    }
+    let sim_time = (FRAMES - 1) as f32 * STEP;
+
+    let mut renderer = Renderer::new(WIDTH, HEIGHT);
+    renderer.render(&scene, &camera(&scene), sim_time, options);
+    renderer.pixels().to_vec()
+}
+
+fn reference_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{}.png", name))
+}
+
+fn output_dir() -> PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden")
+}
+
+fn channels(pixel: u32) -> [u8; 3] {
+    [(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8]
+}
+
+// Píxeles que se pasan de la tolerancia, la mayor diferencia en un canal y la imagen
+// de las diferencias
+fn compare(pixels: &[u32], reference: &RgbImage) -> (usize, u8, RgbImage) {
+    let mut different = 0;
+    let mut largest = 0;
+    let diff = RgbImage::from_fn(WIDTH as u32, HEIGHT as u32, |x, y| {
+        let actual = channels(pixels[y as usize * WIDTH + x as usize]);
+        let expected = reference.get_pixel(x, y).0;
+        let delta: [u8; 3] = std::array::from_fn(|channel| actual[channel].abs_diff(expected[channel]));
+        let max = delta.into_iter().max().unwrap_or(0);
+        largest = largest.max(max);
+        if max > CHANNEL_TOLERANCE {
+            different += 1;
+        }
+        image::Rgb(delta.map(|value| value.saturating_mul(DIFF_GAIN)))
+    });
+    (different, largest, diff)
+}
+
+fn check(name: &str, source: SceneSource, camera: fn(&Scene) -> Camera, options: RenderOptions) {
+    let pixels = render(&source, camera, &options);
+    let reference_path = reference_path(name);
+
+    if std::env::var_os("GOLDEN_UPDATE").is_some() {
+        std::fs::create_dir_all(reference_path.parent().unwrap()).unwrap();
+        recorder::save_png(&reference_path, &pixels, WIDTH, HEIGHT).unwrap();
+        println!("referencia actualizada: {}", reference_path.display());
+        return;
+    }
+
+    let reference = match image::open(&reference_path) {
+        Ok(reference) => reference.to_rgb8(),
+        Err(err) => panic!(
+            "no se pudo leer la referencia {} ({}); se crea con GOLDEN_UPDATE=1",
+            reference_path.display(),
+            err
+        ),
+    };
+    assert_eq!(
+        (reference.width() as usize, reference.height() as usize),
+        (WIDTH, HEIGHT),
+        "la referencia {} tiene otro tamaño",
+        reference_path.display()
+    );
+
+    let (different, largest, diff) = compare(&pixels, &reference);
+    let allowed = (MAX_DIFFERENT_PIXELS * (WIDTH * HEIGHT) as f32) as usize;
+    if different > allowed {
+        let dir = output_dir();
+        std::fs::create_dir_all(&dir).unwrap();
+        let actual_path = dir.join(format!("{}.png", name));
+        let diff_path = dir.join(format!("{}_diff.png", name));
+        recorder::save_png(&actual_path, &pixels, WIDTH, HEIGHT).unwrap();
+        diff.save(&diff_path).unwrap();
+        panic!(
+            "{}: {} píxeles distintos de la referencia (se toleran {}), diferencia máxima {}; \
+             imagen obtenida en {} y diferencia en {}",
+            name,
+            different,
+            allowed,
+            largest,
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+#[test]
+fn solar_system_overview() {
+    check("solar_system", solar_system(), overview_camera, RenderOptions::default());
+}
+
+#[test]
+fn solar_system_terra_closeup() {
+    check("terra_closeup", solar_system(), terra_camera, RenderOptions::default());
+}
+
+#[test]
+fn generated_system() {
+    check("generated_7", SceneSource::Generated { seed: 7 }, overview_camera, RenderOptions::default());
+}
+
+#[test]
+fn toon_style() {
+    check("toon", solar_system(), overview_camera, RenderOptions { toon: true, ..RenderOptions::default() });
+}
+
+// Solo la geometría: el rasterizador sin shaders ni efectos
+#[test]
+fn normals_view() {
+    check("normals", solar_system(), terra_camera, RenderOptions { debug_view: DebugView::Normals, ..RenderOptions::default() });
+}